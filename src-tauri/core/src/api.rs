@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use crate::error::AppError;
+use crate::files::MAX_FILE_SIZE;
+
+// Relative to the app data directory; clients read the token from here
+pub const TOKEN_FILE: &str = "prompt-copy/api-token";
+pub const DEFAULT_PORT: u16 = 47821;
+const RPC_PATH: &str = "/rpc";
+// Request line and headers together
+const MAX_HEAD_BYTES: u64 = 16 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+// Bodies grow past this as they arrive instead of being allocated up front
+const BODY_CAPACITY: u64 = 64 * 1024;
+// Connections served at once; more are turned away until one finishes
+const MAX_CONNECTIONS: usize = 16;
+// How long the UI gets to answer; copying may wait on a confirmation dialog
+const UI_TIMEOUT: Duration = Duration::from_secs(60);
+
+// JSON-RPC 2.0 error codes, and ours in the range it leaves to applications
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+// `data` holds the AppError as the frontend gets it
+pub const APP_ERROR: i64 = -32000;
+// The UI did not answer, or answered with an error
+pub const UI_ERROR: i64 = -32001;
+
+#[derive(Serialize, Debug)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> RpcError {
+        RpcError { code, message: message.into(), data: None }
+    }
+
+    pub fn method_not_found(method: &str) -> RpcError {
+        RpcError::new(METHOD_NOT_FOUND, format!("Unknown method {}", method))
+    }
+}
+
+impl From<AppError> for RpcError {
+    fn from(e: AppError) -> Self {
+        RpcError { code: APP_ERROR, message: e.to_string(), data: serde_json::to_value(&e).ok() }
+    }
+}
+
+// Decodes a method's params, reporting a mismatch as invalid params
+pub fn params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    // Methods without params may be called with none at all
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+pub fn to_result<T: Serialize>(value: T) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| RpcError::from(AppError::from(e.to_string())))
+}
+
+// Answers one method call; runs on the connection's thread
+pub type Handler = dyn Fn(&str, Value) -> Result<Value, RpcError> + Send + Sync;
+
+// The token clients send as `Authorization: Bearer <token>`, created on first use
+pub fn load_or_create_token(path: &Path) -> Result<String, AppError> {
+    if let Ok(token) = fs::read_to_string(path) {
+        if !token.trim().is_empty() {
+            return Ok(token.trim().to_string());
+        }
+    }
+    let token = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| AppError::io(parent, e))?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+        .map_err(|e| AppError::io(path, e))?;
+    // Other users on the machine must not be able to read it, so access is
+    // narrowed before the token is written
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600)).map_err(|e| AppError::io(path, e))?;
+    }
+    file.write_all(token.as_bytes()).map_err(|e| AppError::io(path, e))?;
+    Ok(token)
+}
+
+// Compares in time independent of where the first difference is
+fn token_matches(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    jsonrpc: String,
+    // Absent for notifications, which get no response
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": error })
+}
+
+// Runs one JSON-RPC request body; None when there is nothing to send back
+pub fn dispatch(body: &[u8], handler: &Handler) -> Option<Value> {
+    let value: Value = match serde_json::from_slice(body) {
+        Ok(value) => value,
+        Err(e) => return Some(error_response(Value::Null, RpcError::new(PARSE_ERROR, e.to_string()))),
+    };
+    let request: RpcRequest = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(e) => return Some(error_response(Value::Null, RpcError::new(INVALID_REQUEST, e.to_string()))),
+    };
+    if request.jsonrpc != "2.0" {
+        let id = request.id.unwrap_or(Value::Null);
+        return Some(error_response(id, RpcError::new(INVALID_REQUEST, "Only JSON-RPC 2.0 is supported")));
+    }
+
+    let result = handler(&request.method, request.params);
+    let id = request.id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => error_response(id, error),
+    })
+}
+
+struct HttpResponse {
+    status: u16,
+    body: Option<Value>,
+}
+
+impl HttpResponse {
+    fn error(status: u16, message: &str) -> HttpResponse {
+        HttpResponse { status, body: Some(json!({ "error": message })) }
+    }
+}
+
+// Reads one request's body. Everything that can be decided from the head is
+// checked first, so unauthorized clients can't make us buffer a body.
+fn read_request(stream: &TcpStream, token: &str) -> Result<Vec<u8>, HttpResponse> {
+    let malformed = || HttpResponse::error(400, "Malformed request");
+    let mut reader = BufReader::new(stream);
+    let mut head_bytes = 0;
+    let mut read_line = |reader: &mut BufReader<&TcpStream>| {
+        let mut line = String::new();
+        let read = reader.by_ref()
+            .take(MAX_HEAD_BYTES - head_bytes)
+            .read_line(&mut line)
+            .map_err(|_| malformed())?;
+        head_bytes += read as u64;
+        // Cut off by the connection closing or by the size limit
+        if !line.ends_with('\n') {
+            return Err(malformed());
+        }
+        Ok(line.trim_end().to_string())
+    };
+
+    let request_line = read_line(&mut reader)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(malformed());
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    // Names lowercased
+    let mut headers = HashMap::new();
+    loop {
+        let line = read_line(&mut reader)?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    check_head(&method, &path, &headers, token)?;
+
+    let length: u64 = headers.get("content-length").and_then(|l| l.parse().ok()).unwrap_or(0);
+    if length > MAX_FILE_SIZE {
+        return Err(HttpResponse::error(413, "Request too large"));
+    }
+    let mut body = Vec::with_capacity(length.min(BODY_CAPACITY) as usize);
+    reader.take(length).read_to_end(&mut body).map_err(|_| malformed())?;
+    if body.len() as u64 != length {
+        return Err(malformed());
+    }
+    Ok(body)
+}
+
+fn check_head(method: &str, path: &str, headers: &HashMap<String, String>, token: &str) -> Result<(), HttpResponse> {
+    // Browsers send an Origin with cross-site requests; editors and scripts don't,
+    // so a web page can never reach the API even if it learned the port
+    if headers.contains_key("origin") {
+        return Err(HttpResponse::error(403, "Browser requests are not accepted"));
+    }
+    if path != RPC_PATH {
+        return Err(HttpResponse::error(404, "Not found"));
+    }
+    if method != "POST" {
+        return Err(HttpResponse::error(405, "Use POST"));
+    }
+    let authorized = headers.get("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| token_matches(given.trim(), token));
+    if !authorized {
+        return Err(HttpResponse::error(401, "Missing or wrong token"));
+    }
+    Ok(())
+}
+
+// Runs a request that passed the checks and was read in full
+fn handle(body: &[u8], handler: &Handler) -> HttpResponse {
+    match dispatch(body, handler) {
+        Some(body) => HttpResponse { status: 200, body: Some(body) },
+        None => HttpResponse { status: 204, body: None },
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        503 => "Service Unavailable",
+        _ => "Error",
+    }
+}
+
+fn write_response(mut stream: &TcpStream, response: HttpResponse) -> std::io::Result<()> {
+    let body = response.body.map(|body| body.to_string()).unwrap_or_default();
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nConnection: close\r\nContent-Length: {}\r\n",
+        response.status, reason(response.status), body.len()
+    );
+    if !body.is_empty() {
+        head.push_str("Content-Type: application/json\r\n");
+    }
+    if response.status == 401 {
+        head.push_str("WWW-Authenticate: Bearer\r\n");
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body.as_bytes())?;
+    stream.flush()
+}
+
+// One request per connection
+fn serve(stream: TcpStream, token: &str, handler: &Handler) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let response = match read_request(&stream, token) {
+        Ok(body) => handle(&body, handler),
+        Err(response) => response,
+    };
+    let _ = write_response(&stream, response);
+}
+
+// Counts a connection being served until dropped
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    fn take(open: &Arc<AtomicUsize>) -> Option<ConnectionSlot> {
+        open.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < MAX_CONNECTIONS).then_some(n + 1))
+            .ok()
+            .map(|_| ConnectionSlot(open.clone()))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// Listens on 127.0.0.1 until dropped
+pub struct Server {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Server {
+    // Port 0 picks a free port, see `port`
+    pub fn start(port: u16, token: String, handler: Arc<Handler>) -> Result<Server, AppError> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+            .map_err(|e| AppError::from(e.to_string()).context(&format!("Could not listen on port {}", port)))?;
+        let addr = listener.local_addr().map_err(|e| e.to_string())?;
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let stopping = stop.clone();
+        let token: Arc<str> = token.into();
+        let open = Arc::new(AtomicUsize::new(0));
+        let thread = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopping.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let Some(slot) = ConnectionSlot::take(&open) else {
+                    let _ = write_response(&stream, HttpResponse::error(503, "Too many connections"));
+                    continue;
+                };
+                let (token, handler) = (token.clone(), handler.clone());
+                thread::spawn(move || {
+                    serve(stream, &token, &*handler);
+                    drop(slot);
+                });
+            }
+        });
+        Ok(Server { addr, stop, thread: Some(thread) })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // Wakes the accept loop so it sees the flag; joining frees the port
+        // before a restarted server binds it again
+        let _ = TcpStream::connect(self.addr);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// Sent to the UI for methods about tabs, which only the UI knows about
+#[derive(Serialize, Clone, Debug)]
+pub struct UiRequest {
+    pub id: u64,
+    pub method: String,
+    pub params: Value,
+}
+
+// Requests forwarded to the UI, each waiting until the UI answers through `resolve`
+#[derive(Default)]
+pub struct UiBridge {
+    next_id: AtomicU64,
+    pending: Mutex<HashMap<u64, mpsc::Sender<Result<Value, String>>>>,
+}
+
+impl UiBridge {
+    // Hands the request to `send` and blocks until it is answered or times out
+    pub fn call(
+        &self,
+        method: &str,
+        params: Value,
+        send: impl FnOnce(UiRequest) -> Result<(), AppError>
+    ) -> Result<Value, RpcError> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = mpsc::channel();
+        self.pending.lock().unwrap_or_else(|e| e.into_inner()).insert(id, sender);
+
+        let result = send(UiRequest { id, method: method.to_string(), params })
+            .map_err(RpcError::from)
+            .and_then(|()| {
+                receiver.recv_timeout(UI_TIMEOUT)
+                    .map_err(|_| RpcError::new(UI_ERROR, "The app did not answer in time"))
+            });
+        self.pending.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+        result?.map_err(|message| RpcError::new(UI_ERROR, message))
+    }
+
+    // Delivers the UI's answer; false when nobody is waiting for it any more
+    pub fn resolve(&self, id: u64, result: Result<Value, String>) -> bool {
+        let sender = self.pending.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+        sender.is_some_and(|sender| sender.send(result).is_ok())
+    }
+}
@@ -0,0 +1,567 @@
+// Builds the same prompts as the app from scripts and terminals, reading the
+// app's settings, workspaces and history
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use clap::{Args, Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use tauriprompt_lib::error::AppError;
+use tauriprompt_lib::files::{self, FileInfo, Walk, MAX_FILE_SIZE};
+use tauriprompt_lib::git::{self, DiffSource, FileStatus};
+use tauriprompt_lib::guard::{GuardAction, GuardOptions};
+use tauriprompt_lib::history::{self, HISTORY_DIR};
+use tauriprompt_lib::prompt::{self, GitHistoryOptions, InclusionMode, PromptFormat, PromptRequest};
+use tauriprompt_lib::redact::RedactionOptions;
+use tauriprompt_lib::settings::{self, Settings, SettingsStore};
+use tauriprompt_lib::template;
+use tauriprompt_lib::tree::TreeOptions;
+use tauriprompt_lib::workspace::{self, Workspace, WORKSPACES_DIR};
+
+// Characters of the prompt shown per entry in history listings
+const PROMPT_PREVIEW: usize = 60;
+
+#[derive(Parser)]
+#[command(name = "promptcopy", version, about = "Build Prompt Copy prompts without the app")]
+struct Cli {
+    #[arg(long, global = true, value_name = "DIR", help = "Use this data directory instead of the app's")]
+    data_dir: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Build a prompt from files and print it or copy it to the clipboard")]
+    Copy(CopyArgs),
+    #[command(about = "List the files below a folder")]
+    List(ListArgs),
+    #[command(subcommand, about = "Browse the prompt history")]
+    History(HistoryCommand),
+    #[command(subcommand, about = "Fill in prompt templates")]
+    Template(TemplateCommand),
+    // The detached process that keeps serving a copy, see write_clipboard
+    #[command(hide = true)]
+    ServeClipboard,
+}
+
+#[derive(Subcommand)]
+enum HistoryCommand {
+    #[command(about = "List entries, newest first")]
+    List {
+        #[arg(long, short = 'n', default_value_t = 20, help = "Show at most this many entries; 0 for all")]
+        limit: usize,
+        #[arg(long, help = "Print the entries as JSON")]
+        json: bool,
+    },
+    #[command(about = "Show one entry")]
+    Show {
+        #[arg(help = "Entry id as printed by `history list`")]
+        id: String,
+        #[arg(long, help = "Print the whole entry, file snapshots included, as JSON")]
+        json: bool,
+    },
+    #[command(about = "Find entries whose prompt or files mention a text")]
+    Search {
+        query: String,
+        #[arg(long, help = "Also search the recorded file contents")]
+        contents: bool,
+        #[arg(long, short = 'n', default_value_t = 20, help = "Show at most this many entries; 0 for all")]
+        limit: usize,
+        #[arg(long, help = "Print the entries as JSON")]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TemplateCommand {
+    #[command(about = "Fill in a template's {{placeholders}} and {{tree}}")]
+    Render {
+        #[arg(help = "Template file, or - for stdin; defaults to the workspace's template")]
+        file: Option<PathBuf>,
+        #[command(flatten)]
+        context: Context,
+        #[command(flatten)]
+        output: Output,
+    },
+}
+
+// Where a prompt comes from: a saved workspace, a folder, or both
+#[derive(Args)]
+struct Context {
+    #[arg(long, short, help = "Saved workspace, by name or id")]
+    workspace: Option<String>,
+    #[arg(long, short, help = "Folder the prompt is about; defaults to the workspace's root, then the current directory")]
+    root: Option<PathBuf>,
+    #[arg(long = "set", value_name = "NAME=VALUE", value_parser = parse_var, help = "Value for a {{NAME}} placeholder")]
+    vars: Vec<(String, String)>,
+}
+
+#[derive(Args)]
+struct Output {
+    #[arg(long, short, help = "Copy to the clipboard instead of printing")]
+    clipboard: bool,
+}
+
+#[derive(Args)]
+struct CopyArgs {
+    #[arg(help = "Files relative to the root; `path:10-20` and `path#symbol` include part of a file")]
+    files: Vec<String>,
+    #[command(flatten)]
+    context: Context,
+    #[arg(long, short, conflicts_with = "prompt_file", help = "Prompt text; defaults to the workspace's template")]
+    prompt: Option<String>,
+    #[arg(long, value_name = "FILE", help = "Read the prompt text from a file, or - for stdin")]
+    prompt_file: Option<PathBuf>,
+    #[arg(long, value_name = "FILE", help = "Include only the declarations of this file")]
+    outline: Vec<String>,
+    #[arg(long, value_name = "SOURCE", help = "Add a git diff: staged, unstaged or a branch to compare against")]
+    diff: Option<String>,
+    #[arg(long, value_name = "N", help = "Add the last N commits touching the files")]
+    commits: Option<usize>,
+    #[arg(long, help = "Add git blame for the files")]
+    blame: bool,
+    #[arg(long, help = "Add the project tree")]
+    tree: bool,
+    #[arg(long, help = "Leave secrets in place instead of redacting them")]
+    no_redact: bool,
+    #[arg(long, value_name = "FILE", help = "Include a file the sensitive-file rules would hold back")]
+    allow: Vec<String>,
+    #[arg(long, help = "End with the \"send full script with fix\" request")]
+    script_fix: bool,
+    #[arg(long, value_parser = parse_format, help = "Lay out files as plain, markdown or xml; defaults to the app's setting")]
+    format: Option<PromptFormat>,
+    #[arg(long, requires = "clipboard", help = "Also copy a highlighted HTML version; on by default when set in the app")]
+    rich: bool,
+    #[command(flatten)]
+    output: Output,
+}
+
+#[derive(Args)]
+struct ListArgs {
+    #[arg(help = "Folder to list; defaults to the current directory")]
+    root: Option<PathBuf>,
+    #[arg(long, short, default_value_t = files::MAX_DEPTH, help = "How many folder levels to descend")]
+    depth: u32,
+    #[arg(long, help = "Include git-ignored files")]
+    ignored: bool,
+    #[arg(long, help = "Print the listing as JSON")]
+    json: bool,
+}
+
+// What the detached clipboard process is handed on stdin
+#[derive(Serialize, Deserialize, Clone)]
+struct ClipboardContent {
+    text: String,
+    html: Option<String>,
+}
+
+fn parse_format(name: &str) -> Result<PromptFormat, String> {
+    serde_json::from_value(serde_json::Value::from(name))
+        .map_err(|_| format!("expected plain, markdown or xml, got {:?}", name))
+}
+
+fn parse_var(spec: &str) -> Result<(String, String), String> {
+    spec.split_once('=')
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected NAME=VALUE, got {:?}", spec))
+}
+
+// The app resolves its data directory with tauri's default config, which has
+// no bundle identifier, so it is the platform data directory itself
+fn app_dir(data_dir: &Option<PathBuf>) -> Result<PathBuf, AppError> {
+    data_dir.clone()
+        .or_else(dirs_next::data_dir)
+        .ok_or_else(|| AppError::from("Could not get app directory"))
+}
+
+fn current_dir() -> Result<PathBuf, AppError> {
+    std::env::current_dir().map_err(|e| AppError::io(Path::new("."), e))
+}
+
+fn load_settings(app_dir: &Path) -> Result<Settings, AppError> {
+    SettingsStore::default().load(&app_dir.join(settings::SETTINGS_DIR))
+}
+
+fn find_workspace(app_dir: &Path, name: &str) -> Result<Workspace, AppError> {
+    workspace::list(&app_dir.join(WORKSPACES_DIR))?
+        .into_iter()
+        .find(|w| w.id == name || w.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| AppError::from(format!("No saved workspace named {}", name)))
+}
+
+fn read_source(path: &Path) -> Result<String, AppError> {
+    if path == Path::new("-") {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text).map_err(|e| AppError::io(path, e))?;
+        return Ok(text);
+    }
+    fs::read_to_string(path).map_err(|e| AppError::io(path, e))
+}
+
+// Selection specs are relative to the root, like the paths shown in prompts
+fn resolve(root: &Path, spec: &str) -> String {
+    root.join(spec).to_string_lossy().into_owned()
+}
+
+fn diff_source(source: &str) -> DiffSource {
+    match source {
+        "staged" => DiffSource::Staged,
+        "unstaged" => DiffSource::Unstaged,
+        branch => DiffSource::Branch { branch: branch.to_string() },
+    }
+}
+
+// The workspace and root a command works on, with the root made absolute
+fn resolve_context(app_dir: &Path, context: &Context) -> Result<(Option<Workspace>, PathBuf), AppError> {
+    let workspace = context.workspace.as_deref().map(|name| find_workspace(app_dir, name)).transpose()?;
+    let root = match (&context.root, &workspace) {
+        (Some(root), _) => root.clone(),
+        (None, Some(workspace)) => PathBuf::from(&workspace.root),
+        (None, None) => current_dir()?,
+    };
+    Ok((workspace, std::path::absolute(&root).map_err(|e| AppError::io(&root, e))?))
+}
+
+fn copy(app_dir: &Path, args: CopyArgs) -> Result<String, AppError> {
+    let settings = load_settings(app_dir)?;
+    let (workspace, root) = resolve_context(app_dir, &args.context)?;
+
+    let template = match (&args.prompt, &args.prompt_file, &workspace) {
+        (Some(prompt), _, _) => prompt.clone(),
+        (None, Some(file), _) => read_source(file)?,
+        (None, None, Some(workspace)) => workspace.default_template.clone(),
+        (None, None, None) => String::new(),
+    };
+    let vars: HashMap<String, String> = args.context.vars.into_iter().collect();
+
+    // Files named on the command line replace the workspace's selection
+    let (mut files, outlined): (Vec<String>, Vec<String>) = match &workspace {
+        Some(workspace) if args.files.is_empty() && args.outline.is_empty() => {
+            (workspace.checked_files.clone(), workspace.outline_files.clone())
+        }
+        _ => (
+            args.files.iter().map(|f| resolve(&root, f)).collect(),
+            args.outline.iter().map(|f| resolve(&root, f)).collect(),
+        ),
+    };
+    for file in &outlined {
+        if !files.contains(file) {
+            files.push(file.clone());
+        }
+    }
+
+    let request = PromptRequest {
+        root: Some(root.to_string_lossy().into_owned()),
+        prompt: template::render(&template, &vars)?,
+        files,
+        modes: outlined.into_iter().map(|f| (f, InclusionMode::Outline)).collect(),
+        add_script_fix: args.script_fix,
+        git_diff: args.diff.as_deref().map(diff_source),
+        git_history: (args.commits.is_some() || args.blame).then(|| GitHistoryOptions {
+            commit_limit: args.commits.unwrap_or(0),
+            blame: args.blame,
+        }),
+        tree: args.tree.then(|| TreeOptions { mark_included: true, ..TreeOptions::default() }),
+        redaction: RedactionOptions {
+            enabled: !args.no_redact,
+            patterns: settings.redaction_patterns.clone(),
+            ..RedactionOptions::default()
+        },
+        guard: GuardOptions {
+            rules: settings.guard_rules.clone(),
+            confirmed: args.allow.iter().map(|f| resolve(&root, f)).collect(),
+            ..GuardOptions::default()
+        },
+        rich_theme: (args.output.clipboard && (args.rich || settings.rich_copy)).then(|| settings.theme.clone()),
+        format: Some(args.format.unwrap_or(settings.default_format)),
+        ignore_rules: settings.ignore_rules.clone(),
+    };
+    let built = prompt::build(&request, MAX_FILE_SIZE)?;
+
+    if built.needs_confirmation {
+        let denied: Vec<&str> = built.guard.iter()
+            .filter(|f| f.action == GuardAction::Deny && !f.confirmed)
+            .map(|f| f.path.as_str())
+            .collect();
+        return Err(AppError::from(format!(
+            "These files may contain secrets: {}. Pass --allow for each to include them anyway.",
+            denied.join(", ")
+        )));
+    }
+    for finding in built.guard.iter().filter(|f| f.action == GuardAction::Warn) {
+        eprintln!("warning: {} matches the sensitive-file rule {}", finding.path, finding.pattern);
+    }
+    for file in &built.unreadable_files {
+        eprintln!("warning: could not read {}", file);
+    }
+    for file in &built.redactions {
+        eprintln!("redacted {} secret(s) in {}", file.redactions.len(), file.path);
+    }
+    if let Some(budget) = settings.token_budget.filter(|&budget| built.estimated_tokens > budget) {
+        eprintln!("warning: about {} tokens, over the budget of {}", built.estimated_tokens, budget);
+    }
+
+    emit(built.text, built.html, args.output.clipboard)
+}
+
+fn status_marker(status: Option<FileStatus>) -> char {
+    match status {
+        None => ' ',
+        Some(FileStatus::Ignored) => '!',
+        Some(FileStatus::Untracked) => '?',
+        Some(FileStatus::Added) => 'A',
+        Some(FileStatus::Modified) => 'M',
+        Some(FileStatus::Conflicted) => 'U',
+    }
+}
+
+fn write_listing(out: &mut String, entries: &[FileInfo], root: &Path) {
+    for entry in entries {
+        match &entry.children {
+            Some(children) => write_listing(out, children, root),
+            None if !entry.is_directory => {
+                let path = Path::new(&entry.path);
+                let relative = path.strip_prefix(root).unwrap_or(path);
+                let _ = writeln!(out, "{} {}", status_marker(entry.git_status), relative.display());
+            }
+            None => {}
+        }
+    }
+}
+
+fn list(app_dir: &Path, args: ListArgs) -> Result<String, AppError> {
+    let root = match args.root {
+        Some(root) => root,
+        None => current_dir()?,
+    };
+    let root = std::path::absolute(&root).map_err(|e| AppError::io(&root, e))?;
+    if !root.is_dir() {
+        return Err(AppError::NotFound { path: root.to_string_lossy().into_owned() });
+    }
+
+    let settings = load_settings(app_dir)?;
+    let statuses = git::StatusMap::load(&root);
+    let walk = Walk { exclude: &settings.ignore_rules, skip_ignored: !args.ignored, max_entries: None };
+    let entries = files::read_dir_filtered(&root, args.depth, 0, &statuses, &walk)?;
+
+    if args.json {
+        return to_json(&entries);
+    }
+    let mut out = String::new();
+    write_listing(&mut out, &entries, &root);
+    Ok(out)
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, AppError> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    Ok(format!("{}\n", json))
+}
+
+// First line of the prompt, shortened to fit a listing
+fn preview(prompt: &str) -> String {
+    let line = prompt.lines().find(|l| !l.trim().is_empty()).unwrap_or_default().trim();
+    match line.char_indices().nth(PROMPT_PREVIEW) {
+        Some((end, _)) => format!("{}...", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+fn format_entries(entries: &[history::StoredEntry], json: bool) -> Result<String, AppError> {
+    if json {
+        return to_json(&entries);
+    }
+    let mut out = String::new();
+    for stored in entries {
+        let entry = &stored.entry;
+        let _ = writeln!(
+            out,
+            "{}  {}  {} file(s){}  {}",
+            stored.id,
+            entry.timestamp,
+            entry.files.len(),
+            if entry.success { ", worked" } else { "" },
+            preview(&entry.prompt)
+        );
+    }
+    Ok(out)
+}
+
+fn limited(mut entries: Vec<history::StoredEntry>, limit: usize) -> Vec<history::StoredEntry> {
+    if limit > 0 {
+        entries.truncate(limit);
+    }
+    entries
+}
+
+fn history(app_dir: &Path, command: HistoryCommand) -> Result<String, AppError> {
+    let history_dir = app_dir.join(HISTORY_DIR);
+    match command {
+        HistoryCommand::List { limit, json } => {
+            format_entries(&limited(history::entries(&history_dir)?, limit), json)
+        }
+        HistoryCommand::Search { query, contents, limit, json } => {
+            let found: Vec<_> = history::entries(&history_dir)?
+                .into_iter()
+                .filter(|stored| stored.entry.matches(&query, contents))
+                .collect();
+            format_entries(&limited(found, limit), json)
+        }
+        HistoryCommand::Show { id, json } => {
+            let path = history::entry_path(&history_dir, &id)?;
+            let entry = history::load_entry(&path)?;
+            if json {
+                return to_json(&history::StoredEntry { id, entry });
+            }
+            let mut out = format!("Entry: {}\nTime: {}\n", id, entry.timestamp);
+            if let Some(root) = history::entry_root(&entry) {
+                let _ = writeln!(out, "Root: {}", root.display());
+            }
+            let _ = writeln!(out, "Worked: {}\nFiles:", if entry.success { "yes" } else { "no" });
+            for file in &entry.files {
+                let _ = writeln!(out, "  {}", file.path);
+            }
+            let _ = writeln!(out, "\n{}", entry.prompt);
+            Ok(out)
+        }
+    }
+}
+
+fn template(app_dir: &Path, command: TemplateCommand) -> Result<String, AppError> {
+    let TemplateCommand::Render { file, context, output } = command;
+    let (workspace, root) = resolve_context(app_dir, &context)?;
+    let source = match (&file, &workspace) {
+        (Some(file), _) => read_source(file)?,
+        (None, Some(workspace)) => workspace.default_template.clone(),
+        (None, None) => return Err(AppError::from("Give a template file or a --workspace")),
+    };
+
+    let vars: HashMap<String, String> = context.vars.into_iter().collect();
+    let mut rendered = template::render(&source, &vars)?;
+    if rendered.contains(prompt::TREE_PLACEHOLDER) {
+        let tree = prompt::tree_text(Some(&root.to_string_lossy()), &TreeOptions::default(), &[])?;
+        rendered = rendered.replace(prompt::TREE_PLACEHOLDER, &tree);
+    }
+    emit(rendered, None, output.clipboard)
+}
+
+// What to print: the text itself, or nothing once it is on the clipboard
+fn emit(text: String, html: Option<String>, clipboard: bool) -> Result<String, AppError> {
+    if !clipboard {
+        return Ok(format!("{}\n", text));
+    }
+    let length = text.len();
+    write_clipboard(ClipboardContent { text, html })?;
+    eprintln!("Copied {} bytes to the clipboard", length);
+    Ok(String::new())
+}
+
+fn set_clipboard(set: arboard::Set, content: ClipboardContent) -> Result<(), arboard::Error> {
+    match content.html {
+        Some(html) => set.html(html, Some(content.text)),
+        None => set.text(content.text),
+    }
+}
+
+// X11 and Wayland serve the clipboard from the process that set it, so the copy
+// is handed to a detached `serve-clipboard` that keeps it available after this
+// process exits, until something else is copied
+#[cfg(target_os = "linux")]
+fn write_clipboard(content: ClipboardContent) -> Result<(), AppError> {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    let clipboard_error = |e: io::Error| AppError::from(e.to_string()).context("clipboard");
+    let exe = std::env::current_exe().map_err(clipboard_error)?;
+    let mut child = Command::new(exe)
+        .arg("serve-clipboard")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(clipboard_error)?;
+
+    let payload = serde_json::to_vec(&content).map_err(|e| e.to_string())?;
+    let mut stdin = child.stdin.take().ok_or("Could not start the clipboard process")?;
+    stdin.write_all(&payload).map_err(clipboard_error)?;
+    drop(stdin);
+
+    // The child reports once it could open the clipboard, or why it couldn't
+    let mut status = String::new();
+    let stdout = child.stdout.take().ok_or("Could not start the clipboard process")?;
+    BufReader::new(stdout).read_line(&mut status).map_err(clipboard_error)?;
+    match status.trim_end() {
+        "ok" => Ok(()),
+        "" => Err(AppError::from("The clipboard process exited").context("clipboard")),
+        message => Err(AppError::from(message).context("clipboard")),
+    }
+}
+
+// Reads the content and sets it once without waiting, so a copy that can't
+// be made fails here rather than after "ok" was reported
+#[cfg(target_os = "linux")]
+fn take_clipboard() -> Result<(arboard::Clipboard, ClipboardContent), String> {
+    let mut payload = String::new();
+    io::stdin().read_to_string(&mut payload).map_err(|e| e.to_string())?;
+    let content: ClipboardContent = serde_json::from_str(&payload).map_err(|e| e.to_string())?;
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+    set_clipboard(clipboard.set(), content.clone()).map_err(|e| e.to_string())?;
+    Ok((clipboard, content))
+}
+
+#[cfg(target_os = "linux")]
+fn serve_clipboard() -> Result<String, AppError> {
+    use arboard::SetExtLinux;
+
+    // stderr is discarded by the parent, which reads a single status line from stdout
+    let (mut clipboard, content) = match take_clipboard() {
+        Ok(taken) => taken,
+        Err(e) => {
+            println!("{}", e.replace('\n', " "));
+            return Ok(String::new());
+        }
+    };
+    println!("ok");
+    // Set again, this time blocking until another program takes over the clipboard
+    set_clipboard(clipboard.set().wait(), content).map_err(|e| AppError::from(e.to_string()).context("clipboard"))?;
+    Ok(String::new())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn write_clipboard(content: ClipboardContent) -> Result<(), AppError> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| AppError::from(e.to_string()).context("clipboard"))?;
+    set_clipboard(clipboard.set(), content).map_err(|e| AppError::from(e.to_string()).context("clipboard"))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn serve_clipboard() -> Result<String, AppError> {
+    Err(AppError::from("Only needed on Linux"))
+}
+
+// Output of the command, printed by main
+fn run(cli: Cli) -> Result<String, AppError> {
+    let app_dir = app_dir(&cli.data_dir)?;
+    match cli.command {
+        Command::Copy(args) => copy(&app_dir, args),
+        Command::List(args) => list(&app_dir, args),
+        Command::History(command) => history(&app_dir, command),
+        Command::Template(command) => template(&app_dir, command),
+        Command::ServeClipboard => serve_clipboard(),
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(out) => {
+            // A reader that stopped early, like `head`, is not an error
+            let _ = io::stdout().lock().write_all(out.as_bytes());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("promptcopy: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
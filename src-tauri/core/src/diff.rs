@@ -0,0 +1,166 @@
+use serde::Serialize;
+use similar::{Algorithm, ChangeTag, DiffOp, TextDiff};
+
+pub const DEFAULT_CONTEXT: usize = 3;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum LineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+// Part of a line; `changed` marks the words that differ from the paired line
+#[derive(Serialize)]
+pub struct DiffSpan {
+    pub text: String,
+    pub changed: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub kind: LineKind,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    pub content: String,
+    // Only present when word-level refinement was requested
+    pub spans: Option<Vec<DiffSpan>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    pub header: String,
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDiff {
+    pub old_label: String,
+    pub new_label: String,
+    pub identical: bool,
+    pub additions: usize,
+    pub deletions: usize,
+    pub hunks: Vec<DiffHunk>,
+    pub unified: String,
+}
+
+fn line_kind(tag: ChangeTag) -> LineKind {
+    match tag {
+        ChangeTag::Equal => LineKind::Context,
+        ChangeTag::Insert => LineKind::Added,
+        ChangeTag::Delete => LineKind::Removed,
+    }
+}
+
+fn strip_newline(line: &str) -> String {
+    line.trim_end_matches(['\n', '\r']).to_string()
+}
+
+fn hunk_header(old_start: usize, old_lines: usize, new_start: usize, new_lines: usize) -> String {
+    format!("@@ -{},{} +{},{} @@", old_start, old_lines, new_start, new_lines)
+}
+
+fn build_hunk<'a>(diff: &'a TextDiff<'a, 'a, 'a, str>, ops: &[DiffOp], word_level: bool) -> DiffHunk {
+    let first = ops.first().expect("grouped ops are never empty");
+    let last = ops.last().expect("grouped ops are never empty");
+    let old_range = first.old_range().start..last.old_range().end;
+    let new_range = first.new_range().start..last.new_range().end;
+
+    let mut lines = Vec::new();
+    for op in ops {
+        if word_level {
+            for change in diff.iter_inline_changes(op) {
+                let spans: Vec<DiffSpan> = change
+                    .iter_strings_lossy()
+                    .map(|(changed, text)| DiffSpan { text: strip_newline(&text), changed })
+                    .filter(|span| !span.text.is_empty())
+                    .collect();
+                let content = spans.iter().map(|s| s.text.as_str()).collect();
+
+                lines.push(DiffLine {
+                    kind: line_kind(change.tag()),
+                    old_line: change.old_index().map(|i| i + 1),
+                    new_line: change.new_index().map(|i| i + 1),
+                    content,
+                    spans: Some(spans),
+                });
+            }
+        } else {
+            for change in diff.iter_changes(op) {
+                lines.push(DiffLine {
+                    kind: line_kind(change.tag()),
+                    old_line: change.old_index().map(|i| i + 1),
+                    new_line: change.new_index().map(|i| i + 1),
+                    content: strip_newline(change.value()),
+                    spans: None,
+                });
+            }
+        }
+    }
+
+    // Unified diff convention: an empty range starts at the line before it
+    let old_start = if old_range.is_empty() { old_range.start } else { old_range.start + 1 };
+    let new_start = if new_range.is_empty() { new_range.start } else { new_range.start + 1 };
+
+    DiffHunk {
+        header: hunk_header(old_start, old_range.len(), new_start, new_range.len()),
+        old_start,
+        old_lines: old_range.len(),
+        new_start,
+        new_lines: new_range.len(),
+        lines,
+    }
+}
+
+// Line-based diff of two texts, grouped into hunks with `context` lines around each change
+pub fn diff_text(
+    old: &str,
+    new: &str,
+    old_label: &str,
+    new_label: &str,
+    context: usize,
+    word_level: bool,
+) -> FileDiff {
+    let diff = TextDiff::configure()
+        .algorithm(Algorithm::Myers)
+        .diff_lines(old, new);
+
+    let hunks: Vec<DiffHunk> = diff
+        .grouped_ops(context)
+        .iter()
+        .map(|ops| build_hunk(&diff, ops, word_level))
+        .collect();
+
+    let (additions, deletions) = hunks
+        .iter()
+        .flat_map(|h| h.lines.iter())
+        .fold((0, 0), |(add, del), line| match line.kind {
+            LineKind::Added => (add + 1, del),
+            LineKind::Removed => (add, del + 1),
+            LineKind::Context => (add, del),
+        });
+
+    let unified = diff
+        .unified_diff()
+        .context_radius(context)
+        .header(old_label, new_label)
+        .to_string();
+
+    FileDiff {
+        old_label: old_label.to_string(),
+        new_label: new_label.to_string(),
+        identical: hunks.is_empty(),
+        additions,
+        deletions,
+        hunks,
+        unified,
+    }
+}
@@ -0,0 +1,138 @@
+use std::fmt;
+use std::io;
+use std::path::Path;
+use serde::{Serialize, Serializer};
+
+// What every command fails with. Sent to the frontend as
+// `{ code, message, path, context }` so it can react to the kind of failure
+// instead of parsing messages.
+#[derive(Debug)]
+pub enum AppError {
+    NotFound { path: String },
+    PermissionDenied { path: String },
+    // The path is not inside an opened folder or the app data directory
+    OutsideScope { path: String },
+    // History deletion was asked for something outside the history directory
+    OutsideHistory { path: String },
+    // A path relative to the app data directory was absolute or escaped it
+    InvalidPath { path: String, reason: String },
+    TooLarge { path: Option<String>, size: u64, limit: u64 },
+    // A file that is not UTF-8 text
+    Binary { path: String },
+    MemoryLimit { requested: u64, used: u64, limit: u64 },
+    Io { path: Option<String>, message: String },
+    // Failures without a more specific code, e.g. from git or the window system
+    Other { message: String, context: Option<String> },
+}
+
+impl AppError {
+    // Sorts an I/O failure on `path` into the codes the frontend handles
+    pub fn io(path: &Path, e: io::Error) -> AppError {
+        let path = path.to_string_lossy().into_owned();
+        match e.kind() {
+            io::ErrorKind::NotFound => AppError::NotFound { path },
+            io::ErrorKind::PermissionDenied => AppError::PermissionDenied { path },
+            // read_to_string's error for content that isn't UTF-8
+            io::ErrorKind::InvalidData => AppError::Binary { path },
+            _ => AppError::Io { path: Some(path), message: e.to_string() },
+        }
+    }
+
+    // Names what was being attempted when a generic failure happened
+    pub fn context(self, context: &str) -> AppError {
+        match self {
+            AppError::Other { message, .. } => AppError::Other { message, context: Some(context.to_string()) },
+            other => other,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound { .. } => "notFound",
+            AppError::PermissionDenied { .. } => "permissionDenied",
+            AppError::OutsideScope { .. } => "outsideScope",
+            AppError::OutsideHistory { .. } => "outsideHistory",
+            AppError::InvalidPath { .. } => "invalidPath",
+            AppError::TooLarge { .. } => "tooLarge",
+            AppError::Binary { .. } => "binary",
+            AppError::MemoryLimit { .. } => "memoryLimit",
+            AppError::Io { .. } => "io",
+            AppError::Other { .. } => "other",
+        }
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            AppError::NotFound { path }
+            | AppError::PermissionDenied { path }
+            | AppError::OutsideScope { path }
+            | AppError::OutsideHistory { path }
+            | AppError::InvalidPath { path, .. }
+            | AppError::Binary { path } => Some(path),
+            AppError::TooLarge { path, .. } | AppError::Io { path, .. } => path.as_deref(),
+            AppError::MemoryLimit { .. } | AppError::Other { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppError::NotFound { path } => write!(f, "{} does not exist", path),
+            AppError::PermissionDenied { path } => write!(f, "Permission denied: {}", path),
+            AppError::OutsideScope { path } => write!(f, "Access denied: {} is outside the opened folders", path),
+            AppError::OutsideHistory { path } => write!(f, "Access denied: {} is not a history file", path),
+            AppError::InvalidPath { path, reason } => write!(f, "Invalid path {:?}: {}", path, reason),
+            AppError::TooLarge { path: Some(path), size, limit } => {
+                write!(f, "{} is too large ({} bytes, the limit is {})", path, size, limit)
+            }
+            AppError::TooLarge { path: None, size, limit } => {
+                write!(f, "Content is too large ({} bytes, the limit is {})", size, limit)
+            }
+            AppError::Binary { path } => write!(f, "{} is not a text file", path),
+            AppError::MemoryLimit { requested, used, limit } => write!(
+                f,
+                "Memory limit exceeded: {} bytes requested with {} of {} bytes in use",
+                requested, used, limit
+            ),
+            AppError::Io { path: Some(path), message } => write!(f, "{}: {}", path, message),
+            AppError::Io { path: None, message } => write!(f, "{}", message),
+            AppError::Other { message, context: Some(context) } => write!(f, "{}: {}", context, message),
+            AppError::Other { message, context: None } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+#[derive(Serialize)]
+struct Serialized<'a> {
+    code: &'static str,
+    message: String,
+    path: Option<&'a str>,
+    context: Option<&'a str>,
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let context = match self {
+            AppError::InvalidPath { reason, .. } => Some(reason.as_str()),
+            AppError::Other { context, .. } => context.as_deref(),
+            _ => None,
+        };
+        Serialized { code: self.code(), message: self.to_string(), path: self.path(), context }.serialize(serializer)
+    }
+}
+
+// Fallback for failures that have no more specific code
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Other { message, context: None }
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Other { message: message.to_string(), context: None }
+    }
+}
@@ -0,0 +1,115 @@
+use std::{fs, path::Path};
+use std::time::Duration;
+use crate::error::AppError;
+use crate::git;
+use crate::tree;
+
+pub const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024; // 10MB limit
+pub const MAX_DEPTH: u32 = 6; // Reduced max depth
+pub const BATCH_SIZE: usize = 50; // Batch size for processing
+
+#[derive(serde::Serialize)]
+pub struct FileInfo {
+    pub name: String,
+    pub path: String,
+    pub is_directory: bool,
+    pub children: Option<Vec<FileInfo>>,
+    pub git_status: Option<git::FileStatus>
+}
+
+// What a listing leaves out. Excluded and ignored folders are skipped without
+// being read, and once `max_entries` have been listed no more folders are
+// opened, so large checkouts stay cheap to render.
+#[derive(Default)]
+pub struct Walk<'a> {
+    // Name patterns, `*` and `?` wildcards allowed
+    pub exclude: &'a [String],
+    pub skip_ignored: bool,
+    pub max_entries: Option<usize>,
+}
+
+impl Walk<'_> {
+    fn skips(&self, name: &str, status: Option<git::FileStatus>) -> bool {
+        (self.skip_ignored && status == Some(git::FileStatus::Ignored))
+            || self.exclude.iter().any(|pattern| tree::matches_pattern(pattern, name))
+    }
+}
+
+pub fn read_dir_recursive(
+    path: &Path,
+    max_depth: u32,
+    current_depth: u32,
+    statuses: &git::StatusMap
+) -> Result<Vec<FileInfo>, AppError> {
+    read_dir_filtered(path, max_depth, current_depth, statuses, &Walk::default())
+}
+
+pub fn read_dir_filtered(
+    path: &Path,
+    max_depth: u32,
+    current_depth: u32,
+    statuses: &git::StatusMap,
+    walk: &Walk
+) -> Result<Vec<FileInfo>, AppError> {
+    let mut listed = 0;
+    walk_dir(path, max_depth, current_depth, statuses, walk, &mut listed)
+}
+
+fn walk_dir(
+    path: &Path,
+    max_depth: u32,
+    current_depth: u32,
+    statuses: &git::StatusMap,
+    walk: &Walk,
+    listed: &mut usize
+) -> Result<Vec<FileInfo>, AppError> {
+    if current_depth > max_depth || current_depth > MAX_DEPTH {
+        return Ok(Vec::new());
+    }
+
+    let mut result = Vec::new();
+    let entries: Vec<_> = fs::read_dir(path)
+        .map_err(|e| AppError::io(path, e))?
+        .filter_map(Result::ok)
+        .collect();
+
+    for chunk in entries.chunks(BATCH_SIZE) {
+        let chunk_len = chunk.len();
+        for entry in chunk.iter() {
+            let path_buf = entry.path();
+            let file_type = entry.file_type().map_err(|e| AppError::io(&path_buf, e))?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let git_status = statuses.get(&path_buf);
+            if walk.skips(&name, git_status) {
+                continue;
+            }
+
+            result.push(FileInfo {
+                name,
+                path: path_buf.to_string_lossy().into_owned(),
+                is_directory: file_type.is_dir(),
+                children: None,
+                git_status
+            });
+        }
+        
+        if chunk_len == BATCH_SIZE {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    // Folders are opened in the order the tree renders them, folders first, so
+    // the entry budget is spent on what ends up shown
+    result.sort_by_key(|info| (!info.is_directory, info.name.to_lowercase()));
+    for info in result.iter_mut() {
+        *listed += 1;
+        let within_budget = walk.max_entries.is_none_or(|max| *listed < max);
+        if info.is_directory && current_depth < max_depth && within_budget {
+            let children = walk_dir(Path::new(&info.path), max_depth, current_depth + 1, statuses, walk, listed)?;
+            info.children = Some(children);
+        }
+    }
+
+    result.sort_by_key(|info| info.name.to_lowercase());
+    Ok(result)
+}
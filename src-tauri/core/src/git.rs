@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+
+pub const DEFAULT_COMMIT_LIMIT: usize = 10;
+
+// Which changes to include when diffing the watched root
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum DiffSource {
+    Staged,
+    Unstaged,
+    Branch { branch: String },
+}
+
+impl DiffSource {
+    pub fn label(&self) -> String {
+        match self {
+            DiffSource::Staged => "staged changes".to_string(),
+            DiffSource::Unstaged => "unstaged changes".to_string(),
+            DiffSource::Branch { branch } => format!("changes against {}", branch),
+        }
+    }
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> Result<String, AppError> {
+    let output = Command::new("git")
+        .args(["-c", "core.quotepath=off"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| AppError::from(format!("Could not run git: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::from(stderr.trim()).context("git"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Location of `root` inside its repository, e.g. "src-tauri/" (empty at the top level)
+fn repo_prefix(root: &Path) -> Result<String, AppError> {
+    Ok(run_git(root, &["rev-parse", "--show-prefix"])?.trim().to_string())
+}
+
+// Maps a repository-relative path from git output onto `root` as the UI knows it
+fn resolve_under_root(root: &Path, prefix: &str, repo_path: &str) -> Option<PathBuf> {
+    let relative = repo_path.strip_prefix(prefix)?;
+    Some(root.join(relative))
+}
+
+// Unified diff limited to `root` (which may be a subdirectory of the repository),
+// with paths relative to `root`
+pub fn diff(root: &Path, source: &DiffSource) -> Result<String, AppError> {
+    let mut args = vec!["diff", "--no-color", "--no-ext-diff", "--relative"];
+    match source {
+        DiffSource::Staged => args.push("--cached"),
+        DiffSource::Unstaged => {}
+        DiffSource::Branch { branch } => {
+            if branch.starts_with('-') {
+                return Err(AppError::from(format!("Invalid branch name: {}", branch)).context("git"));
+            }
+            args.push(branch);
+        }
+    }
+    args.extend(["--", "."]);
+    run_git(root, &args)
+}
+
+// One file's part of a unified diff
+pub struct DiffFile<'a> {
+    // Relative to the diffed root; the old path for deletions
+    pub path: String,
+    pub text: &'a str,
+}
+
+// The path a file section of a diff is about, read from its header
+fn diff_path(section: &str) -> Option<String> {
+    let header = section.split("\n@@").next().unwrap_or(section);
+    let side = |marker: &str, prefix: &str| {
+        header.lines()
+            .find_map(|line| line.strip_prefix(marker))
+            .map(|path| path.trim_matches('"'))
+            .and_then(|path| path.strip_prefix(prefix))
+            .map(str::to_string)
+    };
+    side("+++ ", "b/").or_else(|| side("--- ", "a/")).or_else(|| {
+        // Binary files and mode changes have no ---/+++ lines
+        let (_, path) = header.lines().next()?.strip_prefix("diff --git ")?.rsplit_once(" b/")?;
+        Some(path.trim_matches('"').to_string())
+    })
+}
+
+// Splits a diff into its per-file sections
+pub fn split_diff(diff: &str) -> Vec<DiffFile<'_>> {
+    let mut starts: Vec<usize> = diff.match_indices("\ndiff --git ").map(|(i, _)| i + 1).collect();
+    if diff.starts_with("diff --git ") {
+        starts.insert(0, 0);
+    }
+    starts.iter()
+        .enumerate()
+        .filter_map(|(i, &start)| {
+            let text = &diff[start..starts.get(i + 1).copied().unwrap_or(diff.len())];
+            Some(DiffFile { path: diff_path(text)?, text })
+        })
+        .collect()
+}
+
+// Files under `root` that are modified, added, renamed or untracked.
+// Deleted files are skipped since there is nothing left to check.
+pub fn modified_files(root: &Path) -> Result<Vec<String>, AppError> {
+    let prefix = repo_prefix(root)?;
+    let output = run_git(root, &["status", "--porcelain=v1", "-z", "--untracked-files=all", "--", "."])?;
+
+    let mut files = Vec::new();
+    let mut records = output.split('\0').filter(|r| !r.is_empty());
+    while let Some(record) = records.next() {
+        if record.len() < 4 {
+            continue;
+        }
+        let (status, path) = record.split_at(3);
+        let status = status.as_bytes();
+
+        // Renames and copies are followed by the original path
+        if status[0] == b'R' || status[0] == b'C' {
+            records.next();
+        }
+        if status[0] == b'D' || status[1] == b'D' {
+            continue;
+        }
+
+        if let Some(full_path) = resolve_under_root(root, &prefix, path) {
+            if full_path.is_file() {
+                files.push(full_path.to_string_lossy().into_owned());
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+// Working tree status shown as a badge in the file tree. Ordered by how
+// strongly a directory should reflect a change somewhere below it.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    Ignored,
+    Untracked,
+    Added,
+    Modified,
+    Conflicted,
+}
+
+impl FileStatus {
+    fn from_porcelain(x: u8, y: u8) -> Option<FileStatus> {
+        match (x, y) {
+            (b'!', b'!') => Some(FileStatus::Ignored),
+            (b'?', b'?') => Some(FileStatus::Untracked),
+            (b'U', _) | (_, b'U') | (b'A', b'A') | (b'D', b'D') => Some(FileStatus::Conflicted),
+            (b'A', _) | (b'R', _) | (b'C', _) => Some(FileStatus::Added),
+            (b' ', b' ') => None,
+            _ => Some(FileStatus::Modified),
+        }
+    }
+}
+
+// Status of every changed path under a root, collected from a single `git status`
+#[derive(Default)]
+pub struct StatusMap {
+    root: PathBuf,
+    entries: HashMap<PathBuf, FileStatus>,
+}
+
+impl StatusMap {
+    // Empty when `root` is not inside a repository or git is unavailable
+    pub fn load(root: &Path) -> StatusMap {
+        let Ok(prefix) = repo_prefix(root) else {
+            return StatusMap { root: root.to_path_buf(), entries: HashMap::new() };
+        };
+        let Ok(output) = run_git(root, &[
+            "status", "--porcelain=v1", "-z", "--untracked-files=all", "--ignored=matching", "--", ".",
+        ]) else {
+            return StatusMap { root: root.to_path_buf(), entries: HashMap::new() };
+        };
+        StatusMap::parse(root, &prefix, &output)
+    }
+
+    // `output` is `git status --porcelain=v1 -z` run in `root`, which sits at
+    // `prefix` inside the repository
+    pub fn parse(root: &Path, prefix: &str, output: &str) -> StatusMap {
+        let mut map = StatusMap { root: root.to_path_buf(), entries: HashMap::new() };
+        let mut records = output.split('\0').filter(|r| !r.is_empty());
+        while let Some(record) = records.next() {
+            if record.len() < 4 {
+                continue;
+            }
+            let (code, path) = record.split_at(3);
+            let code = code.as_bytes();
+            if code[0] == b'R' || code[0] == b'C' {
+                records.next();
+            }
+
+            let Some(status) = FileStatus::from_porcelain(code[0], code[1]) else {
+                continue;
+            };
+            if let Some(full_path) = resolve_under_root(root, prefix, path.trim_end_matches('/')) {
+                map.insert(full_path, status);
+            }
+        }
+        map
+    }
+
+    fn insert(&mut self, path: PathBuf, status: FileStatus) {
+        if status == FileStatus::Ignored {
+            self.entries.insert(path, status);
+            return;
+        }
+
+        // Directories take the strongest status of anything beneath them
+        let mut current = Some(path.as_path());
+        while let Some(p) = current {
+            let entry = self.entries.entry(p.to_path_buf()).or_insert(status);
+            if *entry < status {
+                *entry = status;
+            }
+            if p == self.root {
+                break;
+            }
+            current = p.parent().filter(|parent| parent.starts_with(&self.root));
+        }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<FileStatus> {
+        if let Some(status) = self.entries.get(path) {
+            return Some(*status);
+        }
+
+        // Contents of ignored directories are only reported at the directory itself
+        path.ancestors()
+            .skip(1)
+            .take_while(|p| p.starts_with(&self.root))
+            .find(|p| self.entries.get(*p) == Some(&FileStatus::Ignored))
+            .map(|_| FileStatus::Ignored)
+    }
+}
+
+// The index file git rewrites whenever staging or committing changes status
+pub fn index_path(root: &Path) -> Result<PathBuf, AppError> {
+    let git_dir = run_git(root, &["rev-parse", "--absolute-git-dir"])?;
+    Ok(PathBuf::from(git_dir.trim()).join("index"))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitInfo {
+    pub hash: String,
+    pub short_hash: String,
+    pub author: String,
+    pub date: String,
+    pub subject: String,
+    pub body: String,
+}
+
+// Last `limit` commits touching any of `files`, newest first
+pub fn recent_commits(root: &Path, files: &[String], limit: usize) -> Result<Vec<CommitInfo>, AppError> {
+    let limit = limit.to_string();
+    let mut args = vec![
+        "log", "--no-color", "-n", limit.as_str(),
+        "--format=%H%x1f%h%x1f%an%x1f%ad%x1f%s%x1f%b%x1e", "--date=short", "--",
+    ];
+    args.extend(files.iter().map(String::as_str));
+
+    let output = run_git(root, &args)?;
+    Ok(output
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').splitn(6, '\x1f');
+            Some(CommitInfo {
+                hash: fields.next()?.to_string(),
+                short_hash: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                date: fields.next()?.to_string(),
+                subject: fields.next()?.to_string(),
+                body: fields.next()?.trim().to_string(),
+            })
+        })
+        .collect())
+}
+
+// Consecutive lines last changed by the same commit
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlameRange {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub short_hash: String,
+    pub author: String,
+    pub date: String,
+    pub summary: String,
+}
+
+// Days since the Unix epoch to a proleptic Gregorian (year, month, day)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn format_unix_date(seconds: i64) -> String {
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Blame for `file`, optionally limited to an inclusive 1-based line range
+pub fn blame(root: &Path, file: &str, lines: Option<(usize, usize)>) -> Result<Vec<BlameRange>, AppError> {
+    let range = lines.map(|(start, end)| format!("{},{}", start, end));
+    let mut args = vec!["blame", "--line-porcelain"];
+    if let Some(range) = &range {
+        args.extend(["-L", range.as_str()]);
+    }
+    args.extend(["--", file]);
+    let output = run_git(root, &args)?;
+
+    let mut ranges: Vec<BlameRange> = Vec::new();
+    let (mut short_hash, mut line_no) = (String::new(), 0usize);
+    let (mut author, mut date, mut summary) = (String::new(), String::new(), String::new());
+
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            // Content line closes the record for `line_no`
+            match ranges.last_mut() {
+                Some(last) if last.short_hash == short_hash && last.end_line + 1 == line_no => {
+                    last.end_line = line_no;
+                }
+                _ => ranges.push(BlameRange {
+                    start_line: line_no,
+                    end_line: line_no,
+                    short_hash: short_hash.clone(),
+                    author: author.clone(),
+                    date: date.clone(),
+                    summary: summary.clone(),
+                }),
+            }
+        } else if let Some(value) = line.strip_prefix("author ") {
+            author = value.to_string();
+        } else if let Some(value) = line.strip_prefix("author-time ") {
+            date = value.parse().map(format_unix_date).unwrap_or_default();
+        } else if let Some(value) = line.strip_prefix("summary ") {
+            summary = value.to_string();
+        } else {
+            let mut parts = line.split(' ');
+            if let (Some(h), Some(_), Some(final_line)) = (parts.next(), parts.next(), parts.next()) {
+                // SHA-1 or SHA-256 object names
+                if (h.len() == 40 || h.len() == 64) && h.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    short_hash = h[..7].to_string();
+                    line_no = final_line.parse().unwrap_or(0);
+                }
+            }
+        }
+    }
+    Ok(ranges)
+}
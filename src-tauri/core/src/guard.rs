@@ -0,0 +1,117 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::tree::matches_pattern;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum GuardAction {
+    // Exempts a file from the rules below it
+    Allow,
+    // Included, but reported alongside the prompt
+    Warn,
+    // Not copied until the user confirms the file
+    Deny,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GuardRule {
+    // Matched against the file name, or the root-relative path when it contains `/`
+    pub pattern: String,
+    pub action: GuardAction,
+}
+
+// Checked in order after the user's rules; the first matching rule wins
+const DEFAULT_RULES: &[(&str, GuardAction)] = &[
+    (".env.example", GuardAction::Allow),
+    (".env.sample", GuardAction::Allow),
+    (".env.template", GuardAction::Allow),
+    (".env", GuardAction::Deny),
+    (".env.*", GuardAction::Deny),
+    ("*.env", GuardAction::Deny),
+    ("id_rsa", GuardAction::Deny),
+    ("id_dsa", GuardAction::Deny),
+    ("id_ecdsa", GuardAction::Deny),
+    ("id_ed25519", GuardAction::Deny),
+    ("*.pem", GuardAction::Deny),
+    ("*.key", GuardAction::Deny),
+    ("*.p12", GuardAction::Deny),
+    ("*.pfx", GuardAction::Deny),
+    ("*.keystore", GuardAction::Deny),
+    ("credentials.json", GuardAction::Deny),
+    ("credentials", GuardAction::Warn),
+    ("*.tfstate", GuardAction::Warn),
+    (".npmrc", GuardAction::Warn),
+    (".pypirc", GuardAction::Warn),
+    (".netrc", GuardAction::Warn),
+    (".pgpass", GuardAction::Warn),
+    ("secrets.*", GuardAction::Warn),
+];
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GuardOptions {
+    #[serde(default)]
+    pub rules: Vec<GuardRule>,
+    #[serde(default = "default_true")]
+    pub use_defaults: bool,
+    // Denied files the user explicitly chose to include anyway
+    #[serde(default)]
+    pub confirmed: Vec<String>,
+}
+
+impl Default for GuardOptions {
+    fn default() -> Self {
+        Self { rules: Vec::new(), use_defaults: true, confirmed: Vec::new() }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GuardFinding {
+    pub path: String,
+    pub pattern: String,
+    pub action: GuardAction,
+    pub confirmed: bool,
+}
+
+// Case-insensitive, since `.ENV` is the same file as `.env` on Windows and macOS
+fn rule_matches(pattern: &str, path: &str, root: Option<&str>) -> bool {
+    let pattern = pattern.to_lowercase();
+    if pattern.contains('/') {
+        let relative = root
+            .and_then(|r| path.strip_prefix(r))
+            .unwrap_or(path)
+            .replace('\\', "/")
+            .to_lowercase();
+        return matches_pattern(pattern.trim_start_matches('/'), relative.trim_start_matches('/'));
+    }
+    let name = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    matches_pattern(&pattern, &name)
+}
+
+// The first rule matching `path`, if it calls for a warning or confirmation
+pub fn check(path: &str, root: Option<&str>, options: &GuardOptions) -> Option<GuardFinding> {
+    let defaults = DEFAULT_RULES
+        .iter()
+        .filter(|_| options.use_defaults)
+        .map(|(pattern, action)| (*pattern, *action));
+    let (pattern, action) = options.rules
+        .iter()
+        .map(|rule| (rule.pattern.as_str(), rule.action))
+        .chain(defaults)
+        .find(|(pattern, _)| rule_matches(pattern, path, root))?;
+
+    (action != GuardAction::Allow).then(|| GuardFinding {
+        path: path.to_string(),
+        pattern: pattern.to_string(),
+        action,
+        confirmed: options.confirmed.iter().any(|c| c == path),
+    })
+}
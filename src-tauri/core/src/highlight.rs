@@ -0,0 +1,165 @@
+use std::path::Path;
+use std::sync::OnceLock;
+use serde::Serialize;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Color, FontStyle, Style, Theme, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+
+// App theme to the bundled syntect theme closest to it
+const THEMES: &[(&str, &str)] = &[
+    ("light", "InspiredGitHub"),
+    ("dark", "base16-eighties.dark"),
+    ("cosmic", "base16-eighties.dark"),
+    ("emerald", "base16-mocha.dark"),
+    ("sunset", "base16-mocha.dark"),
+    ("ocean", "base16-ocean.dark"),
+    ("solarized", "Solarized (dark)"),
+];
+const DEFAULT_THEME: &str = "Solarized (dark)";
+// Extensions without a bundled grammar, highlighted with a close relative
+const EXTENSION_ALIASES: &[(&str, &str)] = &[
+    ("ts", "js"),
+    ("tsx", "js"),
+    ("mts", "js"),
+    ("cts", "js"),
+    ("jsx", "js"),
+];
+// Longer lines are passed through unstyled; the regex engine slows down badly on them
+const MAX_LINE_BYTES: usize = 4096;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Span {
+    pub text: String,
+    // CSS hex color
+    pub color: String,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+// Loading the bundled grammars and themes takes a moment, so it happens once on first use
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn themes() -> &'static ThemeSet {
+    static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+    THEME_SET.get_or_init(ThemeSet::load_defaults)
+}
+
+fn theme_for(app_theme: &str) -> &'static Theme {
+    let name = THEMES.iter()
+        .find(|(app, _)| *app == app_theme)
+        .map_or(DEFAULT_THEME, |(_, syntect)| *syntect);
+    &themes().themes[name]
+}
+
+// By extension, then by a shebang or mode line, falling back to plain text
+fn syntax_for(path: &str, first_line: &str) -> &'static SyntaxReference {
+    let set = syntaxes();
+    let name = Path::new(path).file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let extension = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or_default();
+    let extension = EXTENSION_ALIASES.iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(extension))
+        .map_or(extension, |(_, actual)| *actual);
+    set.find_syntax_by_extension(name)
+        .or_else(|| set.find_syntax_by_extension(extension))
+        .or_else(|| set.find_syntax_by_first_line(first_line))
+        .unwrap_or_else(|| set.find_syntax_plain_text())
+}
+
+fn hex(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+fn to_span(style: Style, text: &str) -> Span {
+    Span {
+        text: text.to_string(),
+        color: hex(style.foreground),
+        bold: style.font_style.contains(FontStyle::BOLD),
+        italic: style.font_style.contains(FontStyle::ITALIC),
+    }
+}
+
+// Joins neighbouring spans that look the same, which is most of them in plain stretches
+fn merge(spans: impl Iterator<Item = Span>) -> Vec<Span> {
+    let mut merged: Vec<Span> = Vec::new();
+    for span in spans.filter(|span| !span.text.is_empty()) {
+        match merged.last_mut() {
+            Some(last) if last.color == span.color && last.bold == span.bold && last.italic == span.italic => {
+                last.text.push_str(&span.text);
+            }
+            _ => merged.push(span),
+        }
+    }
+    merged
+}
+
+// Highlights consecutive lines of `path`, one list of spans per line. Line
+// endings are not part of the spans.
+pub fn lines<'a>(path: &str, lines: impl IntoIterator<Item = &'a str>, app_theme: &str) -> Vec<Vec<Span>> {
+    let mut lines = lines.into_iter().peekable();
+    let first_line = lines.peek().copied().unwrap_or_default();
+    let theme = theme_for(app_theme);
+    let plain = Style { foreground: theme.settings.foreground.unwrap_or(Color::BLACK), ..Style::default() };
+    let mut highlighter = HighlightLines::new(syntax_for(path, first_line), theme);
+
+    lines.map(|line| {
+        if line.len() > MAX_LINE_BYTES {
+            return vec![to_span(plain, line)];
+        }
+        // The newline-based grammars expect every line to end in one
+        let with_newline = format!("{}\n", line);
+        match highlighter.highlight_line(&with_newline, syntaxes()) {
+            Ok(ranges) => merge(ranges.into_iter().map(|(style, text)| to_span(style, text.trim_end_matches('\n')))),
+            Err(_) => vec![to_span(plain, line)],
+        }
+    }).collect()
+}
+
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// `text` as inline-styled HTML spans, without a surrounding element
+pub fn html(path: &str, text: &str, app_theme: &str) -> String {
+    let mut output = String::with_capacity(text.len() * 2);
+    for (i, spans) in lines(path, text.lines(), app_theme).into_iter().enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+        for span in spans {
+            let mut style = format!("color:{}", span.color);
+            if span.bold {
+                style.push_str(";font-weight:bold");
+            }
+            if span.italic {
+                style.push_str(";font-style:italic");
+            }
+            output.push_str(&format!("<span style=\"{}\">{}</span>", style, escape(&span.text)));
+        }
+    }
+    output
+}
+
+// Wraps highlighted HTML in a block carrying the theme's colors, so it keeps
+// its look when pasted into documents or chat apps
+pub fn document(body: &str, app_theme: &str) -> String {
+    let settings = &theme_for(app_theme).settings;
+    let background = settings.background.map_or_else(|| "#ffffff".to_string(), hex);
+    let foreground = settings.foreground.map_or_else(|| "#000000".to_string(), hex);
+    format!(
+        "<pre style=\"background:{};color:{};padding:12px;font-family:Menlo,Consolas,monospace;font-size:13px;white-space:pre-wrap\">{}</pre>",
+        background, foreground, body
+    )
+}
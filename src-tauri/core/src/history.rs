@@ -0,0 +1,336 @@
+use std::{fs, path::{Path, PathBuf}};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::atomic;
+use crate::error::AppError;
+use crate::files;
+use crate::scope;
+use crate::selection::Selection;
+
+// Where entries live, relative to the app data directory
+pub const HISTORY_DIR: &str = "prompt-copy/history";
+// Entries that failed to parse are moved here, keeping their YYYY/MM/DD layout
+pub const QUARANTINE_DIR: &str = "prompt-copy/quarantine";
+
+// A single file captured in a history entry
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistoryFile {
+    pub path: String,
+    pub content: String,
+}
+
+// History entry as written by the frontend under prompt-copy/history/YYYY/MM/DD
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub timestamp: String,
+    pub prompt: String,
+    pub files: Vec<HistoryFile>,
+    #[serde(default)]
+    pub add_script_fix: bool,
+    #[serde(default)]
+    pub success: bool,
+    // Older entries were saved without the watched root
+    #[serde(default)]
+    pub root: Option<String>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum RestoreStatus {
+    Unchanged,
+    Changed,
+    Missing,
+    // The entry holds a note instead of the file's contents, so there is nothing to compare
+    Unrecorded,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoredFile {
+    pub recorded_path: String,
+    pub path: String,
+    pub status: RestoreStatus,
+}
+
+// Everything the UI needs to rebuild a tab from a history entry
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoredSelection {
+    pub root: Option<String>,
+    pub prompt: String,
+    pub add_script_fix: bool,
+    pub checked_files: Vec<String>,
+    pub files: Vec<RestoredFile>,
+    pub missing_count: usize,
+    pub changed_count: usize,
+}
+
+pub fn load_entry(path: &Path) -> Result<HistoryEntry, AppError> {
+    let content = fs::read_to_string(path).map_err(|e| AppError::io(path, e))?;
+    parse_entry(&content).and_then(|value| HistoryEntry::deserialize(value).map_err(corrupt))
+}
+
+// Writes an entry as assembled by the frontend. Only paths below `history_dir`
+// are accepted; workspace roots grant read access and must not be writable this way.
+pub fn write(path: &Path, history_dir: &Path, content: &str) -> Result<(), AppError> {
+    if !path.starts_with(history_dir) {
+        return Err(AppError::OutsideHistory { path: path.to_string_lossy().into_owned() });
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| AppError::io(parent, e))?;
+    }
+    atomic::write(path, content.as_bytes()).map_err(|e| AppError::io(path, e))
+}
+
+// Deletes one entry, and its day folder once the last entry in it is gone;
+// never the history folder itself
+pub fn delete(path: &Path, history_dir: &Path) -> Result<(), AppError> {
+    let resolved = scope::check_history_file(path, history_dir)?;
+    fs::remove_file(&resolved).map_err(|e| AppError::io(&resolved, e))?;
+
+    if let Some(parent) = resolved.parent() {
+        if parent.exists() && parent != scope::canonical(history_dir)? {
+            let is_empty = fs::read_dir(parent)
+                .map_err(|e| AppError::io(parent, e))?
+                .next()
+                .is_none();
+            if is_empty {
+                fs::remove_dir(parent).map_err(|e| AppError::io(parent, e))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Every entry file below `dir`, at any depth; empty when `dir` doesn't exist yet
+pub fn list_files(dir: &Path) -> Result<Vec<String>, AppError> {
+    let mut files = Vec::new();
+    list_json_files_recursive(dir, &mut files)?;
+    Ok(files)
+}
+
+// An entry together with its path relative to the history directory, e.g.
+// `2024/01/02/1704164645000.json`, which is how the CLI refers to it
+#[derive(Serialize)]
+pub struct StoredEntry {
+    pub id: String,
+    #[serde(flatten)]
+    pub entry: HistoryEntry,
+}
+
+// Every readable entry below `history_dir`, newest first. Unlike load_day this
+// only reads: unparsable entries are skipped, not quarantined.
+pub fn entries(history_dir: &Path) -> Result<Vec<StoredEntry>, AppError> {
+    let mut entries: Vec<StoredEntry> = list_files(history_dir)?
+        .into_iter()
+        .filter_map(|file| {
+            let path = Path::new(&file);
+            let entry = load_entry(path).ok()?;
+            let id = path.strip_prefix(history_dir).ok()?.to_string_lossy().replace('\\', "/");
+            Some(StoredEntry { id, entry })
+        })
+        .collect();
+    entries.sort_by(|a, b| b.entry.timestamp.cmp(&a.entry.timestamp).then_with(|| b.id.cmp(&a.id)));
+    Ok(entries)
+}
+
+// Resolves an id from `entries` back to its file, refusing anything that
+// leaves the history directory
+pub fn entry_path(history_dir: &Path, id: &str) -> Result<PathBuf, AppError> {
+    scope::check_history_file(&history_dir.join(id), history_dir)
+}
+
+impl HistoryEntry {
+    // Case-insensitive match on the prompt and recorded paths, and on the
+    // file snapshots when `contents` is set
+    pub fn matches(&self, query: &str, contents: bool) -> bool {
+        let query = query.to_lowercase();
+        self.prompt.to_lowercase().contains(&query)
+            || self.files.iter().any(|file| {
+                file.path.to_lowercase().contains(&query)
+                    || (contents && file.content.to_lowercase().contains(&query))
+            })
+    }
+}
+
+fn list_json_files_recursive(dir: &Path, files: &mut Vec<String>) -> Result<(), AppError> {
+    if dir.is_dir() {
+        let entries: Vec<_> = fs::read_dir(dir)
+            .map_err(|e| AppError::io(dir, e))?
+            .filter_map(Result::ok)
+            .collect();
+
+        for chunk in entries.chunks(files::BATCH_SIZE) {
+            let chunk_len = chunk.len();
+            for entry in chunk.iter() {
+                let path = entry.path();
+
+                if path.is_dir() {
+                    list_json_files_recursive(&path, files)?;
+                } else if path.extension().and_then(|ext| ext.to_str()) == Some("json") {
+                    files.push(path.to_string_lossy().into_owned());
+                }
+            }
+
+            if chunk_len == files::BATCH_SIZE {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+    Ok(())
+}
+
+// One day of history; entries are passed through as written so fields this
+// module does not know about still reach the UI
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryDay {
+    pub entries: Vec<serde_json::Value>,
+    // Files moved aside because they were truncated or not a history entry
+    pub quarantined: Vec<String>,
+}
+
+// Truncated files and JSON that isn't a history entry
+fn corrupt(e: serde_json::Error) -> AppError {
+    AppError::from(e.to_string()).context("history entry")
+}
+
+fn parse_entry(content: &str) -> Result<serde_json::Value, AppError> {
+    let value: serde_json::Value = serde_json::from_str(content).map_err(corrupt)?;
+    HistoryEntry::deserialize(&value).map_err(corrupt)?;
+    Ok(value)
+}
+
+fn quarantine(path: &Path, history_dir: &Path, quarantine_dir: &Path) -> Result<PathBuf, AppError> {
+    let relative = path.strip_prefix(history_dir)
+        .map_err(|_| AppError::OutsideHistory { path: path.to_string_lossy().into_owned() })?;
+    let target = quarantine_dir.join(relative);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| AppError::io(parent, e))?;
+    }
+    fs::rename(path, &target).map_err(|e| AppError::io(path, e))?;
+    Ok(target)
+}
+
+// Loads every entry in `day_dir`, moving corrupt ones into the quarantine
+// instead of failing the whole listing
+pub fn load_day(day_dir: &Path, history_dir: &Path, quarantine_dir: &Path) -> Result<HistoryDay, AppError> {
+    let mut day = HistoryDay { entries: Vec::new(), quarantined: Vec::new() };
+    if !day_dir.is_dir() {
+        return Ok(day);
+    }
+
+    for dir_entry in fs::read_dir(day_dir).map_err(|e| AppError::io(day_dir, e))?.filter_map(Result::ok) {
+        let path = dir_entry.path();
+        if !path.is_file() || path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let parsed = fs::read_to_string(&path)
+            .map_err(|e| AppError::io(&path, e))
+            .and_then(|content| parse_entry(&content));
+        match parsed {
+            Ok(entry) => day.entries.push(entry),
+            Err(e) => {
+                eprintln!("Quarantining corrupt history entry {}: {}", path.display(), e);
+                match quarantine(&path, history_dir, quarantine_dir) {
+                    Ok(target) => day.quarantined.push(target.to_string_lossy().into_owned()),
+                    Err(e) => eprintln!("Could not quarantine {}: {}", path.display(), e),
+                }
+            }
+        }
+    }
+    Ok(day)
+}
+
+pub fn snapshot<'a>(entry: &'a HistoryEntry, file: &str) -> Result<&'a HistoryFile, AppError> {
+    entry.files.iter()
+        .find(|f| f.path == file)
+        .ok_or_else(|| AppError::from(format!("{} is not part of this history entry", file)))
+}
+
+// Recorded root, or the deepest directory shared by every recorded file
+pub fn entry_root(entry: &HistoryEntry) -> Option<PathBuf> {
+    if let Some(root) = &entry.root {
+        return Some(PathBuf::from(root));
+    }
+
+    let mut paths = entry.files.iter().map(|f| Path::new(&f.path));
+    let mut common = paths.next()?.parent()?.to_path_buf();
+    for path in paths {
+        while !path.starts_with(&common) {
+            common = common.parent()?.to_path_buf();
+        }
+    }
+    Some(common)
+}
+
+// What FolderHistory.ts saves for a file it skipped or could not read
+fn is_placeholder(content: &str) -> bool {
+    content.starts_with("Skipped: ") || content == "Error: Could not read file"
+}
+
+// Checks each recorded file against the disk, optionally relocating the
+// selection onto a different root (e.g. a moved or re-cloned checkout)
+pub fn restore(entry: &HistoryEntry, root_override: Option<&Path>) -> RestoredSelection {
+    let original_root = entry_root(entry);
+    let root = root_override.map(Path::to_path_buf).or_else(|| original_root.clone());
+    // Compared after resolving `..` and symlinks, so no recorded path reads outside the root
+    let canonical_root = root.as_deref().map(|r| fs::canonicalize(r).ok());
+
+    let mut files = Vec::with_capacity(entry.files.len());
+    let mut checked_files = Vec::new();
+
+    for recorded in &entry.files {
+        // Entries may be partial selections such as `src/main.rs:120-200`
+        let selection = Selection::parse(&recorded.path);
+        let suffix = &recorded.path[selection.path.len()..];
+        let recorded_path = Path::new(&selection.path);
+        let relative = original_root.as_ref().and_then(|r| recorded_path.strip_prefix(r).ok());
+
+        let path = match (&root, relative) {
+            (Some(root), Some(relative)) => root.join(relative),
+            _ => recorded_path.to_path_buf(),
+        };
+
+        let inside_root = match &canonical_root {
+            None => true,
+            Some(Some(root)) => fs::canonicalize(&path).is_ok_and(|p| p.starts_with(root)),
+            Some(None) => false,
+        };
+        let status = if !inside_root || !path.is_file() {
+            RestoreStatus::Missing
+        } else {
+            match fs::read_to_string(&path) {
+                Ok(current) if current == recorded.content => RestoreStatus::Unchanged,
+                Ok(_) if is_placeholder(&recorded.content) => RestoreStatus::Unrecorded,
+                Ok(_) => RestoreStatus::Changed,
+                Err(_) => RestoreStatus::Missing,
+            }
+        };
+
+        let path = format!("{}{}", path.to_string_lossy(), suffix);
+        if status != RestoreStatus::Missing {
+            checked_files.push(path.clone());
+        }
+
+        files.push(RestoredFile {
+            recorded_path: recorded.path.clone(),
+            path,
+            status,
+        });
+    }
+
+    let missing_count = files.iter().filter(|f| f.status == RestoreStatus::Missing).count();
+    let changed_count = files.iter().filter(|f| f.status == RestoreStatus::Changed).count();
+
+    RestoredSelection {
+        root: root.map(|r| r.to_string_lossy().into_owned()),
+        prompt: entry.prompt.clone(),
+        add_script_fix: entry.add_script_fix,
+        checked_files,
+        files,
+        missing_count,
+        changed_count,
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::viewers::{ViewerInfo, ViewerMode};
+
+// Logical pixels given to attached viewers until the user resizes them
+pub const DEFAULT_DOCK_SIZE: f64 = 1600.0;
+// Layout used on monitors nothing has been saved for yet
+pub const FALLBACK_MONITOR: &str = "";
+// Attached viewers are never squeezed below this many logical pixels
+const MIN_DOCK_SIZE: f64 = 200.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum DockSide {
+    Left,
+    #[default]
+    Right,
+    Bottom,
+}
+
+// Position and size in logical pixels, relative to the monitor's top-left corner
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct WindowLayout {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+// Everything remembered about the windows on one monitor
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MonitorLayout {
+    pub main: Option<WindowLayout>,
+    pub dock_side: DockSide,
+    // Width of the viewer area when docked left or right, its height at the bottom
+    pub dock_size: f64,
+    // Where the last floating viewer was left
+    pub floating: Option<WindowLayout>,
+    // Mode the shared viewer opens in
+    pub viewer_mode: ViewerMode,
+}
+
+impl Default for MonitorLayout {
+    fn default() -> Self {
+        Self {
+            main: None,
+            dock_side: DockSide::default(),
+            dock_size: DEFAULT_DOCK_SIZE,
+            floating: None,
+            viewer_mode: ViewerMode::Docked,
+        }
+    }
+}
+
+// Physical pixels in desktop coordinates
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct MonitorInfo {
+    // Name reported by the OS, or its geometry when it has none
+    pub key: String,
+    pub bounds: Rect,
+    pub scale: f64,
+}
+
+impl MonitorInfo {
+    pub fn new(name: Option<&str>, bounds: Rect, scale: f64) -> MonitorInfo {
+        let key = match name {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => format!("{}x{}@{},{}", bounds.width, bounds.height, bounds.x, bounds.y),
+        };
+        MonitorInfo { key, bounds, scale }
+    }
+
+    pub fn to_physical(&self, layout: &WindowLayout) -> Rect {
+        Rect {
+            x: self.bounds.x + (layout.x * self.scale).round() as i32,
+            y: self.bounds.y + (layout.y * self.scale).round() as i32,
+            width: (layout.width * self.scale).round().max(1.0) as u32,
+            height: (layout.height * self.scale).round().max(1.0) as u32,
+        }
+    }
+
+    pub fn to_logical(&self, rect: Rect) -> WindowLayout {
+        WindowLayout {
+            x: (rect.x - self.bounds.x) as f64 / self.scale,
+            y: (rect.y - self.bounds.y) as f64 / self.scale,
+            width: rect.width as f64 / self.scale,
+            height: rect.height as f64 / self.scale,
+        }
+    }
+
+    // Shrinks and shifts `rect` onto this monitor so a restored window is never out of reach
+    pub fn fit(&self, rect: Rect) -> Rect {
+        let bounds = self.bounds;
+        let width = rect.width.min(bounds.width);
+        let height = rect.height.min(bounds.height);
+        Rect {
+            x: rect.x.clamp(bounds.x, bounds.x + (bounds.width - width) as i32),
+            y: rect.y.clamp(bounds.y, bounds.y + (bounds.height - height) as i32),
+            width,
+            height,
+        }
+    }
+
+    // The dock size in physical pixels on this monitor
+    pub fn dock_size(&self, layout: &MonitorLayout) -> u32 {
+        (layout.dock_size.max(MIN_DOCK_SIZE) * self.scale).round() as u32
+    }
+}
+
+// Where to restore the main window: onto the monitor it was last on if still
+// connected, else any connected monitor with a saved layout, else the
+// fallback layout on the `current` monitor
+pub fn restore_target(
+    monitors: &[MonitorInfo],
+    current: Option<&MonitorInfo>,
+    layouts: &HashMap<String, MonitorLayout>,
+    last: Option<&str>,
+) -> Option<Rect> {
+    let saved = |monitor: &MonitorInfo| {
+        let main = layouts.get(&monitor.key)?.main?;
+        Some(monitor.fit(monitor.to_physical(&main)))
+    };
+    monitors.iter()
+        .find(|m| Some(m.key.as_str()) == last)
+        .and_then(saved)
+        .or_else(|| monitors.iter().find_map(saved))
+        .or_else(|| {
+            let monitor = current?;
+            let main = layouts.get(FALLBACK_MONITOR)?.main?;
+            Some(monitor.fit(monitor.to_physical(&main)))
+        })
+}
+
+fn attached(viewers: &[ViewerInfo]) -> Vec<&ViewerInfo> {
+    let docked = viewers.iter().filter(|v| v.mode == ViewerMode::Docked);
+    let tiled = viewers.iter().filter(|v| v.mode == ViewerMode::Tiled);
+    docked.chain(tiled).collect()
+}
+
+// The area attached viewers share next to `main`
+fn dock_area(main: Rect, side: DockSide, size: u32) -> Rect {
+    match side {
+        DockSide::Right => Rect { x: main.x + main.width as i32, y: main.y, width: size, height: main.height },
+        DockSide::Left => Rect { x: main.x - size as i32, y: main.y, width: size, height: main.height },
+        DockSide::Bottom => Rect { x: main.x, y: main.y + main.height as i32, width: main.width, height: size },
+    }
+}
+
+// Where each docked or tiled viewer goes: side by side in the dock area, the
+// docked one first, splitting it evenly. Floating viewers are left out.
+pub fn arrange(main: Rect, side: DockSide, size: u32, viewers: &[ViewerInfo]) -> Vec<(String, Rect)> {
+    let attached = attached(viewers);
+    if attached.is_empty() {
+        return Vec::new();
+    }
+
+    let area = dock_area(main, side, size);
+    let width = area.width / attached.len() as u32;
+    attached.iter().enumerate().map(|(i, viewer)| {
+        (viewer.label.clone(), Rect {
+            x: area.x + (i as u32 * width) as i32,
+            y: area.y,
+            width,
+            height: area.height,
+        })
+    }).collect()
+}
+
+// Dock size implied by the user resizing one of `count` attached viewers to `rect`
+pub fn dock_size_from(rect: Rect, side: DockSide, count: usize) -> u32 {
+    match side {
+        DockSide::Left | DockSide::Right => rect.width * count.max(1) as u32,
+        DockSide::Bottom => rect.height,
+    }
+}
+
+// Where the main window goes when the first attached viewer is dragged to `viewer`
+pub fn main_for_viewer(viewer: Rect, main_width: u32, main_height: u32, side: DockSide, size: u32) -> (i32, i32) {
+    match side {
+        DockSide::Right => (viewer.x - main_width as i32, viewer.y),
+        DockSide::Left => (viewer.x + size as i32, viewer.y),
+        DockSide::Bottom => (viewer.x, viewer.y - main_height as i32),
+    }
+}
@@ -0,0 +1,26 @@
+// Everything the app does that doesn't need a window, so it can be used and
+// tested without Tauri; main.rs wires it up to commands and window events
+pub mod api;
+pub mod atomic;
+pub mod diff;
+pub mod error;
+pub mod files;
+pub mod git;
+pub mod guard;
+pub mod highlight;
+pub mod history;
+pub mod layout;
+pub mod memory;
+pub mod outline;
+pub mod paging;
+pub mod prompt;
+pub mod redact;
+pub mod scope;
+pub mod selection;
+pub mod settings;
+pub mod stream;
+pub mod template;
+pub mod tree;
+pub mod viewers;
+pub mod watcher;
+pub mod workspace;
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use serde::Serialize;
+use crate::error::AppError;
+
+// What a reservation is for, so usage can be broken down in memory_stats
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum Subsystem {
+    // Prompt history being written to disk
+    History,
+    // Content being sent to a viewer window
+    Viewer,
+    // Chunks kept until the viewer acknowledges a streamed transfer
+    Transfers,
+    // History snapshots held in memory while the viewer pages through them
+    Paging,
+}
+
+const SUBSYSTEMS: [Subsystem; 4] = [Subsystem::History, Subsystem::Viewer, Subsystem::Transfers, Subsystem::Paging];
+
+#[derive(Default)]
+struct Usage {
+    bytes: AtomicU64,
+    allocations: AtomicU64,
+}
+
+struct Counters {
+    limit: u64,
+    used: AtomicU64,
+    peak: AtomicU64,
+    subsystems: [Usage; SUBSYSTEMS.len()],
+}
+
+impl Counters {
+    fn usage(&self, subsystem: Subsystem) -> &Usage {
+        &self.subsystems[subsystem as usize]
+    }
+}
+
+// Bytes held by the large buffers the backend keeps around, capped at a limit
+pub struct MemoryMonitor {
+    counters: Arc<Counters>,
+}
+
+// Counts against the limit until dropped
+pub struct Allocation {
+    counters: Arc<Counters>,
+    subsystem: Subsystem,
+    size: u64,
+}
+
+impl Drop for Allocation {
+    fn drop(&mut self) {
+        self.counters.used.fetch_sub(self.size, Ordering::SeqCst);
+        let usage = self.counters.usage(self.subsystem);
+        usage.bytes.fetch_sub(self.size, Ordering::SeqCst);
+        usage.allocations.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SubsystemStats {
+    pub subsystem: Subsystem,
+    pub bytes: u64,
+    // Reservations currently held
+    pub allocations: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryStats {
+    pub used: u64,
+    pub limit: u64,
+    // Highest usage since startup
+    pub peak: u64,
+    pub subsystems: Vec<SubsystemStats>,
+}
+
+impl MemoryMonitor {
+    pub fn new(limit: u64) -> MemoryMonitor {
+        MemoryMonitor {
+            counters: Arc::new(Counters {
+                limit,
+                used: AtomicU64::new(0),
+                peak: AtomicU64::new(0),
+                subsystems: Default::default(),
+            }),
+        }
+    }
+
+    // Reserves `size` bytes, failing without reserving anything if that would
+    // go over the limit. Concurrent reservations can't overshoot it together.
+    pub fn reserve(&self, subsystem: Subsystem, size: u64) -> Result<Allocation, AppError> {
+        let counters = &self.counters;
+        let used = counters.used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                used.checked_add(size).filter(|&total| total <= counters.limit)
+            })
+            .map_err(|used| AppError::MemoryLimit { requested: size, used, limit: counters.limit })?;
+        counters.peak.fetch_max(used + size, Ordering::SeqCst);
+
+        let usage = counters.usage(subsystem);
+        usage.bytes.fetch_add(size, Ordering::SeqCst);
+        usage.allocations.fetch_add(1, Ordering::SeqCst);
+        Ok(Allocation { counters: counters.clone(), subsystem, size })
+    }
+
+    pub fn stats(&self) -> MemoryStats {
+        let counters = &self.counters;
+        MemoryStats {
+            used: counters.used.load(Ordering::SeqCst),
+            limit: counters.limit,
+            peak: counters.peak.load(Ordering::SeqCst),
+            subsystems: SUBSYSTEMS.iter().map(|&subsystem| {
+                let usage = counters.usage(subsystem);
+                SubsystemStats {
+                    subsystem,
+                    bytes: usage.bytes.load(Ordering::SeqCst),
+                    allocations: usage.allocations.load(Ordering::SeqCst),
+                }
+            }).collect(),
+        }
+    }
+}
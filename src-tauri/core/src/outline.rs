@@ -0,0 +1,266 @@
+use std::path::Path;
+use tree_sitter::{Language, Node, Parser};
+use crate::error::AppError;
+
+// Longest statement kept verbatim in an outline before it is cut to its first line
+const MAX_SHORT_LINES: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BodyStyle {
+    Braces,
+    Indented,
+}
+
+// How each node kind of a grammar is treated when outlining
+struct Rules {
+    style: BodyStyle,
+    // Declarations whose body is replaced with a placeholder
+    elide: &'static [&'static str],
+    // Declarations whose members are outlined in turn
+    containers: &'static [&'static str],
+    // Declarations kept verbatim
+    keep: &'static [&'static str],
+    // Kept verbatim when short, otherwise cut to their first line
+    short: &'static [&'static str],
+    // Nodes wrapping a declaration, with the field holding it
+    wrappers: &'static [(&'static str, &'static str)],
+    // Nodes whose children are outlined as if they belonged to the parent
+    transparent: &'static [&'static str],
+}
+
+const RUST: Rules = Rules {
+    style: BodyStyle::Braces,
+    elide: &["function_item"],
+    containers: &["impl_item", "trait_item", "mod_item"],
+    keep: &[
+        "struct_item", "enum_item", "union_item", "type_item", "use_declaration",
+        "function_signature_item", "attribute_item", "associated_type", "extern_crate_declaration",
+    ],
+    short: &["const_item", "static_item"],
+    wrappers: &[],
+    transparent: &[],
+};
+
+const TYPESCRIPT: Rules = Rules {
+    style: BodyStyle::Braces,
+    elide: &[
+        "function_declaration", "generator_function_declaration", "method_definition",
+        "lexical_declaration", "variable_declaration",
+    ],
+    containers: &["class_declaration", "abstract_class_declaration", "internal_module", "module"],
+    keep: &[
+        "import_statement", "interface_declaration", "type_alias_declaration", "enum_declaration",
+        "function_signature", "method_signature", "abstract_method_signature", "ambient_declaration",
+    ],
+    short: &["public_field_definition"],
+    wrappers: &[("export_statement", "declaration")],
+    transparent: &[],
+};
+
+const PYTHON: Rules = Rules {
+    style: BodyStyle::Indented,
+    elide: &["function_definition"],
+    containers: &["class_definition"],
+    keep: &["import_statement", "import_from_statement", "future_import_statement"],
+    short: &["expression_statement"],
+    wrappers: &[("decorated_definition", "definition")],
+    transparent: &[],
+};
+
+const GO: Rules = Rules {
+    style: BodyStyle::Braces,
+    elide: &["function_declaration", "method_declaration"],
+    containers: &[],
+    keep: &["package_clause", "import_declaration", "type_declaration"],
+    short: &["const_declaration", "var_declaration"],
+    wrappers: &[],
+    transparent: &[],
+};
+
+const JAVA: Rules = Rules {
+    style: BodyStyle::Braces,
+    elide: &["method_declaration", "constructor_declaration", "compact_constructor_declaration"],
+    containers: &[
+        "class_declaration", "interface_declaration", "enum_declaration",
+        "record_declaration", "annotation_type_declaration",
+    ],
+    keep: &["package_declaration", "import_declaration", "constant_declaration", "enum_constant"],
+    short: &["field_declaration"],
+    wrappers: &[],
+    transparent: &["enum_body_declarations"],
+};
+
+fn grammar_for(path: &Path) -> Option<(Language, &'static Rules)> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    let grammar = match ext.as_str() {
+        "rs" => (tree_sitter_rust::LANGUAGE.into(), &RUST),
+        "ts" | "mts" | "cts" => (tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(), &TYPESCRIPT),
+        // The TSX grammar also covers plain JavaScript
+        "tsx" | "js" | "jsx" | "mjs" | "cjs" => (tree_sitter_typescript::LANGUAGE_TSX.into(), &TYPESCRIPT),
+        "py" | "pyi" => (tree_sitter_python::LANGUAGE.into(), &PYTHON),
+        "go" => (tree_sitter_go::LANGUAGE.into(), &GO),
+        "java" => (tree_sitter_java::LANGUAGE.into(), &JAVA),
+        _ => return None,
+    };
+    Some(grammar)
+}
+
+struct Outliner<'a> {
+    source: &'a str,
+    rules: &'static Rules,
+    lines: Vec<String>,
+    // Used for members that share a line with their container, e.g. `interface I { void n(); }`
+    member_indent: String,
+}
+
+impl<'a> Outliner<'a> {
+    // Leading whitespace before `byte` when it starts its line
+    fn indent_at(&self, byte: usize) -> String {
+        let line_start = self.source[..byte].rfind('\n').map_or(0, |i| i + 1);
+        let prefix = &self.source[line_start..byte];
+        if prefix.trim().is_empty() { prefix.to_string() } else { self.member_indent.clone() }
+    }
+
+    fn push_text(&mut self, start: usize, text: &str) {
+        let indent = self.indent_at(start);
+        for (i, line) in text.lines().enumerate() {
+            if i == 0 {
+                self.lines.push(format!("{}{}", indent, line));
+            } else {
+                self.lines.push(line.to_string());
+            }
+        }
+    }
+
+    fn push_short(&mut self, start: usize, end: usize) {
+        let text = &self.source[start..end];
+        if text.lines().count() <= MAX_SHORT_LINES {
+            self.push_text(start, text);
+        } else {
+            let first = text.lines().next().unwrap_or_default();
+            self.push_text(start, &format!("{} ...", first.trim_end()));
+        }
+    }
+
+    fn header(&self, start: usize, body: Node) -> &'a str {
+        self.source[start..body.start_byte()].trim_end()
+    }
+
+    // Function bodies, including `const f = () => { ... }` in TypeScript
+    fn function_body<'t>(&self, node: Node<'t>) -> Option<Node<'t>> {
+        if let Some(body) = node.child_by_field_name("body") {
+            return Some(body);
+        }
+        let mut cursor = node.walk();
+        let declarators: Vec<Node<'t>> = node.named_children(&mut cursor).collect();
+        declarators.into_iter()
+            .filter_map(|declarator| declarator.child_by_field_name("value"))
+            .filter(|value| matches!(value.kind(), "arrow_function" | "function_expression" | "function"))
+            .filter_map(|value| value.child_by_field_name("body"))
+            .find(|body| body.kind() == "statement_block")
+    }
+
+    fn elide(&mut self, node: Node, start: usize) {
+        let Some(body) = self.function_body(node) else {
+            self.push_short(start, node.end_byte());
+            return;
+        };
+
+        let header = self.header(start, body);
+        match self.rules.style {
+            BodyStyle::Braces => self.push_text(start, &format!("{} {{ ... }}", header)),
+            BodyStyle::Indented => {
+                self.push_text(start, header);
+                let body_indent = self.indent_at(body.start_byte());
+                // Keep docstrings, they are usually the best summary of a function
+                if let Some(first) = body.named_child(0) {
+                    let is_docstring = first.kind() == "expression_statement"
+                        && first.named_child(0).is_some_and(|c| c.kind() == "string");
+                    if is_docstring {
+                        let source = self.source;
+                        self.push_text(first.start_byte(), &source[first.start_byte()..first.end_byte()]);
+                    }
+                }
+                self.lines.push(format!("{}...", body_indent));
+            }
+        }
+    }
+
+    fn container(&mut self, node: Node, start: usize) {
+        let Some(body) = node.child_by_field_name("body") else {
+            self.push_short(start, node.end_byte());
+            return;
+        };
+
+        let header = self.header(start, body);
+        match self.rules.style {
+            BodyStyle::Braces => {
+                self.push_text(start, &format!("{} {{", header));
+                let indent = self.indent_at(start);
+                let outer = std::mem::replace(&mut self.member_indent, format!("{}    ", indent));
+                self.children(body);
+                self.member_indent = outer;
+                self.lines.push(format!("{}}}", indent));
+            }
+            BodyStyle::Indented => {
+                self.push_text(start, header);
+                let before = self.lines.len();
+                let member_indent = format!("{}    ", self.indent_at(start));
+                let outer = std::mem::replace(&mut self.member_indent, member_indent);
+                self.children(body);
+                self.member_indent = outer;
+                if self.lines.len() == before {
+                    let body_indent = self.indent_at(body.start_byte());
+                    self.lines.push(format!("{}...", body_indent));
+                }
+            }
+        }
+    }
+
+    fn node(&mut self, node: Node, start: usize) {
+        let kind = node.kind();
+        let rules = self.rules;
+
+        if let Some((_, field)) = rules.wrappers.iter().find(|(k, _)| *k == kind) {
+            match node.child_by_field_name(field) {
+                Some(inner) => self.node(inner, start),
+                None => self.push_short(start, node.end_byte()),
+            }
+        } else if rules.transparent.contains(&kind) {
+            self.children(node);
+        } else if rules.elide.contains(&kind) {
+            self.elide(node, start);
+        } else if rules.containers.contains(&kind) {
+            self.container(node, start);
+        } else if rules.keep.contains(&kind) {
+            let source = self.source;
+            self.push_text(start, &source[start..node.end_byte()]);
+        } else if rules.short.contains(&kind) {
+            self.push_short(start, node.end_byte());
+        }
+    }
+
+    fn children(&mut self, parent: Node) {
+        let mut cursor = parent.walk();
+        let children: Vec<Node> = parent.named_children(&mut cursor).collect();
+        for child in children {
+            self.node(child, child.start_byte());
+        }
+    }
+}
+
+// Declarations and signatures of `source` with function bodies elided
+pub fn outline(path: &Path, source: &str) -> Result<String, AppError> {
+    let (language, rules) = grammar_for(path).ok_or_else(|| {
+        let ext = path.extension().map(|e| e.to_string_lossy().into_owned()).unwrap_or_default();
+        format!("No outline support for .{} files", ext)
+    })?;
+
+    let mut parser = Parser::new();
+    parser.set_language(&language).map_err(|e| AppError::from(e.to_string()).context("Could not load the grammar"))?;
+    let tree = parser.parse(source, None).ok_or("Could not parse file")?;
+
+    let mut outliner = Outliner { source, rules, lines: Vec::new(), member_indent: String::new() };
+    outliner.children(tree.root_node());
+    Ok(outliner.lines.join("\n"))
+}
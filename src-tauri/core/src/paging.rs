@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Cursor, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+use crate::highlight::Span;
+use crate::history;
+use crate::memory::{Allocation, MemoryMonitor, Subsystem};
+
+// Lines between remembered byte offsets; a page read skips at most this many
+const CHECKPOINT_INTERVAL: usize = 1024;
+// Largest page a single request can ask for
+const MAX_PAGE_LINES: usize = 5000;
+// Longer lines are cut so one minified line can't blow up a page
+const MAX_LINE_BYTES: usize = 16 * 1024;
+// Indexed sources kept around; the least recently read is dropped first
+const MAX_CACHED_SOURCES: usize = 16;
+const READ_BUFFER: usize = 64 * 1024;
+
+// What the viewer asks to page through, as sent from the frontend
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum PageSource {
+    File { path: String },
+    // A file captured in a history entry; `entry` is relative to the history directory
+    History { entry: String, file: String },
+}
+
+// A source after scope checks, with paths resolved on disk
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Target {
+    File(PathBuf),
+    History { entry: PathBuf, file: String },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Page {
+    // 0-based index of the first line in `lines`
+    pub start: usize,
+    pub lines: Vec<String>,
+    pub total_lines: usize,
+    pub total_bytes: u64,
+    // Indices into `lines` that were cut at MAX_LINE_BYTES
+    pub truncated: Vec<usize>,
+    // Highlighted `lines`, when the viewer asked for a theme
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spans: Option<Vec<Vec<Span>>>,
+}
+
+// Byte offsets of every CHECKPOINT_INTERVAL-th line, so a page can seek
+// close to its first line without keeping the content around
+struct LineIndex {
+    checkpoints: Vec<u64>,
+    total_lines: usize,
+    total_bytes: u64,
+}
+
+enum Body {
+    File(PathBuf),
+    // History snapshots live inside a JSON entry, so the text is kept once extracted
+    Text(Arc<str>),
+}
+
+struct Indexed {
+    body: Arc<Body>,
+    index: Arc<LineIndex>,
+    // Length and modification time of the file the index was built from
+    stamp: (u64, Option<SystemTime>),
+    used: Instant,
+    // Covers extracted snapshot text while it is cached
+    _allocation: Option<Allocation>,
+}
+
+fn stamp(path: &Path) -> Result<(u64, Option<SystemTime>), AppError> {
+    let metadata = fs::metadata(path).map_err(|e| AppError::io(path, e))?;
+    Ok((metadata.len(), metadata.modified().ok()))
+}
+
+fn build_index<R: BufRead>(mut reader: R) -> io::Result<LineIndex> {
+    let mut checkpoints = vec![0];
+    let mut offset = 0u64;
+    let mut newlines = 0usize;
+    let mut ends_with_newline = true;
+
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            break;
+        }
+        for (i, _) in buffer.iter().enumerate().filter(|(_, b)| **b == b'\n') {
+            newlines += 1;
+            if newlines.is_multiple_of(CHECKPOINT_INTERVAL) {
+                checkpoints.push(offset + i as u64 + 1);
+            }
+        }
+        ends_with_newline = buffer.last() == Some(&b'\n');
+        let len = buffer.len();
+        offset += len as u64;
+        reader.consume(len);
+    }
+
+    // A final line without a trailing newline still counts
+    let total_lines = if offset == 0 || ends_with_newline { newlines } else { newlines + 1 };
+    Ok(LineIndex { checkpoints, total_lines, total_bytes: offset })
+}
+
+// Reads one line into `line` without its line ending, keeping at most `limit`
+// bytes. Returns whether it was cut short, or None at the end of the input.
+fn read_line_capped<R: BufRead>(reader: &mut R, line: &mut Vec<u8>, limit: usize) -> io::Result<Option<bool>> {
+    line.clear();
+    let mut read_any = false;
+    let mut truncated = false;
+
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            break;
+        }
+        read_any = true;
+        let (part, used, done) = match buffer.iter().position(|&b| b == b'\n') {
+            Some(i) => (&buffer[..i], i + 1, true),
+            None => (buffer, buffer.len(), false),
+        };
+        let room = limit.saturating_sub(line.len());
+        truncated |= part.len() > room;
+        line.extend_from_slice(&part[..part.len().min(room)]);
+        reader.consume(used);
+        if done {
+            break;
+        }
+    }
+
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    Ok(read_any.then_some(truncated))
+}
+
+fn read_lines<R: BufRead + Seek>(mut reader: R, index: &LineIndex, start: usize, count: usize) -> io::Result<Page> {
+    let start = start.min(index.total_lines);
+    let count = count.min(MAX_PAGE_LINES).min(index.total_lines - start);
+    // Past the last line there may be no checkpoint to seek to, e.g. when the
+    // file ends in an unterminated line that completes a checkpoint interval
+    if count == 0 {
+        return Ok(Page {
+            start,
+            lines: Vec::new(),
+            total_lines: index.total_lines,
+            total_bytes: index.total_bytes,
+            truncated: Vec::new(),
+            spans: None,
+        });
+    }
+    let checkpoint = start / CHECKPOINT_INTERVAL;
+    reader.seek(SeekFrom::Start(index.checkpoints[checkpoint]))?;
+
+    let mut line = Vec::new();
+    for _ in checkpoint * CHECKPOINT_INTERVAL..start {
+        read_line_capped(&mut reader, &mut line, 0)?;
+    }
+
+    let mut lines = Vec::with_capacity(count);
+    let mut truncated = Vec::new();
+    while lines.len() < count {
+        match read_line_capped(&mut reader, &mut line, MAX_LINE_BYTES)? {
+            Some(cut) => {
+                if cut {
+                    truncated.push(lines.len());
+                }
+                lines.push(String::from_utf8_lossy(&line).into_owned());
+            }
+            None => break,
+        }
+    }
+
+    Ok(Page { start, lines, total_lines: index.total_lines, total_bytes: index.total_bytes, truncated, spans: None })
+}
+
+fn open(target: &Target, memory: &MemoryMonitor) -> Result<(Body, LineIndex, Option<Allocation>), AppError> {
+    match target {
+        Target::File(path) => {
+            let file = File::open(path).map_err(|e| AppError::io(path, e))?;
+            let mut reader = BufReader::with_capacity(READ_BUFFER, file);
+            // Text never contains NUL bytes, so one near the start marks a binary file
+            if reader.fill_buf().map_err(|e| AppError::io(path, e))?.contains(&0) {
+                return Err(AppError::Binary { path: path.to_string_lossy().into_owned() });
+            }
+            let index = build_index(reader).map_err(|e| AppError::io(path, e))?;
+            Ok((Body::File(path.clone()), index, None))
+        }
+        Target::History { entry, file } => {
+            let entry = history::load_entry(entry)?;
+            let content = &history::snapshot(&entry, file)?.content;
+            let allocation = memory.reserve(Subsystem::Paging, content.len() as u64)?;
+            let text: Arc<str> = Arc::from(content.as_str());
+            let index = build_index(Cursor::new(text.as_bytes())).map_err(|e| e.to_string())?;
+            Ok((Body::Text(text), index, Some(allocation)))
+        }
+    }
+}
+
+// Line indexes for the sources the viewer is paging through
+#[derive(Default)]
+pub struct PageStore {
+    sources: Mutex<HashMap<Target, Indexed>>,
+}
+
+impl PageStore {
+    // The index for `target`, rebuilt when the file on disk has changed
+    fn indexed(&self, target: &Target, memory: &MemoryMonitor) -> Result<(Arc<Body>, Arc<LineIndex>), AppError> {
+        let current = stamp(match target {
+            Target::File(path) => path,
+            Target::History { entry, .. } => entry,
+        })?;
+
+        {
+            let mut sources = self.sources.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(cached) = sources.get_mut(target).filter(|c| c.stamp == current) {
+                cached.used = Instant::now();
+                return Ok((cached.body.clone(), cached.index.clone()));
+            }
+        }
+
+        // Indexing a large file takes a while, so it happens outside the lock
+        let (body, index, allocation) = open(target, memory)?;
+        let (body, index) = (Arc::new(body), Arc::new(index));
+
+        let mut sources = self.sources.lock().unwrap_or_else(|e| e.into_inner());
+        if sources.len() >= MAX_CACHED_SOURCES && !sources.contains_key(target) {
+            let oldest = sources.iter().min_by_key(|(_, c)| c.used).map(|(t, _)| t.clone());
+            if let Some(oldest) = oldest {
+                sources.remove(&oldest);
+            }
+        }
+        sources.insert(target.clone(), Indexed {
+            body: body.clone(),
+            index: index.clone(),
+            stamp: current,
+            used: Instant::now(),
+            _allocation: allocation,
+        });
+        Ok((body, index))
+    }
+
+    // Up to `count` lines starting at the 0-based line `start`
+    pub fn read(&self, target: &Target, start: usize, count: usize, memory: &MemoryMonitor) -> Result<Page, AppError> {
+        let (body, index) = self.indexed(target, memory)?;
+        match body.as_ref() {
+            Body::File(path) => {
+                let file = File::open(path).map_err(|e| AppError::io(path, e))?;
+                read_lines(BufReader::with_capacity(READ_BUFFER, file), &index, start, count)
+                    .map_err(|e| AppError::io(path, e))
+            }
+            Body::Text(text) => read_lines(Cursor::new(text.as_bytes()), &index, start, count)
+                .map_err(|e| AppError::from(e.to_string())),
+        }
+    }
+}
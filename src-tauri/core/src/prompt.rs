@@ -0,0 +1,523 @@
+use std::{collections::{HashMap, HashSet}, fs, path::Path};
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+use crate::git;
+use crate::guard::{self, GuardAction, GuardFinding, GuardOptions};
+use crate::highlight;
+use crate::outline;
+use crate::redact::{FileRedactions, Redaction, RedactionOptions, Redactor};
+use crate::selection::{self, Selection, SelectionRange};
+use crate::tree::{self, TreeOptions};
+
+const SCRIPT_FIX_TEXT: &str = "send full script with fix";
+// Placeholder in the prompt text replaced with the project tree
+pub const TREE_PLACEHOLDER: &str = "{{tree}}";
+
+fn default_commit_limit() -> usize {
+    git::DEFAULT_COMMIT_LIMIT
+}
+
+// Commit messages and blame for the checked files, read from the local repository
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHistoryOptions {
+    #[serde(default = "default_commit_limit")]
+    pub commit_limit: usize,
+    #[serde(default)]
+    pub blame: bool,
+}
+
+// How much of a whole-file selection goes into the prompt
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum InclusionMode {
+    #[default]
+    Full,
+    // Declarations and signatures only, see outline.rs
+    Outline,
+}
+
+// How file contents are laid out in the prompt
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptFormat {
+    // `Filename:` and `Contents:` lines
+    #[default]
+    Plain,
+    // A heading and a fenced code block per file
+    Markdown,
+    // A `<file path="...">` element per file
+    Xml,
+}
+
+// Everything needed to assemble the clipboard text for one tab
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptRequest {
+    pub root: Option<String>,
+    #[serde(default)]
+    pub prompt: String,
+    #[serde(default)]
+    pub files: Vec<String>,
+    // Per-file inclusion mode keyed by path; files not listed are included in full
+    #[serde(default)]
+    pub modes: HashMap<String, InclusionMode>,
+    #[serde(default)]
+    pub add_script_fix: bool,
+    #[serde(default)]
+    pub git_diff: Option<git::DiffSource>,
+    #[serde(default)]
+    pub git_history: Option<GitHistoryOptions>,
+    // Adds a project tree section; also implied by `{{tree}}` in the prompt
+    #[serde(default)]
+    pub tree: Option<TreeOptions>,
+    #[serde(default)]
+    pub redaction: RedactionOptions,
+    #[serde(default)]
+    pub guard: GuardOptions,
+    // App theme to also render a highlighted HTML copy in
+    #[serde(default)]
+    pub rich_theme: Option<String>,
+    // The settings' default format when left out
+    #[serde(default)]
+    pub format: Option<PromptFormat>,
+    // Name patterns left out of the project tree on top of its own exclusions,
+    // i.e. the ignore rules from the settings
+    #[serde(default)]
+    pub ignore_rules: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuiltPrompt {
+    pub text: String,
+    pub unreadable_files: Vec<String>,
+    // What was replaced with placeholders, per file
+    pub redactions: Vec<FileRedactions>,
+    // Checked files matching the sensitive-file rules
+    pub guard: Vec<GuardFinding>,
+    // Set when a denied file was not confirmed; `text` is empty and nothing may be copied
+    pub needs_confirmation: bool,
+    // `text` as highlighted HTML, when a rich theme was asked for
+    pub html: Option<String>,
+    // Rough size of `text`, to compare with the settings' token budget
+    pub estimated_tokens: usize,
+}
+
+// About four characters per token, which is close enough for a budget warning
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+// A file's part of the prompt, laid out once the format is known
+struct FileBlock {
+    display: String,
+    // What part of the file this is, e.g. `outline` or `lines 1-20 of 80`
+    detail: Option<String>,
+    notes: Vec<String>,
+    contents: String,
+}
+
+enum Section {
+    Text(String),
+    File { path: String, block: FileBlock },
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// A backtick fence longer than any run of backticks in `contents`
+fn fence_for(contents: &str) -> String {
+    let longest = contents.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat((longest + 1).max(3))
+}
+
+impl FileBlock {
+    // Text before and after the contents, so the contents can be highlighted on their own
+    fn frame(&self, path: &str, format: PromptFormat) -> (String, String) {
+        let detail = self.detail.as_ref().map(|d| format!(" ({})", d)).unwrap_or_default();
+        match format {
+            PromptFormat::Plain => {
+                let mut head = format!("Filename: {}{}\n", self.display, detail);
+                for note in &self.notes {
+                    head.push_str(&format!("Note: {}\n", note));
+                }
+                head.push_str("Contents:\n");
+                (head, String::new())
+            }
+            PromptFormat::Markdown => {
+                let mut head = format!("### {}{}\n", self.display, detail);
+                for note in &self.notes {
+                    head.push_str(&format!("> Note: {}\n", note));
+                }
+                let fence = fence_for(&self.contents);
+                let language = Path::new(path).extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+                head.push_str(&format!("{}{}\n", fence, language));
+                (head, format!("\n{}", fence))
+            }
+            PromptFormat::Xml => {
+                let mut head = format!("<file path=\"{}\"", xml_escape(&self.display));
+                if let Some(detail) = &self.detail {
+                    head.push_str(&format!(" part=\"{}\"", xml_escape(detail)));
+                }
+                head.push_str(">\n");
+                for note in &self.notes {
+                    head.push_str(&format!("<note>{}</note>\n", xml_escape(note)));
+                }
+                (head, "\n</file>".to_string())
+            }
+        }
+    }
+}
+
+impl Section {
+    fn text(&self, format: PromptFormat) -> String {
+        match self {
+            Section::Text(text) => text.clone(),
+            Section::File { path, block } => {
+                let (head, tail) = block.frame(path, format);
+                format!("{}{}{}", head, block.contents, tail)
+            }
+        }
+    }
+
+    fn html(&self, format: PromptFormat, theme: &str) -> String {
+        match self {
+            Section::Text(text) => highlight::escape(text),
+            Section::File { path, block } => {
+                let (head, tail) = block.frame(path, format);
+                format!(
+                    "{}{}{}",
+                    highlight::escape(&head),
+                    highlight::html(path, &block.contents, theme),
+                    highlight::escape(&tail)
+                )
+            }
+        }
+    }
+}
+
+fn error_section(display: &str, error: &str, format: PromptFormat) -> String {
+    match format {
+        PromptFormat::Plain => format!("Filename: {}\nError: {}", display, error),
+        PromptFormat::Markdown => format!("### {}\n> Error: {}", display, error),
+        PromptFormat::Xml => format!("<file path=\"{}\" error=\"{}\"/>", xml_escape(display), xml_escape(error)),
+    }
+}
+
+fn read_limited(path: &Path, max_size: u64) -> Result<String, AppError> {
+    let metadata = fs::metadata(path).map_err(|e| AppError::io(path, e))?;
+    if metadata.len() > max_size {
+        let path = Some(path.to_string_lossy().into_owned());
+        return Err(AppError::TooLarge { path, size: metadata.len(), limit: max_size });
+    }
+    fs::read_to_string(path).map_err(|e| AppError::io(path, e))
+}
+
+// Path shown in the prompt: relative to the root with a leading separator
+fn display_path(file: &str, root: Option<&str>) -> String {
+    match root.and_then(|r| file.strip_prefix(r)) {
+        Some(relative) => format!("\\{}", relative.trim_start_matches(['/', '\\'])),
+        None => file.to_string(),
+    }
+}
+
+fn git_section(source: &git::DiffSource, diff: String, redactor: Option<&Redactor>) -> (String, Vec<Redaction>) {
+    if diff.trim().is_empty() {
+        return (format!("Git diff ({}): no changes", source.label()), Vec::new());
+    }
+    let (diff, redactions) = match redactor {
+        Some(redactor) => redactor.redact("", &diff),
+        None => (diff, Vec::new()),
+    };
+    (format!("Git diff ({}):\n```diff\n{}```", source.label(), diff), redactions)
+}
+
+fn commits_section(root: &Path, files: &[String], limit: usize) -> Result<String, AppError> {
+    let commits = git::recent_commits(root, files, limit)?;
+    if commits.is_empty() {
+        return Ok("Recent commits: none".to_string());
+    }
+
+    let mut section = String::from("Recent commits:");
+    for commit in commits {
+        section.push_str(&format!("\n- {} {} {}: {}", commit.short_hash, commit.date, commit.author, commit.subject));
+        for line in commit.body.lines().filter(|l| !l.trim().is_empty()) {
+            section.push_str(&format!("\n    {}", line));
+        }
+    }
+    Ok(section)
+}
+
+fn blame_section(root: &Path, file: &ResolvedFile, display: &str) -> Result<String, AppError> {
+    let mut section = format!("Blame for {}:", display);
+    let blamed = match &file.lines {
+        None => git::blame(root, &file.path, None)?,
+        Some(lines) => {
+            let mut blamed = Vec::new();
+            for range in lines {
+                blamed.extend(git::blame(root, &file.path, Some(*range))?);
+            }
+            blamed
+        }
+    };
+    for range in blamed {
+        let lines = if range.start_line == range.end_line {
+            range.start_line.to_string()
+        } else {
+            format!("{}-{}", range.start_line, range.end_line)
+        };
+        section.push_str(&format!(
+            "\n  {:>9}  {} {} {}: {}",
+            lines, range.short_hash, range.date, range.author, range.summary
+        ));
+    }
+    Ok(section)
+}
+
+fn history_sections(
+    root: Option<&str>,
+    files: &[ResolvedFile],
+    options: &GitHistoryOptions
+) -> Result<Vec<String>, AppError> {
+    let root = root.ok_or("A folder must be open to include git history")?;
+    let root_path = Path::new(root);
+    let mut sections = Vec::new();
+
+    // With no readable files `git log --` would list the whole repository
+    if options.commit_limit > 0 && !files.is_empty() {
+        let paths: Vec<String> = files.iter().map(|f| f.path.clone()).collect();
+        // Like blame, history is left out when git can't provide it, e.g. outside a repository
+        if let Ok(section) = commits_section(root_path, &paths, options.commit_limit) {
+            sections.push(section);
+        }
+    }
+    if options.blame {
+        for file in files {
+            // Untracked files have no blame; leave them out rather than failing the copy
+            if let Ok(section) = blame_section(root_path, file, &display_path(&file.path, Some(root))) {
+                sections.push(section);
+            }
+        }
+    }
+    Ok(sections)
+}
+
+// A checked file after its selections were resolved; `lines` is None for the whole file
+struct ResolvedFile {
+    path: String,
+    lines: Option<Vec<(usize, usize)>>,
+    // Secrets replaced within the included part of the file
+    redactions: Vec<Redaction>,
+}
+
+// Selections grouped per file, keeping the order files were first checked in
+fn group_selections(specs: &[String]) -> Vec<(String, Vec<SelectionRange>)> {
+    let mut grouped: Vec<(String, Vec<SelectionRange>)> = Vec::new();
+    for spec in specs {
+        let Selection { path, range } = Selection::parse(spec);
+        match grouped.iter_mut().find(|(p, _)| *p == path) {
+            Some((_, ranges)) => ranges.push(range),
+            None => grouped.push((path, vec![range])),
+        }
+    }
+    grouped
+}
+
+fn whole_file_block(path: &str, display: String, content: String, mode: InclusionMode) -> FileBlock {
+    let block = |detail: Option<&str>, notes: Vec<String>, contents: String| FileBlock {
+        display,
+        detail: detail.map(str::to_string),
+        notes,
+        contents,
+    };
+    if mode == InclusionMode::Outline {
+        return match outline::outline(Path::new(path), &content) {
+            Ok(outline) => block(Some("outline"), Vec::new(), outline),
+            Err(e) => block(None, vec![format!("{}, included in full", e)], content),
+        };
+    }
+    block(None, Vec::new(), content)
+}
+
+fn file_section(
+    path: &str,
+    ranges: &[SelectionRange],
+    mode: InclusionMode,
+    root: Option<&str>,
+    redactor: Option<&Redactor>,
+    max_file_size: u64
+) -> Result<(FileBlock, ResolvedFile), AppError> {
+    let content = read_limited(Path::new(path), max_file_size)?;
+    let (content, redactions) = match redactor {
+        Some(redactor) => redactor.redact(path, &content),
+        None => (content, Vec::new()),
+    };
+    let display = display_path(path, root);
+
+    if ranges.contains(&SelectionRange::Whole) {
+        let resolved = ResolvedFile { path: path.to_string(), lines: None, redactions };
+        return Ok((whole_file_block(path, display, content, mode), resolved));
+    }
+
+    let (lines, unresolved) = selection::resolve_ranges(&content, ranges, selection::CONTEXT_LINES);
+    if lines.is_empty() {
+        return Err(unresolved.join("; ").into());
+    }
+
+    let block = FileBlock {
+        display,
+        detail: Some(selection::describe_ranges(&lines, content.lines().count())),
+        notes: if unresolved.is_empty() { Vec::new() } else { vec![unresolved.join("; ")] },
+        contents: selection::extract(&content, &lines),
+    };
+    let redactions = redactions
+        .into_iter()
+        .filter(|r| lines.iter().any(|&(start, end)| (start..=end).contains(&r.line)))
+        .collect();
+    Ok((block, ResolvedFile { path: path.to_string(), lines: Some(lines), redactions }))
+}
+
+// What `{{tree}}` expands to, with the prompt's files marked when asked for
+pub fn tree_text(root: Option<&str>, options: &TreeOptions, files: &[String]) -> Result<String, AppError> {
+    let root = root.ok_or("A folder must be open to include the project tree")?;
+    let included: HashSet<String> = files.iter().map(|f| Selection::parse(f).path).collect();
+    tree::render_dir(Path::new(root), options, &included)
+}
+
+pub fn build(request: &PromptRequest, max_file_size: u64) -> Result<BuiltPrompt, AppError> {
+    let root = request.root.as_deref();
+    let grouped = group_selections(&request.files);
+
+    // Files in the diff are guarded like checked ones, since their changes end up in the prompt
+    let mut guarded: Vec<String> = grouped.iter().map(|(path, _)| path.clone()).collect();
+    let diff = match &request.git_diff {
+        Some(source) => {
+            let root = root.ok_or("A folder must be open to include git changes")?;
+            let diff = git::diff(Path::new(root), source)?;
+            for file in git::split_diff(&diff) {
+                let path = Path::new(root).join(&file.path).to_string_lossy().into_owned();
+                if !guarded.contains(&path) {
+                    guarded.push(path);
+                }
+            }
+            Some((source, diff))
+        }
+        None => None,
+    };
+    let guard: Vec<GuardFinding> = guarded
+        .iter()
+        .filter_map(|path| guard::check(path, root, &request.guard))
+        .collect();
+    if guard.iter().any(|f| f.action == GuardAction::Deny && !f.confirmed) {
+        return Ok(BuiltPrompt {
+            text: String::new(),
+            unreadable_files: Vec::new(),
+            redactions: Vec::new(),
+            guard,
+            needs_confirmation: true,
+            html: None,
+            estimated_tokens: 0,
+        });
+    }
+
+    let format = request.format.unwrap_or_default();
+    let mut unreadable_files = Vec::new();
+    let mut sections = Vec::with_capacity(request.files.len() + 1);
+    let mut prompt_text = request.prompt.clone();
+    let redactor = if request.redaction.enabled {
+        Some(Redactor::new(&request.redaction)?)
+    } else {
+        None
+    };
+    let mut redactions = Vec::new();
+
+    let inline_tree = prompt_text.contains(TREE_PLACEHOLDER);
+    if inline_tree || request.tree.is_some() {
+        let mut options = request.tree.clone().unwrap_or_default();
+        options.exclude.extend(request.ignore_rules.iter().cloned());
+        let tree = tree_text(root, &options, &request.files)?;
+        if inline_tree {
+            prompt_text = prompt_text.replace(TREE_PLACEHOLDER, &tree);
+        } else {
+            sections.push(Section::Text(format!("Project tree:\n```\n{}\n```", tree)));
+        }
+    }
+
+    let mut resolved_files = Vec::new();
+    for (path, ranges) in grouped {
+        let mode = request.modes.get(&path).copied().unwrap_or_default();
+        match file_section(&path, &ranges, mode, root, redactor.as_ref(), max_file_size) {
+            Ok((block, resolved)) => {
+                sections.push(Section::File { path: path.clone(), block });
+                resolved_files.push(resolved);
+            }
+            Err(e) => {
+                sections.push(Section::Text(error_section(&path, &e.to_string(), format)));
+                unreadable_files.push(path);
+            }
+        }
+    }
+
+    for file in &mut resolved_files {
+        if !file.redactions.is_empty() {
+            redactions.push(FileRedactions {
+                path: file.path.clone(),
+                redactions: std::mem::take(&mut file.redactions),
+            });
+        }
+    }
+
+    if let Some((source, diff)) = diff {
+        let (section, diff_redactions) = git_section(source, diff, redactor.as_ref());
+        sections.push(Section::Text(section));
+        if !diff_redactions.is_empty() {
+            redactions.push(FileRedactions {
+                path: format!("git diff ({})", source.label()),
+                redactions: diff_redactions,
+            });
+        }
+    }
+    if let Some(options) = &request.git_history {
+        sections.extend(history_sections(root, &resolved_files, options)?.into_iter().map(Section::Text));
+    }
+
+    let mut text = String::new();
+    if !prompt_text.trim().is_empty() {
+        text.push_str(&format!("Prompt: {}\n\n", prompt_text));
+    }
+    let rendered: Vec<String> = sections.iter().map(|section| section.text(format)).collect();
+    text.push_str(&rendered.join("\n\n"));
+    if request.add_script_fix {
+        text.push('\n');
+        text.push_str(SCRIPT_FIX_TEXT);
+    }
+
+    let html = request.rich_theme.as_deref()
+        .map(|theme| prompt_html(&prompt_text, &sections, format, request.add_script_fix, theme));
+    let estimated_tokens = estimate_tokens(&text);
+
+    Ok(BuiltPrompt { text, unreadable_files, redactions, guard, needs_confirmation: false, html, estimated_tokens })
+}
+
+// The same layout as the plain text, with file contents highlighted by their path
+fn prompt_html(
+    prompt_text: &str,
+    sections: &[Section],
+    format: PromptFormat,
+    add_script_fix: bool,
+    theme: &str
+) -> String {
+    let mut body = String::new();
+    if !prompt_text.trim().is_empty() {
+        body.push_str(&highlight::escape(&format!("Prompt: {}\n\n", prompt_text)));
+    }
+    let sections: Vec<String> = sections.iter().map(|section| section.html(format, theme)).collect();
+    body.push_str(&sections.join("\n\n"));
+    if add_script_fix {
+        body.push('\n');
+        body.push_str(&highlight::escape(SCRIPT_FIX_TEXT));
+    }
+    highlight::document(&body, theme)
+}
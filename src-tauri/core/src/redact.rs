@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::path::Path;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+
+// Random-looking tokens shorter than this are left alone
+const MIN_ENTROPY_TOKEN: usize = 32;
+// Bits per character; hex tops out at 4.0, random base64 sits around 5
+const ENTROPY_THRESHOLD: f64 = 4.5;
+
+// A regex detector; when it has a `secret` group only that part is replaced
+const BUILTIN_DETECTORS: &[(&str, &str)] = &[
+    (
+        "private-key",
+        r"-----BEGIN (?:[A-Z0-9]+ )*PRIVATE KEY-----[\s\S]*?-----END (?:[A-Z0-9]+ )*PRIVATE KEY-----",
+    ),
+    ("aws-access-key", r"\b(?:AKIA|ASIA|AGPA|AIDA|AROA|AIPA|ANPA|ANVA)[0-9A-Z]{16}\b"),
+    (
+        "aws-secret-key",
+        r#"(?i)aws_?secret_?(?:access_?)?key["']?\s*[:=]\s*["']?(?P<secret>[A-Za-z0-9/+=]{40})\b"#,
+    ),
+    ("jwt", r"\beyJ[A-Za-z0-9_-]{8,}\.eyJ[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,}"),
+];
+
+const ENV_ASSIGNMENT: &str = r#"(?m)^[ \t]*(?:export[ \t]+)?[A-Za-z_][A-Za-z0-9_.]*[ \t]*=[ \t]*(?P<secret>[^\s#][^\r\n]*?)[ \t]*$"#;
+const ENTROPY_TOKEN: &str = r"[A-Za-z0-9+/=_-]{32,}";
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CustomPattern {
+    pub name: String,
+    pub pattern: String,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionOptions {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    // Flags long random-looking tokens that no other detector recognises
+    #[serde(default = "default_true")]
+    pub high_entropy: bool,
+    // Extra user-defined detectors, reported under their own name
+    #[serde(default)]
+    pub patterns: Vec<CustomPattern>,
+}
+
+impl Default for RedactionOptions {
+    fn default() -> Self {
+        Self { enabled: true, high_entropy: true, patterns: Vec::new() }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Redaction {
+    pub kind: String,
+    // 1-based line in the original text
+    pub line: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRedactions {
+    pub path: String,
+    pub redactions: Vec<Redaction>,
+}
+
+pub struct Redactor {
+    detectors: Vec<(String, Regex)>,
+    env_assignment: Regex,
+    entropy_token: Option<Regex>,
+}
+
+struct Match {
+    start: usize,
+    end: usize,
+    kind: String,
+}
+
+pub fn placeholder(kind: &str) -> String {
+    format!("[REDACTED:{}]", kind)
+}
+
+// `.env`, `.env.local`, `production.env` and the like
+fn is_env_file(path: &str) -> bool {
+    let name = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    name == ".env" || name.starts_with(".env.") || name.ends_with(".env")
+}
+
+fn shannon_entropy(token: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for c in token.chars() {
+        *counts.entry(c).or_default() += 1;
+    }
+    let len = token.chars().count() as f64;
+    counts.values().map(|&n| {
+        let p = n as f64 / len;
+        -p * p.log2()
+    }).sum()
+}
+
+fn looks_random(token: &str) -> bool {
+    token.len() >= MIN_ENTROPY_TOKEN
+        && token.chars().any(|c| c.is_ascii_digit())
+        && token.chars().any(|c| c.is_ascii_alphabetic())
+        && shannon_entropy(token) >= ENTROPY_THRESHOLD
+}
+
+fn secret_span(regex: &Regex, caps: &regex::Captures) -> Option<(usize, usize)> {
+    let found = regex.capture_names().flatten().any(|n| n == "secret");
+    let m = if found { caps.name("secret")? } else { caps.get(0)? };
+    (!m.as_str().is_empty()).then_some((m.start(), m.end()))
+}
+
+impl Redactor {
+    pub fn new(options: &RedactionOptions) -> Result<Redactor, AppError> {
+        let mut detectors = Vec::with_capacity(BUILTIN_DETECTORS.len() + options.patterns.len());
+        for (kind, pattern) in BUILTIN_DETECTORS {
+            detectors.push((kind.to_string(), Regex::new(pattern).map_err(|e| e.to_string())?));
+        }
+        for custom in &options.patterns {
+            let regex = Regex::new(&custom.pattern).map_err(|e| AppError::Other {
+                message: e.to_string(),
+                context: Some(format!("Invalid redaction pattern {}", custom.name)),
+            })?;
+            detectors.push((custom.name.clone(), regex));
+        }
+
+        Ok(Redactor {
+            detectors,
+            env_assignment: Regex::new(ENV_ASSIGNMENT).map_err(|e| e.to_string())?,
+            entropy_token: if options.high_entropy {
+                Some(Regex::new(ENTROPY_TOKEN).map_err(|e| e.to_string())?)
+            } else {
+                None
+            },
+        })
+    }
+
+    fn find(&self, path: &str, text: &str) -> Vec<Match> {
+        let mut found = Vec::new();
+        for (kind, regex) in &self.detectors {
+            for caps in regex.captures_iter(text) {
+                if let Some((start, end)) = secret_span(regex, &caps) {
+                    found.push(Match { start, end, kind: kind.clone() });
+                }
+            }
+        }
+        if is_env_file(path) {
+            for caps in self.env_assignment.captures_iter(text) {
+                if let Some((start, end)) = secret_span(&self.env_assignment, &caps) {
+                    found.push(Match { start, end, kind: "env-value".to_string() });
+                }
+            }
+        }
+        if let Some(regex) = &self.entropy_token {
+            for m in regex.find_iter(text).filter(|m| looks_random(m.as_str())) {
+                found.push(Match { start: m.start(), end: m.end(), kind: "high-entropy".to_string() });
+            }
+        }
+
+        // Earliest first, longest first on ties; later overlapping matches are dropped
+        found.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+        let mut kept: Vec<Match> = Vec::with_capacity(found.len());
+        for m in found {
+            if kept.last().is_none_or(|last| m.start >= last.end) {
+                kept.push(m);
+            }
+        }
+        kept
+    }
+
+    // Replaces secrets in `text` with placeholders. Newlines inside a match
+    // are kept so line numbers, selections and blame still line up.
+    pub fn redact(&self, path: &str, text: &str) -> (String, Vec<Redaction>) {
+        let matches = self.find(path, text);
+        if matches.is_empty() {
+            return (text.to_string(), Vec::new());
+        }
+
+        let mut output = String::with_capacity(text.len());
+        let mut redactions = Vec::with_capacity(matches.len());
+        let mut last = 0;
+        let mut line = 1;
+        for m in matches {
+            line += text[last..m.start].matches('\n').count();
+            output.push_str(&text[last..m.start]);
+            output.push_str(&placeholder(&m.kind));
+            let newlines = text[m.start..m.end].matches('\n').count();
+            output.push_str(&"\n".repeat(newlines));
+            redactions.push(Redaction { kind: m.kind, line });
+            line += newlines;
+            last = m.end;
+        }
+        output.push_str(&text[last..]);
+        (output, redactions)
+    }
+}
@@ -0,0 +1,238 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::error::AppError;
+
+// Resolves `..` and symlinks, so a path that points outside a root through
+// either is compared by where it really lives
+pub fn canonical(path: &Path) -> Result<PathBuf, AppError> {
+    fs::canonicalize(path).map_err(|e| AppError::io(path, e))
+}
+
+// Roots that may not exist yet, such as a fresh app data directory
+fn canonical_or_self(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// What the app keeps below the app data directory. The directory itself is
+// shared with other applications (the platform data directory when the app
+// has no bundle identifier), so only these are readable.
+pub const APP_DIRS: [&str; 2] = ["prompt-copy", "settings"];
+
+// Folders the user opened through the folder picker; reads are limited to
+// these plus the app's own folders in the app data directory
+#[derive(Default)]
+pub struct Scope {
+    roots: Mutex<Vec<PathBuf>>,
+}
+
+impl Scope {
+    pub fn allow_root(&self, path: &Path) -> Result<(), AppError> {
+        let root = canonical(path)?;
+        let mut roots = self.roots.lock().unwrap_or_else(|e| e.into_inner());
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+        Ok(())
+    }
+
+    // The canonical form of `path` if it may be read
+    pub fn check_read(&self, path: &Path, app_dir: &Path) -> Result<PathBuf, AppError> {
+        let resolved = canonical(path)?;
+        if APP_DIRS.iter().any(|dir| resolved.starts_with(canonical_or_self(&app_dir.join(dir)))) {
+            return Ok(resolved);
+        }
+        let roots = self.roots.lock().unwrap_or_else(|e| e.into_inner());
+        if roots.iter().any(|root| resolved.starts_with(root)) {
+            return Ok(resolved);
+        }
+        Err(AppError::OutsideScope { path: path.to_string_lossy().into_owned() })
+    }
+}
+
+// The canonical form of `path` if it is a file below `history_dir`
+pub fn check_history_file(path: &Path, history_dir: &Path) -> Result<PathBuf, AppError> {
+    let denied = || AppError::OutsideHistory { path: path.to_string_lossy().into_owned() };
+    let resolved = canonical(path)?;
+    let history_dir = canonical(history_dir).map_err(|_| denied())?;
+    if resolved == history_dir || !resolved.starts_with(&history_dir) || !resolved.is_file() {
+        return Err(denied());
+    }
+    Ok(resolved)
+}
+
+fn invalid(path: &str, reason: &str) -> AppError {
+    AppError::InvalidPath { path: path.to_string(), reason: reason.to_string() }
+}
+
+// Joins a caller-supplied relative path onto `base` without touching the disk.
+// Both `/` and `\` separate segments on every platform, so a Windows-style
+// path cannot sneak through on Unix and the other way around.
+pub fn join_relative(base: &Path, relative: &str) -> Result<PathBuf, AppError> {
+    if relative.trim().is_empty() {
+        return Err(invalid(relative, "path is empty"));
+    }
+    if relative.contains('\0') {
+        return Err(invalid(relative, "path contains a NUL byte"));
+    }
+    if relative.starts_with(['/', '\\']) || Path::new(relative).has_root() {
+        return Err(invalid(relative, "absolute paths are not allowed"));
+    }
+    // Drive letters (`C:`, `C:foo`), verbatim prefixes and NTFS alternate streams
+    if relative.contains(':') {
+        return Err(invalid(relative, "drive and stream syntax is not allowed"));
+    }
+
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in relative.split(['/', '\\']) {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                if segments.pop().is_none() {
+                    return Err(invalid(relative, "path escapes the app data directory"));
+                }
+            }
+            // Windows drops trailing dots and spaces, so `.. ` and `...` could alias `..`
+            _ if segment.ends_with(['.', ' ']) => {
+                return Err(invalid(relative, "segments may not end with a dot or space"));
+            }
+            _ => segments.push(segment),
+        }
+    }
+    if segments.is_empty() {
+        return Err(invalid(relative, "path does not name anything below the app data directory"));
+    }
+
+    Ok(segments.iter().fold(base.to_path_buf(), |path, segment| path.join(segment)))
+}
+
+// `relative` resolved below `app_dir`, also refusing paths whose existing part
+// leaves the directory through a symlink
+pub fn app_data_path(app_dir: &Path, relative: &str) -> Result<PathBuf, AppError> {
+    let joined = join_relative(app_dir, relative)?;
+    let existing = joined.ancestors().find(|p| p.exists());
+    if let Some(existing) = existing {
+        if !canonical(existing)?.starts_with(canonical_or_self(app_dir)) {
+            return Err(invalid(relative, "path escapes the app data directory"));
+        }
+    }
+    Ok(joined)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> PathBuf {
+        PathBuf::from("app")
+    }
+
+    fn rejects(relative: &str) {
+        assert!(
+            matches!(join_relative(&base(), relative), Err(AppError::InvalidPath { .. })),
+            "expected {:?} to be rejected",
+            relative
+        );
+    }
+
+    #[test]
+    fn joins_plain_relative_paths() {
+        let joined = join_relative(&base(), "prompt-copy/history/2024/01/02/1.json").unwrap();
+        assert_eq!(joined, base().join("prompt-copy").join("history").join("2024").join("01").join("02").join("1.json"));
+    }
+
+    #[test]
+    fn normalizes_dots_and_separators() {
+        let expected = base().join("settings").join("theme.json");
+        assert_eq!(join_relative(&base(), "./settings//theme.json").unwrap(), expected);
+        assert_eq!(join_relative(&base(), "history/../settings/theme.json").unwrap(), expected);
+        assert_eq!(join_relative(&base(), "settings\\theme.json").unwrap(), expected);
+        assert_eq!(join_relative(&base(), "settings/theme.json/").unwrap(), expected);
+    }
+
+    #[test]
+    fn rejects_parent_escapes() {
+        rejects("..");
+        rejects("../secret.json");
+        rejects("history/../../secret.json");
+        rejects("a/b/../../../c");
+        rejects("..\\..\\Windows\\win.ini");
+        rejects("history\\..\\..\\x");
+    }
+
+    #[test]
+    fn rejects_unix_absolute_paths() {
+        rejects("/etc/passwd");
+        rejects("//server/share");
+    }
+
+    #[test]
+    fn rejects_windows_absolute_and_prefixed_paths() {
+        rejects("C:\\Windows\\System32");
+        rejects("c:/Windows");
+        rejects("C:relative.json");
+        rejects("\\Windows");
+        rejects("\\\\server\\share\\file");
+        rejects("\\\\?\\C:\\file");
+        rejects("\\\\.\\pipe\\name");
+    }
+
+    #[test]
+    fn rejects_windows_aliases_of_parent() {
+        rejects(".. /secret.json");
+        rejects(".../secret.json");
+        rejects("history/... ");
+        rejects("settings/theme.json:stream");
+    }
+
+    #[test]
+    fn rejects_empty_and_nul() {
+        rejects("");
+        rejects("   ");
+        rejects(".");
+        rejects("./");
+        rejects("settings/\0theme.json");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlink_escapes() {
+        let dir = std::env::temp_dir().join(format!("scope-test-{}", std::process::id()));
+        let app_dir = dir.join("app");
+        let outside = dir.join("outside");
+        fs::create_dir_all(&app_dir).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, app_dir.join("link")).unwrap();
+
+        let result = app_data_path(&app_dir, "link/file.json");
+        let inside = app_data_path(&app_dir, "history/file.json");
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(result, Err(AppError::InvalidPath { .. })));
+        assert_eq!(inside.unwrap(), app_dir.join("history").join("file.json"));
+    }
+
+    #[test]
+    fn only_the_apps_own_folders_in_the_data_dir_are_readable() {
+        let dir = std::env::temp_dir().join(format!("scope-read-test-{}", std::process::id()));
+        let write = |relative: &str| {
+            let path = dir.join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "x").unwrap();
+            path
+        };
+        let history = write("prompt-copy/history/1.json");
+        let settings = write("settings/settings.json");
+        let sibling = write("other-app/Cookies");
+        let top_level = write("token.json");
+
+        let scope = Scope::default();
+        let results = [&history, &settings, &sibling, &top_level].map(|path| scope.check_read(path, &dir));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(results[0].is_ok());
+        assert!(results[1].is_ok());
+        assert!(matches!(results[2], Err(AppError::OutsideScope { .. })));
+        assert!(matches!(results[3], Err(AppError::OutsideScope { .. })));
+    }
+}
@@ -0,0 +1,192 @@
+use std::path::Path;
+
+// Lines of surrounding context included around each partial selection
+pub const CONTEXT_LINES: usize = 3;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SelectionRange {
+    Whole,
+    // Inclusive, 1-based
+    Lines { start: usize, end: usize },
+    Symbol(String),
+}
+
+// A checked entry: a whole file, `path:120-200`, `path:42` or `path#symbol`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Selection {
+    pub path: String,
+    pub range: SelectionRange,
+}
+
+fn parse_line_range(spec: &str) -> Option<(usize, usize)> {
+    let (start, end) = match spec.split_once('-') {
+        Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+        None => {
+            let line = spec.parse().ok()?;
+            (line, line)
+        }
+    };
+    (start >= 1 && start <= end).then_some((start, end))
+}
+
+fn is_symbol_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '$' | '.' | ':'))
+}
+
+impl Selection {
+    pub fn parse(spec: &str) -> Selection {
+        // A real file always wins, so odd names like `notes:1` still work
+        if Path::new(spec).is_file() {
+            return Selection { path: spec.to_string(), range: SelectionRange::Whole };
+        }
+
+        if let Some((path, lines)) = spec.rsplit_once(':') {
+            if let Some((start, end)) = parse_line_range(lines) {
+                return Selection {
+                    path: path.to_string(),
+                    range: SelectionRange::Lines { start, end },
+                };
+            }
+        }
+
+        if let Some((path, symbol)) = spec.rsplit_once('#') {
+            if is_symbol_name(symbol) {
+                return Selection {
+                    path: path.to_string(),
+                    range: SelectionRange::Symbol(symbol.to_string()),
+                };
+            }
+        }
+
+        Selection { path: spec.to_string(), range: SelectionRange::Whole }
+    }
+}
+
+const DECLARATION_KEYWORDS: &[&str] = &[
+    "fn", "struct", "enum", "trait", "impl", "mod", "type", "const", "static",
+    "class", "interface", "function", "def", "func", "let", "var",
+];
+
+// Whether `line` declares `name`, e.g. `pub fn name(`, `class name:`, `const name =`
+fn declares(line: &str, name: &str) -> bool {
+    let words: Vec<&str> = line
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .filter(|w| !w.is_empty())
+        .collect();
+    words.windows(2).any(|pair| DECLARATION_KEYWORDS.contains(&pair[0]) && pair[1] == name)
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+// End of a declaration starting at `start`: matching braces when the body
+// uses them, otherwise the indented block below it (Python-style)
+fn block_end(lines: &[&str], start: usize) -> usize {
+    let mut depth = 0i64;
+    let mut opened = false;
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        for c in line.chars() {
+            match c {
+                '{' => {
+                    depth += 1;
+                    opened = true;
+                }
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        if opened && depth <= 0 {
+            return i;
+        }
+        // Declarations without a body, e.g. `struct Unit;` or `type A = B;`
+        if !opened && line.trim_end().ends_with(';') {
+            return i;
+        }
+        if !opened && line.trim_end().ends_with(':') {
+            let base = indentation(lines[start]);
+            let mut end = i;
+            for (j, next) in lines.iter().enumerate().skip(i + 1) {
+                if next.trim().is_empty() {
+                    continue;
+                }
+                if indentation(next) <= base {
+                    break;
+                }
+                end = j;
+            }
+            return end;
+        }
+    }
+    lines.len().saturating_sub(1)
+}
+
+// Finds a symbol by name using simple declaration matching. `Outer.inner`
+// and `Outer::inner` look for `inner` after the declaration of `Outer`.
+pub fn find_symbol(content: &str, symbol: &str) -> Option<(usize, usize)> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut search_from = 0;
+    let mut parts = symbol.split(['.', ':']).filter(|p| !p.is_empty()).peekable();
+
+    while let Some(part) = parts.next() {
+        let start = (search_from..lines.len()).find(|&i| declares(lines[i], part))?;
+        if parts.peek().is_none() {
+            return Some((start + 1, block_end(&lines, start) + 1));
+        }
+        search_from = start + 1;
+    }
+    None
+}
+
+// Resolves selections against the file contents into 1-based inclusive line
+// ranges, widened by `context` lines and merged where they overlap. Ranges
+// that cannot be resolved are reported separately so the rest still apply.
+pub fn resolve_ranges(content: &str, ranges: &[SelectionRange], context: usize) -> (Vec<(usize, usize)>, Vec<String>) {
+    let total = content.lines().count().max(1);
+    let mut resolved = Vec::with_capacity(ranges.len());
+    let mut unresolved = Vec::new();
+
+    for range in ranges {
+        let (start, end) = match range {
+            SelectionRange::Whole => return (vec![(1, total)], Vec::new()),
+            SelectionRange::Lines { start, .. } if *start > total => {
+                unresolved.push(format!("Line {} is past the end of the file ({} lines)", start, total));
+                continue;
+            }
+            SelectionRange::Lines { start, end } => (*start, (*end).min(total)),
+            SelectionRange::Symbol(name) => match find_symbol(content, name) {
+                Some(lines) => lines,
+                None => {
+                    unresolved.push(format!("Symbol {} not found", name));
+                    continue;
+                }
+            },
+        };
+        resolved.push((start.saturating_sub(context).max(1), (end + context).min(total)));
+    }
+
+    resolved.sort();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(resolved.len());
+    for (start, end) in resolved {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    (merged, unresolved)
+}
+
+// Text of the given ranges, with a marker between ranges that are not adjacent
+pub fn extract(content: &str, ranges: &[(usize, usize)]) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    ranges
+        .iter()
+        .map(|&(start, end)| lines[start - 1..end.min(lines.len())].join("\n"))
+        .collect::<Vec<_>>()
+        .join("\n...\n")
+}
+
+pub fn describe_ranges(ranges: &[(usize, usize)], total: usize) -> String {
+    let parts: Vec<String> = ranges.iter().map(|(s, e)| format!("{}-{}", s, e)).collect();
+    format!("lines {} of {}", parts.join(", "), total)
+}
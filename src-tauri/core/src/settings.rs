@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::api;
+use crate::atomic;
+use crate::error::AppError;
+use crate::guard::GuardRule;
+use crate::layout::{MonitorLayout, FALLBACK_MONITOR};
+use crate::prompt::PromptFormat;
+use crate::redact::CustomPattern;
+
+// Relative to the app data directory
+pub const SETTINGS_DIR: &str = "settings";
+const SETTINGS_FILE: &str = "settings.json";
+// Below the settings directory; unreadable settings files are moved here
+pub const QUARANTINE_DIR: &str = "quarantine";
+// Files written before settings were versioned, read once by the first migration
+const LEGACY_THEME_FILE: &str = "theme.json";
+const LEGACY_REDACTION_FILE: &str = "redaction.json";
+const LEGACY_GUARD_FILE: &str = "guard.json";
+
+pub const CURRENT_VERSION: u32 = 2;
+
+// Each migration takes the settings of version `index` to `index + 1`
+const MIGRATIONS: &[fn(&Path, Value) -> Value] = &[migrate_v0_to_v1, migrate_v1_to_v2];
+
+fn default_theme() -> String {
+    "solarized".to_string()
+}
+
+fn default_shortcuts() -> HashMap<String, String> {
+    [
+        ("copy", "CmdOrCtrl+Enter"),
+        ("newTab", "CmdOrCtrl+T"),
+        ("closeTab", "CmdOrCtrl+W"),
+        ("openFolder", "CmdOrCtrl+O"),
+    ]
+    .into_iter()
+    .map(|(action, accelerator)| (action.to_string(), accelerator.to_string()))
+    .collect()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    pub version: u32,
+    pub theme: String,
+    // Layout of files in copied prompts, unless a request picks its own
+    pub default_format: PromptFormat,
+    // Name patterns hidden from listings and the project tree
+    pub ignore_rules: Vec<String>,
+    // Soft limit on prompt size in estimated tokens, warned about when copying;
+    // None means no limit
+    pub token_budget: Option<usize>,
+    // Window positions, dock side and sizes, keyed by monitor name
+    pub window_layouts: HashMap<String, MonitorLayout>,
+    // Monitor the main window was last on
+    pub last_monitor: Option<String>,
+    // Action name to accelerator, e.g. "copy" -> "CmdOrCtrl+Enter"; the UI
+    // knows copy, newTab, closeTab and openFolder
+    pub shortcuts: HashMap<String, String>,
+    pub redaction_patterns: Vec<CustomPattern>,
+    pub guard_rules: Vec<GuardRule>,
+    // Copy prompts with a highlighted HTML flavor alongside the plain text
+    pub rich_copy: bool,
+    // Serve the local API for editors and scripts, see api.rs
+    pub api_enabled: bool,
+    pub api_port: u16,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: CURRENT_VERSION,
+            theme: default_theme(),
+            default_format: PromptFormat::default(),
+            ignore_rules: Vec::new(),
+            token_budget: None,
+            window_layouts: HashMap::new(),
+            last_monitor: None,
+            shortcuts: default_shortcuts(),
+            redaction_patterns: Vec::new(),
+            guard_rules: Vec::new(),
+            rich_copy: false,
+            api_enabled: false,
+            api_port: api::DEFAULT_PORT,
+        }
+    }
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+// Version 0 is the loose theme.json, redaction.json and guard.json files
+fn migrate_v0_to_v1(dir: &Path, mut settings: Value) -> Value {
+    let legacy = [
+        (LEGACY_THEME_FILE, "theme", "theme"),
+        (LEGACY_REDACTION_FILE, "patterns", "redactionPatterns"),
+        (LEGACY_GUARD_FILE, "rules", "guardRules"),
+    ];
+    if let Value::Object(map) = &mut settings {
+        for (file, key, setting) in legacy {
+            if let Some(value) = read_json(&dir.join(file)).and_then(|v| v.get(key).cloned()) {
+                map.entry(setting).or_insert(value);
+            }
+        }
+    }
+    settings
+}
+
+// Version 1 kept a single main window layout; it becomes the layout for
+// monitors that have none of their own
+fn migrate_v1_to_v2(_dir: &Path, mut settings: Value) -> Value {
+    if let Value::Object(map) = &mut settings {
+        if let Some(main) = map.remove("windowLayout").filter(|v| !v.is_null()) {
+            let layouts = map.entry("windowLayouts").or_insert_with(|| serde_json::json!({}));
+            if let Value::Object(layouts) = layouts {
+                layouts.entry(FALLBACK_MONITOR).or_insert_with(|| serde_json::json!({ "main": main }));
+            }
+        }
+    }
+    settings
+}
+
+fn version_of(settings: &Value) -> u32 {
+    settings.get("version").and_then(Value::as_u64).unwrap_or(0) as u32
+}
+
+fn too_new(version: u32) -> AppError {
+    let message = format!("Settings version {} is newer than this app supports ({})", version, CURRENT_VERSION);
+    AppError::from(message).context("settings")
+}
+
+// Applies every migration between the stored version and the current one
+pub fn migrate(dir: &Path, mut settings: Value) -> Result<Value, AppError> {
+    let from = version_of(&settings);
+    if from > CURRENT_VERSION {
+        return Err(too_new(from));
+    }
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+        settings = migration(dir, settings);
+        if let Value::Object(map) = &mut settings {
+            map.insert("version".to_string(), Value::from(version as u32 + 1));
+        }
+    }
+    Ok(settings)
+}
+
+// Objects are merged key by key, anything else replaces the current value
+fn merge(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                match target.get_mut(&key) {
+                    Some(existing) if existing.is_object() && value.is_object() => merge(existing, value),
+                    _ => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+// Stored settings brought up to the current version, and whether that changed them
+fn read_stored(dir: &Path, stored: Value) -> Result<(Settings, bool), AppError> {
+    let migrated = version_of(&stored) < CURRENT_VERSION;
+    let settings = serde_json::from_value(migrate(dir, stored)?).map_err(invalid)?;
+    Ok((settings, migrated))
+}
+
+// Settings that don't match the expected shape
+fn invalid(e: serde_json::Error) -> AppError {
+    AppError::from(e.to_string()).context("settings")
+}
+
+// Moves an unreadable settings file aside, as history does with corrupt entries
+fn quarantine(dir: &Path, path: &Path) -> Result<PathBuf, AppError> {
+    let quarantine_dir = dir.join(QUARANTINE_DIR);
+    fs::create_dir_all(&quarantine_dir).map_err(|e| AppError::io(&quarantine_dir, e))?;
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or_default();
+    let target = quarantine_dir.join(format!("settings-{}.json", stamp));
+    fs::rename(path, &target).map_err(|e| AppError::io(path, e))?;
+    Ok(target)
+}
+
+// Serializes reads and writes of settings.json across windows
+#[derive(Default)]
+pub struct SettingsStore {
+    lock: Mutex<()>,
+}
+
+impl SettingsStore {
+    // A corrupt or mistyped file is quarantined and the settings start over,
+    // so one bad write can't break every later load and update
+    fn load_unlocked(&self, dir: &Path) -> Result<Settings, AppError> {
+        let path = dir.join(SETTINGS_FILE);
+        let fresh = || serde_json::json!({ "version": 0 });
+        let stored = fs::read_to_string(&path).ok()
+            .map(|content| serde_json::from_str::<Value>(&content).map_err(invalid));
+
+        let (settings, changed) = match stored {
+            None => read_stored(dir, fresh())?,
+            // Left in place for the newer app that wrote it; this one runs on defaults
+            Some(Ok(stored)) if version_of(&stored) > CURRENT_VERSION => {
+                eprintln!("Ignoring {}: {}", path.display(), too_new(version_of(&stored)));
+                (Settings::default(), false)
+            }
+            Some(stored) => match stored.and_then(|stored| read_stored(dir, stored)) {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    eprintln!("Quarantining unreadable settings {}: {}", path.display(), e);
+                    quarantine(dir, &path)?;
+                    (read_stored(dir, fresh())?.0, true)
+                }
+            },
+        };
+        if changed {
+            self.save_unlocked(dir, &settings)?;
+        }
+        Ok(settings)
+    }
+
+    fn save_unlocked(&self, dir: &Path, settings: &Settings) -> Result<(), AppError> {
+        fs::create_dir_all(dir).map_err(|e| AppError::io(dir, e))?;
+        let content = serde_json::to_string_pretty(settings).map_err(invalid)?;
+        let path = dir.join(SETTINGS_FILE);
+        atomic::write(&path, content.as_bytes()).map_err(|e| AppError::io(&path, e))
+    }
+
+    pub fn load(&self, dir: &Path) -> Result<Settings, AppError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.load_unlocked(dir)
+    }
+
+    // Merges `patch` into the stored settings; the result must still be valid.
+    // Settings written by a newer app are never overwritten.
+    pub fn update(&self, dir: &Path, patch: Value) -> Result<Settings, AppError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let stored_version = read_json(&dir.join(SETTINGS_FILE)).map(|stored| version_of(&stored)).unwrap_or(0);
+        if stored_version > CURRENT_VERSION {
+            return Err(too_new(stored_version));
+        }
+        let mut current = serde_json::to_value(self.load_unlocked(dir)?).map_err(invalid)?;
+        merge(&mut current, patch);
+        let mut settings: Settings = serde_json::from_value(current).map_err(invalid)?;
+        settings.version = CURRENT_VERSION;
+        self.save_unlocked(dir, &settings)?;
+        Ok(settings)
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use crate::error::AppError;
+use crate::memory::Allocation;
+
+fn unknown(transfer_id: &str) -> AppError {
+    AppError::from(format!("Unknown or expired transfer {}", transfer_id)).context("transfer")
+}
+
+// Transfers the viewer never completed are dropped after this long
+const TRANSFER_TTL: Duration = Duration::from_secs(300);
+
+// Splits `content` into pieces of at most `max_bytes`, never inside a UTF-8
+// sequence. A piece only runs short of the limit at a character boundary.
+pub fn split_chunks(content: &str, max_bytes: usize) -> Vec<&str> {
+    let max_bytes = max_bytes.max(4);
+    let mut chunks = Vec::with_capacity(content.len() / max_bytes + 1);
+    let mut rest = content;
+    while !rest.is_empty() {
+        let mut end = rest.len().min(max_bytes);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+// Sent once before the chunks so the viewer knows how many to expect
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferStart {
+    pub transfer_id: String,
+    pub total: usize,
+    pub total_bytes: usize,
+    pub file_path: String,
+    pub theme: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Chunk {
+    pub transfer_id: String,
+    pub index: usize,
+    pub total: usize,
+    pub content: String,
+}
+
+struct Transfer {
+    // Label of the viewer window receiving it
+    viewer: String,
+    chunks: Vec<String>,
+    acked: Vec<bool>,
+    started: Instant,
+    // Released together with the chunks
+    _allocation: Allocation,
+}
+
+// Chunks kept until the viewer confirms it has assembled the whole transfer
+#[derive(Default)]
+pub struct TransferStore {
+    transfers: Mutex<HashMap<String, Transfer>>,
+}
+
+fn drop_expired(transfers: &mut HashMap<String, Transfer>) {
+    transfers.retain(|_, t| t.started.elapsed() < TRANSFER_TTL);
+}
+
+impl TransferStore {
+    // `allocation` should cover `content`; it is held until the transfer is finished,
+    // replaced by another one to the same viewer, or expires
+    pub fn start(
+        &self,
+        viewer: &str,
+        content: &str,
+        max_bytes: usize,
+        file_path: &str,
+        theme: Option<String>,
+        allocation: Allocation
+    ) -> TransferStart {
+        let chunks: Vec<String> = split_chunks(content, max_bytes).into_iter().map(String::from).collect();
+        let start = TransferStart {
+            transfer_id: uuid::Uuid::new_v4().to_string(),
+            total: chunks.len(),
+            total_bytes: content.len(),
+            file_path: file_path.to_string(),
+            theme,
+        };
+
+        let mut transfers = self.transfers.lock().unwrap_or_else(|e| e.into_inner());
+        drop_expired(&mut transfers);
+        // The viewer only assembles the latest transfer it was sent
+        transfers.retain(|_, t| t.viewer != viewer);
+        transfers.insert(start.transfer_id.clone(), Transfer {
+            viewer: viewer.to_string(),
+            acked: vec![false; chunks.len()],
+            chunks,
+            started: Instant::now(),
+            _allocation: allocation,
+        });
+        start
+    }
+
+    pub fn chunk(&self, transfer_id: &str, index: usize) -> Result<Chunk, AppError> {
+        let transfers = self.transfers.lock().unwrap_or_else(|e| e.into_inner());
+        let transfer = transfers.get(transfer_id).ok_or_else(|| unknown(transfer_id))?;
+        let content = transfer.chunks.get(index)
+            .ok_or_else(|| AppError::from(format!("Chunk {} out of range", index)).context("transfer"))?;
+        Ok(Chunk {
+            transfer_id: transfer_id.to_string(),
+            index,
+            total: transfer.chunks.len(),
+            content: content.clone(),
+        })
+    }
+
+    // Marks a chunk as received; returns the indices still unacknowledged
+    pub fn ack(&self, transfer_id: &str, index: usize) -> Result<Vec<usize>, AppError> {
+        let mut transfers = self.transfers.lock().unwrap_or_else(|e| e.into_inner());
+        let transfer = transfers.get_mut(transfer_id).ok_or_else(|| unknown(transfer_id))?;
+        if let Some(acked) = transfer.acked.get_mut(index) {
+            *acked = true;
+        }
+        Ok(transfer.acked.iter().enumerate().filter(|(_, a)| !**a).map(|(i, _)| i).collect())
+    }
+
+    // Frees the chunks once the viewer has the full content
+    pub fn finish(&self, transfer_id: &str) {
+        let mut transfers = self.transfers.lock().unwrap_or_else(|e| e.into_inner());
+        transfers.remove(transfer_id);
+        drop_expired(&mut transfers);
+    }
+
+    // Frees whatever was still on its way to a viewer that has closed
+    pub fn finish_viewer(&self, viewer: &str) {
+        self.transfers.lock().unwrap_or_else(|e| e.into_inner()).retain(|_, t| t.viewer != viewer);
+    }
+}
@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use regex::{Captures, Regex};
+use crate::error::AppError;
+
+// Filled in by the prompt builder rather than by the caller, see prompt::TREE_PLACEHOLDER
+const BUILT_INS: &[&str] = &["tree"];
+
+fn placeholder() -> &'static Regex {
+    static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+    PLACEHOLDER.get_or_init(|| Regex::new(r"\{\{([A-Za-z0-9_.-]+)\}\}").unwrap())
+}
+
+// Placeholder names in the order they first appear
+pub fn placeholders(template: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for captures in placeholder().captures_iter(template) {
+        if !names.iter().any(|name| name == &captures[1]) {
+            names.push(captures[1].to_string());
+        }
+    }
+    names
+}
+
+// Fills `{{name}}` placeholders from `vars`; values are inserted as they are,
+// without expanding placeholders inside them. Built-ins are left in place, and
+// any other placeholder without a value is an error so a half-filled prompt
+// is never copied.
+pub fn render(template: &str, vars: &HashMap<String, String>) -> Result<String, AppError> {
+    let missing: Vec<String> = placeholders(template)
+        .into_iter()
+        .filter(|name| !vars.contains_key(name) && !BUILT_INS.contains(&name.as_str()))
+        .collect();
+    if !missing.is_empty() {
+        return Err(AppError::from(format!("No value for {}", missing.join(", "))).context("template"));
+    }
+
+    let rendered = placeholder().replace_all(template, |captures: &Captures| {
+        vars.get(&captures[1]).cloned().unwrap_or_else(|| captures[0].to_string())
+    });
+    Ok(rendered.into_owned())
+}
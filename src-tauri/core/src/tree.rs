@@ -0,0 +1,182 @@
+use std::collections::HashSet;
+use std::path::Path;
+use serde::Deserialize;
+use crate::error::AppError;
+use crate::files::{self, FileInfo, Walk};
+use crate::git::{self, FileStatus};
+
+const DEFAULT_TREE_DEPTH: u32 = 4;
+const DEFAULT_TREE_ENTRIES: usize = 300;
+
+fn default_tree_depth() -> u32 {
+    DEFAULT_TREE_DEPTH
+}
+
+fn default_tree_entries() -> usize {
+    DEFAULT_TREE_ENTRIES
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TreeStyle {
+    // `├── name` branches like the `tree` command
+    #[default]
+    Tree,
+    // Two spaces per level
+    Indented,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeOptions {
+    #[serde(default)]
+    pub style: TreeStyle,
+    #[serde(default = "default_tree_depth")]
+    pub max_depth: u32,
+    #[serde(default = "default_tree_entries")]
+    pub max_entries: usize,
+    // Name patterns to leave out, `*` and `?` wildcards allowed
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    // Show git-ignored entries such as build output
+    #[serde(default)]
+    pub show_ignored: bool,
+    // Append a marker to files that are part of the prompt
+    #[serde(default)]
+    pub mark_included: bool,
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        Self {
+            style: TreeStyle::default(),
+            max_depth: DEFAULT_TREE_DEPTH,
+            max_entries: DEFAULT_TREE_ENTRIES,
+            exclude: Vec::new(),
+            show_ignored: false,
+            mark_included: false,
+        }
+    }
+}
+
+// Shell-style wildcard match on a single file name
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+struct Renderer<'a> {
+    options: &'a TreeOptions,
+    included: &'a HashSet<String>,
+    lines: Vec<String>,
+    rendered: usize,
+}
+
+impl Renderer<'_> {
+    fn visible<'e>(&self, entries: &'e [FileInfo]) -> Vec<&'e FileInfo> {
+        let mut visible: Vec<&FileInfo> = entries
+            .iter()
+            .filter(|e| e.name != ".git")
+            .filter(|e| self.options.show_ignored || e.git_status != Some(FileStatus::Ignored))
+            .filter(|e| !self.options.exclude.iter().any(|p| matches_pattern(p, &e.name)))
+            .collect();
+        // Folders first, matching the file tree in the UI
+        visible.sort_by(|a, b| {
+            b.is_directory.cmp(&a.is_directory)
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        });
+        visible
+    }
+
+    fn label(&self, entry: &FileInfo) -> String {
+        if entry.is_directory {
+            format!("{}/", entry.name)
+        } else if self.options.mark_included && self.included.contains(&entry.path) {
+            format!("{} (included)", entry.name)
+        } else {
+            entry.name.clone()
+        }
+    }
+
+    fn entries(&mut self, entries: &[FileInfo], prefix: &str, depth: u32) {
+        let visible = self.visible(entries);
+        let count = visible.len();
+
+        for (i, entry) in visible.into_iter().enumerate() {
+            let last = i + 1 == count;
+            if self.rendered >= self.options.max_entries {
+                let omitted = format!("... {} more", count - i);
+                self.push(prefix, true, omitted);
+                return;
+            }
+
+            let label = self.label(entry);
+            self.push(prefix, last, label);
+            self.rendered += 1;
+
+            if let Some(children) = entry.children.as_ref().filter(|_| depth < self.options.max_depth) {
+                let child_prefix = match self.options.style {
+                    TreeStyle::Tree => format!("{}{}", prefix, if last { "    " } else { "│   " }),
+                    TreeStyle::Indented => format!("{}  ", prefix),
+                };
+                self.entries(children, &child_prefix, depth + 1);
+            }
+        }
+    }
+
+    fn push(&mut self, prefix: &str, last: bool, label: String) {
+        let line = match self.options.style {
+            TreeStyle::Tree => format!("{}{}{}", prefix, if last { "└── " } else { "├── " }, label),
+            TreeStyle::Indented => format!("{}{}", prefix, label),
+        };
+        self.lines.push(line);
+    }
+}
+
+// Renders a `list_files` result below a root line named `root_name`
+pub fn render(root_name: &str, entries: &[FileInfo], options: &TreeOptions, included: &HashSet<String>) -> String {
+    let mut renderer = Renderer { options, included, lines: vec![format!("{}/", root_name)], rendered: 0 };
+    let prefix = match options.style {
+        TreeStyle::Tree => "",
+        TreeStyle::Indented => "  ",
+    };
+    renderer.entries(entries, prefix, 1);
+    renderer.lines.join("\n")
+}
+
+// Lists `root` deep enough for `options.max_depth` and renders it
+pub fn render_dir(root: &Path, options: &TreeOptions, included: &HashSet<String>) -> Result<String, AppError> {
+    let statuses = git::StatusMap::load(root);
+    let read_depth = options.max_depth.saturating_sub(1);
+    let mut exclude = options.exclude.clone();
+    exclude.push(".git".to_string());
+    let walk = Walk {
+        exclude: &exclude,
+        skip_ignored: !options.show_ignored,
+        max_entries: Some(options.max_entries),
+    };
+    let entries = files::read_dir_filtered(root, read_depth, 0, &statuses, &walk)?;
+    let root_name = root.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| root.to_string_lossy().into_owned());
+    Ok(render(&root_name, &entries, options, included))
+}
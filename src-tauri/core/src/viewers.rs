@@ -0,0 +1,100 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use serde::{Deserialize, Serialize};
+use crate::error::AppError;
+use crate::layout::DockSide;
+
+// The viewer every window shares unless a new one is asked for
+pub const PRIMARY_LABEL: &str = "file-viewer";
+const EXTRA_LABEL_PREFIX: &str = "file-viewer-";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum ViewerMode {
+    // Attached to the main window on the dock side; at most one viewer at a time
+    Docked,
+    // Shares the dock area with the other attached viewers
+    Tiled,
+    // Left wherever the user puts it
+    Floating,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewerInfo {
+    pub label: String,
+    pub title: String,
+    pub mode: ViewerMode,
+}
+
+// What each viewer is told when viewers open, close or change mode
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewerLayout {
+    pub viewers: Vec<ViewerInfo>,
+    pub dock_side: DockSide,
+}
+
+pub fn is_viewer(label: &str) -> bool {
+    label == PRIMARY_LABEL || label.starts_with(EXTRA_LABEL_PREFIX)
+}
+
+// Open viewer windows in the order they were created
+#[derive(Default)]
+pub struct ViewerRegistry {
+    viewers: Mutex<Vec<ViewerInfo>>,
+    next_id: AtomicU64,
+}
+
+impl ViewerRegistry {
+    // A label no open or past viewer has used
+    pub fn new_label(&self) -> String {
+        format!("{}{}", EXTRA_LABEL_PREFIX, self.next_id.fetch_add(1, Ordering::SeqCst) + 1)
+    }
+
+    // Adds the viewer, or updates it if it is already known
+    pub fn register(&self, label: &str, title: &str, mode: ViewerMode) {
+        let mut viewers = self.viewers.lock().unwrap_or_else(|e| e.into_inner());
+        match viewers.iter_mut().find(|v| v.label == label) {
+            Some(viewer) => {
+                viewer.title = title.to_string();
+                viewer.mode = mode;
+            }
+            None => viewers.push(ViewerInfo { label: label.to_string(), title: title.to_string(), mode }),
+        }
+        if mode == ViewerMode::Docked {
+            undock_others(&mut viewers, label);
+        }
+    }
+
+    pub fn set_mode(&self, label: &str, mode: ViewerMode) -> Result<(), AppError> {
+        let mut viewers = self.viewers.lock().unwrap_or_else(|e| e.into_inner());
+        let viewer = viewers.iter_mut()
+            .find(|v| v.label == label)
+            .ok_or_else(|| format!("No viewer named {}", label))?;
+        viewer.mode = mode;
+        if mode == ViewerMode::Docked {
+            undock_others(&mut viewers, label);
+        }
+        Ok(())
+    }
+
+    pub fn remove(&self, label: &str) {
+        self.viewers.lock().unwrap_or_else(|e| e.into_inner()).retain(|v| v.label != label);
+    }
+
+    pub fn get(&self, label: &str) -> Option<ViewerInfo> {
+        self.viewers.lock().unwrap_or_else(|e| e.into_inner()).iter().find(|v| v.label == label).cloned()
+    }
+
+    pub fn list(&self) -> Vec<ViewerInfo> {
+        self.viewers.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+// Docking a viewer moves the one previously docked into the tiles
+fn undock_others(viewers: &mut [ViewerInfo], label: &str) {
+    for viewer in viewers.iter_mut().filter(|v| v.label != label && v.mode == ViewerMode::Docked) {
+        viewer.mode = ViewerMode::Tiled;
+    }
+}
@@ -0,0 +1,129 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use notify::{EventKind, RecursiveMode, Watcher};
+use notify::event::ModifyKind;
+use crate::error::AppError;
+use crate::git;
+
+// No event has been let through yet
+const NEVER: u64 = u64::MAX;
+
+// What the frontend is told about a watched folder
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WatchEvent {
+    Created,
+    Deleted,
+    Modified,
+    // The repository's index changed, e.g. after staging or a checkout
+    GitStatus,
+}
+
+impl WatchEvent {
+    // Name of the window event it is emitted as
+    pub fn name(self) -> &'static str {
+        match self {
+            WatchEvent::Created => "fs-created",
+            WatchEvent::Deleted => "fs-deleted",
+            WatchEvent::Modified => "fs-modified",
+            WatchEvent::GitStatus => "git-status-changed",
+        }
+    }
+}
+
+// Lets at most one event through per `interval` milliseconds
+pub struct Debounce {
+    interval: u64,
+    last: AtomicU64,
+}
+
+impl Debounce {
+    pub fn new(interval: u64) -> Debounce {
+        Debounce { interval, last: AtomicU64::new(NEVER) }
+    }
+
+    // Whether an event at `now` gets through; if so, the interval restarts from it
+    pub fn ready(&self, now: u64) -> bool {
+        self.last
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+                (last == NEVER || now.saturating_sub(last) >= self.interval).then_some(now)
+            })
+            .is_ok()
+    }
+}
+
+// Turns the stream of raw notify events into the few the frontend cares about,
+// with file and git changes debounced separately
+pub struct EventFilter {
+    git_index: Option<PathBuf>,
+    files: Debounce,
+    git: Debounce,
+}
+
+impl EventFilter {
+    pub fn new(git_index: Option<PathBuf>, debounce: u64) -> EventFilter {
+        EventFilter { git_index, files: Debounce::new(debounce), git: Debounce::new(debounce) }
+    }
+
+    pub fn accept(&self, event: &notify::Event, now: u64) -> Option<WatchEvent> {
+        let touches_index = self.git_index.as_ref()
+            .is_some_and(|index| event.paths.iter().any(|p| p == index || p.ends_with(".git/index")));
+        if touches_index {
+            return self.git.ready(now).then_some(WatchEvent::GitStatus);
+        }
+
+        // Other churn inside .git is not interesting to the file tree
+        if event.paths.iter().all(|p| p.components().any(|c| c.as_os_str() == ".git")) {
+            return None;
+        }
+
+        let kind = match event.kind {
+            EventKind::Create(_) => WatchEvent::Created,
+            EventKind::Remove(_) => WatchEvent::Deleted,
+            EventKind::Modify(ModifyKind::Data(_)) => WatchEvent::Modified,
+            _ => return None,
+        };
+        self.files.ready(now).then_some(kind)
+    }
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+// Keeps the I/O codes; running out of inotify watches and the like stay generic
+fn watch_error(path: &Path, e: notify::Error) -> AppError {
+    match e.kind {
+        notify::ErrorKind::Io(e) => AppError::io(path, e),
+        notify::ErrorKind::PathNotFound => AppError::NotFound { path: path.to_string_lossy().into_owned() },
+        _ => AppError::from(e.to_string()).context("Could not watch the folder"),
+    }
+}
+
+// Watches `root` recursively, calling `on_event` with the filtered events until
+// the returned watcher is dropped
+pub fn watch(
+    root: &Path,
+    debounce: u64,
+    on_event: impl Fn(WatchEvent) + Send + 'static
+) -> Result<notify::RecommendedWatcher, AppError> {
+    // Staging, committing and checkouts all rewrite the index
+    let git_index = git::index_path(root).ok();
+    let filter = EventFilter::new(git_index.clone(), debounce);
+
+    let mut watcher = notify::recommended_watcher(move |res: Result<notify::Event, notify::Error>| {
+        if let Some(event) = res.ok().and_then(|event| filter.accept(&event, now_millis())) {
+            on_event(event);
+        }
+    }).map_err(|e| watch_error(root, e))?;
+
+    watcher.watch(root, RecursiveMode::Recursive).map_err(|e| watch_error(root, e))?;
+
+    // The repository may start above the watched folder
+    if let Some(git_dir) = git_index.as_ref().and_then(|index| index.parent()) {
+        if !git_dir.starts_with(root) {
+            watcher.watch(git_dir, RecursiveMode::NonRecursive).map_err(|e| watch_error(git_dir, e))?;
+        }
+    }
+    Ok(watcher)
+}
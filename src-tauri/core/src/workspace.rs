@@ -0,0 +1,149 @@
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::atomic;
+use crate::error::AppError;
+
+// Relative to the app data directory; one `<id>.json` per workspace
+pub const WORKSPACES_DIR: &str = "prompt-copy/workspaces";
+// Which workspaces were open as tabs when the app last ran
+pub const SESSION_FILE: &str = "prompt-copy/session.json";
+
+// A saved root with everything needed to bring its tab back
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Workspace {
+    // Assigned on first save
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub root: String,
+    #[serde(default)]
+    pub checked_files: Vec<String>,
+    #[serde(default)]
+    pub expanded_folders: Vec<String>,
+    #[serde(default)]
+    pub outline_files: Vec<String>,
+    // File tree filter
+    #[serde(default)]
+    pub search_query: String,
+    // Prompt text a new tab for this workspace starts with
+    #[serde(default)]
+    pub default_template: String,
+    // Milliseconds since the epoch
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    // Workspace ids in tab order
+    #[serde(default)]
+    pub open: Vec<String>,
+    #[serde(default)]
+    pub active: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RestoredSession {
+    pub workspaces: Vec<Workspace>,
+    pub active: Option<String>,
+}
+
+// Ids end up in file names, so only uuid-style characters are accepted
+fn check_id(id: &str) -> Result<(), AppError> {
+    let valid = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::InvalidPath { path: id.to_string(), reason: "not a workspace id".to_string() })
+    }
+}
+
+fn invalid(e: serde_json::Error) -> AppError {
+    AppError::from(e.to_string()).context("workspace")
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+pub fn save(dir: &Path, mut workspace: Workspace) -> Result<Workspace, AppError> {
+    if workspace.id.is_empty() {
+        workspace.id = uuid::Uuid::new_v4().to_string();
+    }
+    check_id(&workspace.id)?;
+    if workspace.name.trim().is_empty() {
+        workspace.name = Path::new(&workspace.root)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| workspace.root.clone());
+    }
+    workspace.updated_at = now_millis();
+
+    fs::create_dir_all(dir).map_err(|e| AppError::io(dir, e))?;
+    let content = serde_json::to_string_pretty(&workspace).map_err(invalid)?;
+    let path = dir.join(format!("{}.json", workspace.id));
+    atomic::write(&path, content.as_bytes()).map_err(|e| AppError::io(&path, e))?;
+    Ok(workspace)
+}
+
+pub fn load(dir: &Path, id: &str) -> Result<Workspace, AppError> {
+    check_id(id)?;
+    let path = dir.join(format!("{}.json", id));
+    let content = fs::read_to_string(&path).map_err(|e| AppError::io(&path, e))?;
+    serde_json::from_str(&content).map_err(invalid)
+}
+
+// Saved workspaces by name; unreadable files are skipped
+pub fn list(dir: &Path) -> Result<Vec<Workspace>, AppError> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut workspaces: Vec<Workspace> = fs::read_dir(dir)
+        .map_err(|e| AppError::io(dir, e))?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("json"))
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .filter_map(|content| serde_json::from_str(&content).ok())
+        .collect();
+    workspaces.sort_by_key(|w| w.name.to_lowercase());
+    Ok(workspaces)
+}
+
+pub fn delete(dir: &Path, id: &str) -> Result<(), AppError> {
+    check_id(id)?;
+    let path = dir.join(format!("{}.json", id));
+    fs::remove_file(&path).map_err(|e| AppError::io(&path, e))
+}
+
+pub fn load_session(path: &Path) -> Session {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// The session's workspaces that can still be opened, in tab order. `open_root`
+// is asked for each root, e.g. to add it to the scope. The active workspace is
+// dropped if it did not make it.
+pub fn restore_session(dir: &Path, session: Session, open_root: impl Fn(&Path) -> bool) -> RestoredSession {
+    let workspaces: Vec<Workspace> = session.open
+        .iter()
+        .filter_map(|id| load(dir, id).ok())
+        .filter(|w| open_root(Path::new(&w.root)))
+        .collect();
+    let active = session.active.filter(|id| workspaces.iter().any(|w| &w.id == id));
+    RestoredSession { workspaces, active }
+}
+
+pub fn save_session(path: &Path, session: &Session) -> Result<(), AppError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| AppError::io(parent, e))?;
+    }
+    let content = serde_json::to_string_pretty(session).map_err(invalid)?;
+    atomic::write(path, content.as_bytes()).map_err(|e| AppError::io(path, e))
+}
@@ -0,0 +1,81 @@
+use tauriprompt_lib::diff::{diff_text, DiffLine, FileDiff, LineKind, DEFAULT_CONTEXT};
+
+fn diff(old: &str, new: &str) -> FileDiff {
+    diff_text(old, new, "old", "new", DEFAULT_CONTEXT, false)
+}
+
+fn lines(diff: &FileDiff) -> Vec<(LineKind, Option<usize>, Option<usize>, &str)> {
+    diff.hunks.iter()
+        .flat_map(|h| h.lines.iter())
+        .map(|l: &DiffLine| (l.kind, l.old_line, l.new_line, l.content.as_str()))
+        .collect()
+}
+
+#[test]
+fn insertions_and_deletions_are_numbered_on_their_side() {
+    let inserted = diff("a\nb\n", "a\nx\nb\n");
+    assert_eq!(inserted.hunks.len(), 1);
+    assert_eq!(inserted.hunks[0].header, "@@ -1,2 +1,3 @@");
+    assert_eq!(lines(&inserted), [
+        (LineKind::Context, Some(1), Some(1), "a"),
+        (LineKind::Added, None, Some(2), "x"),
+        (LineKind::Context, Some(2), Some(3), "b"),
+    ]);
+    assert_eq!((inserted.additions, inserted.deletions), (1, 0));
+
+    let deleted = diff("a\nb\nc\n", "a\nc\n");
+    assert_eq!(deleted.hunks[0].header, "@@ -1,3 +1,2 @@");
+    assert_eq!(lines(&deleted)[1], (LineKind::Removed, Some(2), None, "b"));
+    assert_eq!((deleted.additions, deleted.deletions), (0, 1));
+}
+
+#[test]
+fn replaced_lines_mark_the_changed_words() {
+    let replaced = diff_text("let x = 1;\n", "let x = 2;\n", "old", "new", DEFAULT_CONTEXT, true);
+    let changed: Vec<(LineKind, String, String)> = replaced.hunks[0].lines.iter().map(|line| {
+        let spans = line.spans.as_ref().unwrap();
+        let changed = spans.iter().filter(|s| s.changed).map(|s| s.text.as_str()).collect();
+        (line.kind, line.content.clone(), changed)
+    }).collect();
+    assert_eq!(changed, [
+        (LineKind::Removed, "let x = 1;".to_string(), "1;".to_string()),
+        (LineKind::Added, "let x = 2;".to_string(), "2;".to_string()),
+    ]);
+    assert!(diff("a\n", "b\n").hunks[0].lines.iter().all(|l| l.spans.is_none()));
+}
+
+#[test]
+fn distant_changes_get_separate_hunks() {
+    let numbered = |changed: &[usize]| -> String {
+        (1..=20).map(|n| if changed.contains(&n) { format!("changed {}\n", n) } else { format!("{}\n", n) }).collect()
+    };
+    let (old, new) = (numbered(&[]), numbered(&[2, 18]));
+    let diff = diff(&old, &new);
+    let headers: Vec<&str> = diff.hunks.iter().map(|h| h.header.as_str()).collect();
+    assert_eq!(headers, ["@@ -1,5 +1,5 @@", "@@ -15,6 +15,6 @@"]);
+}
+
+#[test]
+fn empty_sides_start_hunks_at_zero() {
+    let same = diff("", "");
+    assert!(same.identical && same.hunks.is_empty() && same.unified.is_empty());
+
+    let added = diff("", "a\nb\n");
+    assert_eq!(added.hunks[0].header, "@@ -0,0 +1,2 @@");
+    assert_eq!(added.additions, 2);
+    let removed = diff("a\nb\n", "");
+    assert_eq!(removed.hunks[0].header, "@@ -1,2 +0,0 @@");
+    assert_eq!(removed.deletions, 2);
+}
+
+#[test]
+fn a_missing_trailing_newline_is_a_change() {
+    let diff = diff("a\nb\n", "a\nb");
+    assert!(!diff.identical);
+    assert_eq!(lines(&diff)[1..], [
+        (LineKind::Removed, Some(2), None, "b"),
+        (LineKind::Added, None, Some(2), "b"),
+    ]);
+    assert!(diff.unified.contains("\\ No newline at end of file"), "{}", diff.unified);
+    assert!(diff.unified.starts_with("--- old\n+++ new\n"), "{}", diff.unified);
+}
@@ -0,0 +1,42 @@
+use std::io;
+use std::path::Path;
+use serde_json::json;
+use tauriprompt_lib::error::AppError;
+
+#[test]
+fn errors_serialize_with_their_code_path_and_context() {
+    let missing = AppError::io(Path::new("/a/b.txt"), io::Error::from(io::ErrorKind::NotFound));
+    assert_eq!(serde_json::to_value(&missing).unwrap(), json!({
+        "code": "notFound",
+        "message": "/a/b.txt does not exist",
+        "path": "/a/b.txt",
+        "context": null,
+    }));
+
+    let failed = AppError::from("exit status 128").context("Could not run git");
+    assert_eq!(serde_json::to_value(&failed).unwrap(), json!({
+        "code": "other",
+        "message": "Could not run git: exit status 128",
+        "path": null,
+        "context": "Could not run git",
+    }));
+
+    let invalid = AppError::InvalidPath { path: "../x".to_string(), reason: "escapes the folder".to_string() };
+    let value = serde_json::to_value(&invalid).unwrap();
+    assert_eq!((&value["code"], &value["path"], &value["context"]), (&json!("invalidPath"), &json!("../x"), &json!("escapes the folder")));
+
+    let large = AppError::TooLarge { path: None, size: 20, limit: 10 };
+    assert_eq!(serde_json::to_value(&large).unwrap()["message"], "Content is too large (20 bytes, the limit is 10)");
+}
+
+#[test]
+fn io_errors_are_sorted_by_kind() {
+    let path = Path::new("/a");
+    let code = |kind| AppError::io(path, io::Error::from(kind)).code();
+    assert_eq!(code(io::ErrorKind::PermissionDenied), "permissionDenied");
+    assert_eq!(code(io::ErrorKind::InvalidData), "binary");
+    assert_eq!(code(io::ErrorKind::TimedOut), "io");
+    // Only generic failures take a context
+    assert!(AppError::from("x").context("y").to_string().starts_with("y: "));
+    assert_eq!(AppError::NotFound { path: "/a".to_string() }.context("y").to_string(), "/a does not exist");
+}
@@ -0,0 +1,100 @@
+use std::path::Path;
+use tauriprompt_lib::git::{self, FileStatus};
+
+#[test]
+fn diffs_are_split_per_file() {
+    let diff = "\
+diff --git a/src/main.rs b/src/main.rs
+index 1111111..2222222 100644
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1 +1 @@
+-fn main() {}
++++ not a header
+diff --git a/.env b/.env
+deleted file mode 100644
+--- a/.env
++++ /dev/null
+@@ -1 +0,0 @@
+-KEY=1
+diff --git a/logo.png b/logo.png
+Binary files a/logo.png and b/logo.png differ
+";
+    let files = git::split_diff(diff);
+    let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, ["src/main.rs", ".env", "logo.png"]);
+    assert!(files[0].text.ends_with("+++ not a header\n"));
+    assert!(files[1].text.starts_with("diff --git a/.env"));
+    assert_eq!(files.iter().map(|f| f.text).collect::<String>(), diff);
+    assert!(git::split_diff("").is_empty());
+}
+
+fn status(records: &[&str]) -> git::StatusMap {
+    git::StatusMap::parse(Path::new("/repo"), "", &(records.join("\0") + "\0"))
+}
+
+#[test]
+fn porcelain_codes_map_to_statuses() {
+    let map = status(&[
+        " M modified.rs", "M  staged.rs", "A  added.rs", "?? new.rs", "UU both.rs", "AA added_twice.rs",
+        "DD deleted_twice.rs", " D removed.rs", "  clean.rs", "!! ignored.log",
+    ]);
+    let get = |path: &str| map.get(&Path::new("/repo").join(path));
+    assert_eq!(get("modified.rs"), Some(FileStatus::Modified));
+    assert_eq!(get("staged.rs"), Some(FileStatus::Modified));
+    assert_eq!(get("added.rs"), Some(FileStatus::Added));
+    assert_eq!(get("new.rs"), Some(FileStatus::Untracked));
+    assert_eq!(get("both.rs"), Some(FileStatus::Conflicted));
+    assert_eq!(get("added_twice.rs"), Some(FileStatus::Conflicted));
+    assert_eq!(get("deleted_twice.rs"), Some(FileStatus::Conflicted));
+    assert_eq!(get("removed.rs"), Some(FileStatus::Modified));
+    assert_eq!(get("clean.rs"), None);
+    assert_eq!(get("ignored.log"), Some(FileStatus::Ignored));
+    assert_eq!(get("untouched.rs"), None);
+}
+
+#[test]
+fn directories_take_the_strongest_status_below_them() {
+    let map = status(&["?? src/a/new.rs", " M src/b/changed.rs", "UU src/b/deep/conflict.rs", "?? docs/new.md"]);
+    let get = |path: &str| map.get(&Path::new("/repo").join(path));
+    assert_eq!(get("src/a"), Some(FileStatus::Untracked));
+    assert_eq!(get("src/b"), Some(FileStatus::Conflicted));
+    assert_eq!(get("src"), Some(FileStatus::Conflicted));
+    assert_eq!(get("docs"), Some(FileStatus::Untracked));
+    assert_eq!(get(""), Some(FileStatus::Conflicted));
+}
+
+#[test]
+fn ignored_directories_cover_their_contents_without_propagating() {
+    let map = status(&["!! target/", "?? src/new.rs"]);
+    let get = |path: &str| map.get(&Path::new("/repo").join(path));
+    assert_eq!(get("target"), Some(FileStatus::Ignored));
+    assert_eq!(get("target/debug/app"), Some(FileStatus::Ignored));
+    // An ignored directory does not mark its parent
+    assert_eq!(get(""), Some(FileStatus::Untracked));
+    assert_eq!(get("src/old.rs"), None);
+}
+
+#[test]
+fn renames_consume_their_source_path() {
+    // With -z the original path follows the record as its own field
+    let map = status(&["R  new_name.rs", "old_name.rs", "C  copy.rs", "original.rs", " M other.rs"]);
+    let get = |path: &str| map.get(&Path::new("/repo").join(path));
+    assert_eq!(get("new_name.rs"), Some(FileStatus::Added));
+    assert_eq!(get("copy.rs"), Some(FileStatus::Added));
+    assert_eq!(get("old_name.rs"), None);
+    assert_eq!(get("original.rs"), None);
+    assert_eq!(get("other.rs"), Some(FileStatus::Modified));
+}
+
+#[test]
+fn subdirectory_roots_drop_the_repository_prefix() {
+    let output = ["?? app/src/new.rs", " M app/main.rs", " M lib/other.rs"].join("\0") + "\0";
+    let map = git::StatusMap::parse(Path::new("/checkout/app"), "app/", &output);
+    assert_eq!(map.get(Path::new("/checkout/app/src/new.rs")), Some(FileStatus::Untracked));
+    assert_eq!(map.get(Path::new("/checkout/app/main.rs")), Some(FileStatus::Modified));
+    assert_eq!(map.get(Path::new("/checkout/app")), Some(FileStatus::Modified));
+    // Outside the opened folder, and nothing above it is marked
+    assert_eq!(map.get(Path::new("/checkout/app/lib/other.rs")), None);
+    assert_eq!(map.get(Path::new("/checkout")), None);
+}
@@ -0,0 +1,67 @@
+use tauriprompt_lib::guard::{self, GuardAction, GuardOptions, GuardRule};
+
+const ROOT: &str = "/project";
+
+fn action(path: &str, options: &GuardOptions) -> Option<GuardAction> {
+    guard::check(&format!("{}/{}", ROOT, path), Some(ROOT), options).map(|finding| finding.action)
+}
+
+fn rules(rules: &[(&str, GuardAction)]) -> GuardOptions {
+    GuardOptions {
+        rules: rules.iter().map(|(pattern, action)| GuardRule { pattern: pattern.to_string(), action: *action }).collect(),
+        ..GuardOptions::default()
+    }
+}
+
+#[test]
+fn default_rules_hold_back_keys_and_env_files_but_not_their_templates() {
+    let options = GuardOptions::default();
+    assert_eq!(action(".env", &options), Some(GuardAction::Deny));
+    assert_eq!(action("config/.env.production", &options), Some(GuardAction::Deny));
+    assert_eq!(action("home/.ssh/id_ed25519", &options), Some(GuardAction::Deny));
+    assert_eq!(action("certs/server.pem", &options), Some(GuardAction::Deny));
+    assert_eq!(action("infra/main.tfstate", &options), Some(GuardAction::Warn));
+    assert_eq!(action("secrets.yaml", &options), Some(GuardAction::Warn));
+    assert_eq!(action(".env.example", &options), None);
+    assert_eq!(action("src/main.rs", &options), None);
+}
+
+#[test]
+fn patterns_match_whatever_the_case() {
+    let options = GuardOptions::default();
+    assert_eq!(action(".ENV", &options), Some(GuardAction::Deny));
+    assert_eq!(action("ID_RSA", &options), Some(GuardAction::Deny));
+    assert_eq!(action("Secrets.PEM", &options), Some(GuardAction::Deny));
+    assert_eq!(action(".Env.Example", &options), None);
+
+    let options = rules(&[("Config/*.YML", GuardAction::Deny)]);
+    assert_eq!(action("config/app.yml", &options), Some(GuardAction::Deny));
+}
+
+#[test]
+fn user_rules_come_first_and_can_exempt_files() {
+    let options = rules(&[("local.env", GuardAction::Allow), ("*.sql", GuardAction::Warn)]);
+    assert_eq!(action("local.env", &options), None);
+    assert_eq!(action("prod.env", &options), Some(GuardAction::Deny));
+    assert_eq!(action("dump.sql", &options), Some(GuardAction::Warn));
+
+    let options = GuardOptions { use_defaults: false, ..rules(&[("*.sql", GuardAction::Deny)]) };
+    assert_eq!(action(".env", &options), None);
+    assert_eq!(action("dump.sql", &options), Some(GuardAction::Deny));
+}
+
+#[test]
+fn patterns_with_a_slash_match_the_root_relative_path() {
+    let options = rules(&[("/deploy/*", GuardAction::Deny)]);
+    assert_eq!(action("deploy/hosts", &options), Some(GuardAction::Deny));
+    assert_eq!(action("src/deploy/hosts", &options), None);
+}
+
+#[test]
+fn confirmed_files_are_reported_as_confirmed() {
+    let path = format!("{}/.env", ROOT);
+    let options = GuardOptions { confirmed: vec![path.clone()], ..GuardOptions::default() };
+    let finding = guard::check(&path, Some(ROOT), &options).unwrap();
+    assert_eq!((finding.action, finding.confirmed, finding.pattern.as_str()), (GuardAction::Deny, true, ".env"));
+    assert!(!guard::check(&format!("{}/.env.local", ROOT), Some(ROOT), &options).unwrap().confirmed);
+}
@@ -0,0 +1,72 @@
+use tauriprompt_lib::highlight::{self, Span};
+
+fn colors(spans: &[Span]) -> usize {
+    let mut colors: Vec<&str> = spans.iter().map(|s| s.color.as_str()).collect();
+    colors.sort();
+    colors.dedup();
+    colors.len()
+}
+
+fn text(spans: &[Span]) -> String {
+    spans.iter().map(|s| s.text.as_str()).collect()
+}
+
+#[test]
+fn languages_are_picked_by_extension() {
+    let line = "fn main() { let x = \"a\"; }";
+    let rust = highlight::lines("src/main.rs", [line], "solarized");
+    assert_eq!(rust.len(), 1);
+    assert_eq!(text(&rust[0]), line);
+    assert!(colors(&rust[0]) > 1, "{:?}", rust);
+
+    // TypeScript has no bundled grammar and borrows JavaScript's
+    let ts = highlight::lines("App.TSX", ["const x = \"a\";"], "solarized");
+    assert!(colors(&ts[0]) > 1, "{:?}", ts);
+}
+
+#[test]
+fn unknown_files_fall_back_to_plain_text() {
+    let plain = highlight::lines("notes.unknown", ["fn main() { let x = \"a\"; }", "", "second"], "solarized");
+    assert_eq!(plain.len(), 3);
+    assert_eq!(plain[0].len(), 1);
+    assert!(plain[1].is_empty());
+    assert_eq!(text(&plain[2]), "second");
+
+    // Without an extension the first line can still name the language
+    let script = highlight::lines("run", ["#!/bin/bash", "echo \"$HOME\""], "solarized");
+    assert!(colors(&script[1]) > 1, "{:?}", script);
+}
+
+#[test]
+fn very_long_lines_are_left_unstyled() {
+    let long = format!("let x = \"{}\";", "a".repeat(5000));
+    let spans = highlight::lines("a.rs", [long.as_str()], "solarized");
+    assert_eq!(spans[0].len(), 1);
+    assert_eq!(spans[0][0].text, long);
+}
+
+#[test]
+fn app_themes_map_to_syntax_themes() {
+    let background = |theme: &str| {
+        let document = highlight::document("", theme);
+        document.split("background:").nth(1).unwrap()[..7].to_string()
+    };
+    assert_eq!(background("light"), "#ffffff");
+    assert_eq!(background("solarized"), "#002b36");
+    assert_ne!(background("ocean"), background("light"));
+    // Unknown themes use the default
+    assert_eq!(background("no-such-theme"), background("solarized"));
+
+    let light = highlight::lines("a.rs", ["fn main() {}"], "light");
+    let dark = highlight::lines("a.rs", ["fn main() {}"], "dark");
+    assert_ne!(light[0][0].color, dark[0][0].color);
+}
+
+#[test]
+fn html_is_escaped_and_keeps_lines() {
+    let html = highlight::html("a.txt", "<b>&\"x\"\nnext", "light");
+    assert_eq!(html.lines().count(), 2);
+    assert!(html.contains("&lt;b&gt;&amp;&quot;x&quot;"), "{}", html);
+    assert!(!html.contains("<b>"));
+    assert_eq!(highlight::escape("a<b>&\"c\"'"), "a&lt;b&gt;&amp;&quot;c&quot;'");
+}
@@ -0,0 +1,232 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauriprompt_lib::error::AppError;
+use tauriprompt_lib::history::{self, RestoreStatus};
+
+struct Dirs {
+    _temp: tempfile::TempDir,
+    history: PathBuf,
+    quarantine: PathBuf,
+}
+
+fn dirs() -> Dirs {
+    let temp = tempfile::tempdir().unwrap();
+    let history = temp.path().join("history");
+    let quarantine = temp.path().join("quarantine");
+    fs::create_dir_all(&history).unwrap();
+    Dirs { history, quarantine, _temp: temp }
+}
+
+fn entry_json(prompt: &str) -> String {
+    serde_json::json!({
+        "timestamp": "2024-01-02T03:04:05Z",
+        "prompt": prompt,
+        "files": [{ "path": "/project/src/main.rs", "content": "fn main() {}" }],
+        "addScriptFix": true,
+    }).to_string()
+}
+
+fn day(dirs: &Dirs) -> PathBuf {
+    dirs.history.join("2024/01/02")
+}
+
+#[test]
+fn written_entries_load_back() {
+    let dirs = dirs();
+    let path = day(&dirs).join("030405.json");
+    history::write(&path, &dirs.history, &entry_json("Explain this")).unwrap();
+
+    let entry = history::load_entry(&path).unwrap();
+    assert_eq!(entry.prompt, "Explain this");
+    assert!(entry.add_script_fix);
+    assert!(!entry.success);
+    assert_eq!(history::snapshot(&entry, "/project/src/main.rs").unwrap().content, "fn main() {}");
+    assert!(history::snapshot(&entry, "/project/other.rs").is_err());
+}
+
+#[test]
+fn loading_tells_missing_entries_from_corrupt_ones() {
+    let dirs = dirs();
+    let missing = day(&dirs).join("missing.json");
+    assert!(matches!(history::load_entry(&missing), Err(AppError::NotFound { .. })));
+
+    let truncated = dirs.history.join("truncated.json");
+    fs::write(&truncated, &entry_json("cut")[..20]).unwrap();
+    let Err(error) = history::load_entry(&truncated) else { panic!("truncated entry loaded") };
+    assert_eq!(error.code(), "other");
+    assert!(error.to_string().starts_with("history entry: "));
+}
+
+#[test]
+fn rewriting_an_entry_replaces_it() {
+    let dirs = dirs();
+    let path = day(&dirs).join("030405.json");
+    history::write(&path, &dirs.history, &entry_json("first")).unwrap();
+    history::write(&path, &dirs.history, &entry_json("second")).unwrap();
+
+    assert_eq!(history::load_entry(&path).unwrap().prompt, "second");
+    assert_eq!(history::list_files(&dirs.history).unwrap().len(), 1);
+}
+
+#[test]
+fn writes_outside_the_history_folder_are_refused() {
+    let dirs = dirs();
+    let outside = dirs.history.parent().unwrap().join("settings.json");
+    let result = history::write(&outside, &dirs.history, "{}");
+    assert!(matches!(result, Err(AppError::OutsideHistory { .. })));
+    assert!(!outside.exists());
+}
+
+#[test]
+fn a_day_lists_its_entries_and_quarantines_corrupt_ones() {
+    let dirs = dirs();
+    history::write(&day(&dirs).join("a.json"), &dirs.history, &entry_json("good")).unwrap();
+    history::write(&day(&dirs).join("b.json"), &dirs.history, "{\"timestamp\": \"2024").unwrap();
+
+    let listed = history::load_day(&day(&dirs), &dirs.history, &dirs.quarantine).unwrap();
+    assert_eq!(listed.entries.len(), 1);
+    assert_eq!(listed.entries[0]["prompt"], "good");
+    assert_eq!(listed.quarantined, [dirs.quarantine.join("2024/01/02/b.json").to_string_lossy()]);
+    assert!(!day(&dirs).join("b.json").exists());
+
+    let missing = history::load_day(&dirs.history.join("2023/12/31"), &dirs.history, &dirs.quarantine).unwrap();
+    assert!(missing.entries.is_empty());
+}
+
+#[test]
+fn deleting_the_last_entry_removes_its_day_folder() {
+    let dirs = dirs();
+    let first = day(&dirs).join("a.json");
+    let second = day(&dirs).join("b.json");
+    history::write(&first, &dirs.history, &entry_json("a")).unwrap();
+    history::write(&second, &dirs.history, &entry_json("b")).unwrap();
+
+    history::delete(&first, &dirs.history).unwrap();
+    assert!(!first.exists());
+    assert!(day(&dirs).is_dir());
+
+    history::delete(&second, &dirs.history).unwrap();
+    assert!(!day(&dirs).exists());
+    assert!(dirs.history.is_dir());
+}
+
+#[test]
+fn only_history_files_can_be_deleted() {
+    let dirs = dirs();
+    let outside = dirs.history.parent().unwrap().join("settings.json");
+    fs::write(&outside, "{}").unwrap();
+
+    assert!(matches!(history::delete(&outside, &dirs.history), Err(AppError::OutsideHistory { .. })));
+    assert!(matches!(history::delete(&dirs.history, &dirs.history), Err(AppError::OutsideHistory { .. })));
+    assert!(matches!(
+        history::delete(&day(&dirs).join("missing.json"), &dirs.history),
+        Err(AppError::NotFound { .. })
+    ));
+    assert!(outside.exists());
+}
+
+#[test]
+fn restoring_reports_missing_and_changed_files() {
+    let dirs = dirs();
+    let root = dirs.history.parent().unwrap().join("project");
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/main.rs"), "fn main() { changed() }").unwrap();
+
+    let content = serde_json::json!({
+        "timestamp": "2024-01-02T03:04:05Z",
+        "prompt": "p",
+        "root": root.to_string_lossy(),
+        "files": [
+            { "path": root.join("src/main.rs").to_string_lossy(), "content": "fn main() {}" },
+            { "path": root.join("src/gone.rs").to_string_lossy(), "content": "" },
+        ],
+    }).to_string();
+    let path = day(&dirs).join("a.json");
+    history::write(&path, &dirs.history, &content).unwrap();
+
+    let restored = history::restore(&history::load_entry(&path).unwrap(), None);
+    assert_eq!(restored.changed_count, 1);
+    assert_eq!(restored.missing_count, 1);
+    assert_eq!(restored.root.as_deref().map(Path::new), Some(root.as_path()));
+}
+
+#[test]
+fn files_saved_as_placeholders_are_not_reported_as_changed() {
+    let dirs = dirs();
+    let root = dirs.history.parent().unwrap().join("project");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("big.log"), "a lot of text").unwrap();
+    fs::write(root.join("locked.txt"), "text").unwrap();
+
+    let content = serde_json::json!({
+        "timestamp": "2024-01-02T03:04:05Z",
+        "prompt": "p",
+        "root": root.to_string_lossy(),
+        "files": [
+            { "path": root.join("big.log").to_string_lossy(), "content": "Skipped: big.log is too large" },
+            { "path": root.join("locked.txt").to_string_lossy(), "content": "Error: Could not read file" },
+        ],
+    }).to_string();
+    let path = day(&dirs).join("a.json");
+    history::write(&path, &dirs.history, &content).unwrap();
+
+    let restored = history::restore(&history::load_entry(&path).unwrap(), None);
+    assert_eq!((restored.changed_count, restored.missing_count), (0, 0));
+    assert!(restored.files.iter().all(|f| f.status == RestoreStatus::Unrecorded));
+    // Still selected again, as they exist
+    assert_eq!(restored.checked_files.len(), 2);
+}
+
+#[test]
+fn restoring_never_reads_outside_the_root() {
+    let dirs = dirs();
+    let root = dirs.history.parent().unwrap().join("project");
+    fs::create_dir_all(&root).unwrap();
+    let secret = dirs.history.parent().unwrap().join("secret.txt");
+    fs::write(&secret, "token").unwrap();
+
+    let content = serde_json::json!({
+        "timestamp": "2024-01-02T03:04:05Z",
+        "prompt": "p",
+        "root": root.to_string_lossy(),
+        "files": [{ "path": root.join("../secret.txt").to_string_lossy(), "content": "token" }],
+    }).to_string();
+    let path = day(&dirs).join("a.json");
+    history::write(&path, &dirs.history, &content).unwrap();
+
+    let restored = history::restore(&history::load_entry(&path).unwrap(), None);
+    assert_eq!(restored.missing_count, 1);
+    assert!(restored.checked_files.is_empty());
+}
+
+#[test]
+fn entries_are_listed_newest_first_and_searchable() {
+    let dirs = dirs();
+    let older = entry_json("Explain the parser");
+    let newer = entry_json("Fix the bug").replace("03:04:05", "09:00:00");
+    history::write(&day(&dirs).join("a.json"), &dirs.history, &older).unwrap();
+    history::write(&day(&dirs).join("b.json"), &dirs.history, &newer).unwrap();
+    history::write(&day(&dirs).join("c.json"), &dirs.history, "not json").unwrap();
+
+    let entries = history::entries(&dirs.history).unwrap();
+    let ids: Vec<&str> = entries.iter().map(|e| e.id.as_str()).collect();
+    assert_eq!(ids, ["2024/01/02/b.json", "2024/01/02/a.json"]);
+    // Listing only reads, so the corrupt entry stays where it is
+    assert!(day(&dirs).join("c.json").exists());
+
+    let entry = &entries[1].entry;
+    assert!(entry.matches("PARSER", false));
+    assert!(entry.matches("src/main.rs", false));
+    assert!(!entry.matches("fn main", false));
+    assert!(entry.matches("fn main", true));
+
+    assert_eq!(
+        history::entry_path(&dirs.history, &entries[0].id).unwrap(),
+        fs::canonicalize(day(&dirs).join("b.json")).unwrap()
+    );
+    fs::write(dirs.history.parent().unwrap().join("settings.json"), "{}").unwrap();
+    assert!(matches!(
+        history::entry_path(&dirs.history, "../settings.json"),
+        Err(AppError::OutsideHistory { .. })
+    ));
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use tauriprompt_lib::layout::{self, DockSide, MonitorInfo, MonitorLayout, Rect, WindowLayout, FALLBACK_MONITOR};
+use tauriprompt_lib::viewers::{ViewerInfo, ViewerMode};
+
+fn rect(x: i32, y: i32, width: u32, height: u32) -> Rect {
+    Rect { x, y, width, height }
+}
+
+fn viewer(label: &str, mode: ViewerMode) -> ViewerInfo {
+    ViewerInfo { label: label.to_string(), title: label.to_string(), mode }
+}
+
+fn saved(x: f64, y: f64) -> MonitorLayout {
+    MonitorLayout { main: Some(WindowLayout { x, y, width: 800.0, height: 600.0 }), ..MonitorLayout::default() }
+}
+
+const MAIN: Rect = Rect { x: 1000, y: 100, width: 800, height: 600 };
+
+#[test]
+fn attached_viewers_share_the_dock_area_on_every_side() {
+    // Tiled after docked, whatever order they were opened in; floating ones stay put
+    let viewers = [
+        viewer("tiled", ViewerMode::Tiled),
+        viewer("floating", ViewerMode::Floating),
+        viewer("docked", ViewerMode::Docked),
+    ];
+
+    assert_eq!(layout::arrange(MAIN, DockSide::Right, 400, &viewers), [
+        ("docked".to_string(), rect(1800, 100, 200, 600)),
+        ("tiled".to_string(), rect(2000, 100, 200, 600)),
+    ]);
+    assert_eq!(layout::arrange(MAIN, DockSide::Left, 400, &viewers), [
+        ("docked".to_string(), rect(600, 100, 200, 600)),
+        ("tiled".to_string(), rect(800, 100, 200, 600)),
+    ]);
+    assert_eq!(layout::arrange(MAIN, DockSide::Bottom, 300, &viewers), [
+        ("docked".to_string(), rect(1000, 700, 400, 300)),
+        ("tiled".to_string(), rect(1400, 700, 400, 300)),
+    ]);
+    assert!(layout::arrange(MAIN, DockSide::Right, 400, &[viewer("f", ViewerMode::Floating)]).is_empty());
+}
+
+#[test]
+fn resizing_a_viewer_sets_the_dock_size_and_dragging_it_moves_the_main_window() {
+    let resized = rect(0, 0, 250, 500);
+    assert_eq!(layout::dock_size_from(resized, DockSide::Right, 2), 500);
+    assert_eq!(layout::dock_size_from(resized, DockSide::Left, 2), 500);
+    assert_eq!(layout::dock_size_from(resized, DockSide::Bottom, 2), 500);
+    assert_eq!(layout::dock_size_from(resized, DockSide::Left, 0), 250);
+
+    let dragged = rect(1800, 100, 200, 600);
+    assert_eq!(layout::main_for_viewer(dragged, 800, 600, DockSide::Right, 400), (1000, 100));
+    assert_eq!(layout::main_for_viewer(rect(600, 100, 200, 600), 800, 600, DockSide::Left, 400), (1000, 100));
+    assert_eq!(layout::main_for_viewer(rect(1000, 700, 400, 300), 800, 600, DockSide::Bottom, 300), (1000, 100));
+}
+
+#[test]
+fn windows_are_fitted_onto_the_monitor() {
+    let monitor = MonitorInfo::new(Some("DP-1"), rect(1920, 0, 1920, 1080), 2.0);
+    assert_eq!(monitor.fit(rect(3700, -50, 400, 300)), rect(3440, 0, 400, 300));
+    assert_eq!(monitor.fit(rect(0, 0, 4000, 2000)), rect(1920, 0, 1920, 1080));
+
+    let layout = WindowLayout { x: 10.0, y: 20.0, width: 300.0, height: 200.0 };
+    let physical = monitor.to_physical(&layout);
+    assert_eq!(physical, rect(1940, 40, 600, 400));
+    assert_eq!(monitor.to_logical(physical), layout);
+    // Small saved sizes still leave room for the viewers
+    assert_eq!(monitor.dock_size(&MonitorLayout { dock_size: 50.0, ..MonitorLayout::default() }), 400);
+}
+
+#[test]
+fn restoring_prefers_the_last_monitor_then_any_saved_one_then_the_fallback() {
+    let left = MonitorInfo::new(Some("left"), rect(0, 0, 1920, 1080), 1.0);
+    let right = MonitorInfo::new(None, rect(1920, 0, 1920, 1080), 1.0);
+    assert_eq!(right.key, "1920x1080@1920,0");
+    let monitors = [left.clone(), right.clone()];
+
+    let layouts = HashMap::from([
+        ("left".to_string(), saved(10.0, 10.0)),
+        (right.key.clone(), saved(20.0, 20.0)),
+        (FALLBACK_MONITOR.to_string(), saved(30.0, 30.0)),
+    ]);
+    let restored = |monitors: &[MonitorInfo], layouts: &HashMap<String, MonitorLayout>, last: Option<&str>| {
+        layout::restore_target(monitors, Some(&left), layouts, last)
+    };
+
+    assert_eq!(restored(&monitors, &layouts, Some(right.key.as_str())), Some(rect(1940, 20, 800, 600)));
+    assert_eq!(restored(&monitors, &layouts, Some("left")), Some(rect(10, 10, 800, 600)));
+
+    // The last monitor was unplugged: another monitor with a saved layout wins
+    let only_right = [right.clone()];
+    assert_eq!(restored(&only_right, &layouts, Some("left")), Some(rect(1940, 20, 800, 600)));
+
+    // Nothing saved for any connected monitor: the fallback layout on the current one
+    let fallback_only = HashMap::from([(FALLBACK_MONITOR.to_string(), saved(30.0, 30.0))]);
+    assert_eq!(restored(&monitors, &fallback_only, Some("gone")), Some(rect(30, 30, 800, 600)));
+    assert_eq!(restored(&monitors, &HashMap::new(), None), None);
+}
@@ -0,0 +1,95 @@
+use std::fs;
+use std::path::Path;
+use tauriprompt_lib::error::AppError;
+use tauriprompt_lib::{files, git, history, tree};
+
+fn touch(path: &Path) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, "x").unwrap();
+}
+
+#[test]
+fn lists_folders_and_files_sorted_by_name() {
+    let dir = tempfile::tempdir().unwrap();
+    touch(&dir.path().join("b.rs"));
+    touch(&dir.path().join("A.rs"));
+    touch(&dir.path().join("src/main.rs"));
+
+    let listing = files::read_dir_recursive(dir.path(), 1, 0, &git::StatusMap::load(dir.path())).unwrap();
+    let names: Vec<&str> = listing.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["A.rs", "b.rs", "src"]);
+
+    let src = &listing[2];
+    assert!(src.is_directory);
+    let children = src.children.as_ref().unwrap();
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].name, "main.rs");
+    assert!(!children[0].is_directory);
+    assert!(children.iter().all(|f| f.git_status.is_none()));
+}
+
+#[test]
+fn stops_at_the_requested_depth() {
+    let dir = tempfile::tempdir().unwrap();
+    touch(&dir.path().join("a/b/c.txt"));
+
+    let shallow = files::read_dir_recursive(dir.path(), 0, 0, &git::StatusMap::load(dir.path())).unwrap();
+    assert_eq!(shallow.len(), 1);
+    assert!(shallow[0].children.is_none());
+
+    let deep = files::read_dir_recursive(dir.path(), 2, 0, &git::StatusMap::load(dir.path())).unwrap();
+    let b = &deep[0].children.as_ref().unwrap()[0];
+    assert_eq!(b.children.as_ref().unwrap()[0].name, "c.txt");
+}
+
+#[test]
+fn listing_a_missing_folder_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("missing");
+    let listed = files::read_dir_recursive(&missing, 1, 0, &git::StatusMap::load(dir.path()));
+    assert!(matches!(listed, Err(AppError::NotFound { path }) if Path::new(&path) == missing));
+}
+
+#[test]
+fn history_listing_finds_entries_at_any_depth() {
+    let dir = tempfile::tempdir().unwrap();
+    touch(&dir.path().join("2024/01/02/a.json"));
+    touch(&dir.path().join("2024/01/03/b.json"));
+    touch(&dir.path().join("2024/01/03/notes.txt"));
+
+    let mut listed = history::list_files(dir.path()).unwrap();
+    listed.sort();
+    let expected: Vec<String> = ["2024/01/02/a.json", "2024/01/03/b.json"]
+        .iter()
+        .map(|p| dir.path().join(p).to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(listed, expected);
+
+    assert!(history::list_files(&dir.path().join("none")).unwrap().is_empty());
+}
+
+#[test]
+fn filtered_walks_skip_excluded_folders_and_stop_opening_folders_at_the_limit() {
+    let dir = tempfile::tempdir().unwrap();
+    touch(&dir.path().join("node_modules/pkg/index.js"));
+    touch(&dir.path().join("a/x.rs"));
+    touch(&dir.path().join("b/y.rs"));
+    let statuses = git::StatusMap::load(dir.path());
+
+    let exclude = ["node_modules".to_string()];
+    let walk = files::Walk { exclude: &exclude, ..Default::default() };
+    let listing = files::read_dir_filtered(dir.path(), 2, 0, &statuses, &walk).unwrap();
+    let names: Vec<&str> = listing.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["a", "b"]);
+
+    // `a` and `a/x.rs` use up the budget, so `b` is listed but not opened
+    let walk = files::Walk { max_entries: Some(2), ..Default::default() };
+    let listing = files::read_dir_filtered(dir.path(), 2, 0, &statuses, &walk).unwrap();
+    assert_eq!(listing[0].children.as_ref().unwrap()[0].name, "x.rs");
+    assert!(listing.iter().find(|f| f.name == "b").unwrap().children.is_none());
+
+    let options = tree::TreeOptions { max_entries: 2, exclude: exclude.to_vec(), ..Default::default() };
+    let rendered = tree::render_dir(dir.path(), &options, &Default::default()).unwrap();
+    let root = dir.path().file_name().unwrap().to_string_lossy();
+    assert_eq!(rendered, format!("{}/\n├── a/\n│   └── x.rs\n└── ... 1 more", root));
+}
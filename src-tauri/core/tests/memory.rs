@@ -0,0 +1,65 @@
+use std::sync::Arc;
+use std::thread;
+use tauriprompt_lib::error::AppError;
+use tauriprompt_lib::memory::{MemoryMonitor, Subsystem};
+
+fn bytes_for(monitor: &MemoryMonitor, subsystem: Subsystem) -> (u64, u64) {
+    let stats = monitor.stats();
+    let usage = stats.subsystems.iter().find(|s| s.subsystem == subsystem).unwrap();
+    (usage.bytes, usage.allocations)
+}
+
+#[test]
+fn reservations_over_the_limit_fail_without_counting() {
+    let monitor = MemoryMonitor::new(100);
+    let held = monitor.reserve(Subsystem::Viewer, 60).unwrap();
+
+    let refused = monitor.reserve(Subsystem::Paging, 41);
+    assert!(matches!(refused, Err(AppError::MemoryLimit { requested: 41, used: 60, limit: 100 })));
+    assert_eq!(monitor.stats().used, 60);
+    assert_eq!(bytes_for(&monitor, Subsystem::Paging), (0, 0));
+    assert!(monitor.reserve(Subsystem::Paging, u64::MAX).is_err());
+    assert_eq!(monitor.stats().used, 60);
+
+    // Exactly up to the limit is fine
+    let rest = monitor.reserve(Subsystem::Paging, 40).unwrap();
+    assert_eq!(monitor.stats().used, 100);
+    drop((held, rest));
+}
+
+#[test]
+fn dropping_an_allocation_releases_it() {
+    let monitor = MemoryMonitor::new(1000);
+    let first = monitor.reserve(Subsystem::Transfers, 300).unwrap();
+    let second = monitor.reserve(Subsystem::Transfers, 200).unwrap();
+    assert_eq!(bytes_for(&monitor, Subsystem::Transfers), (500, 2));
+
+    drop(first);
+    assert_eq!(monitor.stats().used, 200);
+    assert_eq!(bytes_for(&monitor, Subsystem::Transfers), (200, 1));
+
+    drop(second);
+    let stats = monitor.stats();
+    assert_eq!(stats.used, 0);
+    assert!(stats.subsystems.iter().all(|s| s.bytes == 0 && s.allocations == 0));
+    // The peak is kept for diagnostics
+    assert_eq!(stats.peak, 500);
+}
+
+#[test]
+fn concurrent_reservations_never_overshoot_the_limit() {
+    let monitor = Arc::new(MemoryMonitor::new(1000));
+    let threads: Vec<_> = (0..8).map(|_| {
+        let monitor = monitor.clone();
+        thread::spawn(move || {
+            (0..100).filter_map(|_| monitor.reserve(Subsystem::History, 30).ok()).collect::<Vec<_>>()
+        })
+    }).collect();
+    let held: Vec<_> = threads.into_iter().flat_map(|t| t.join().unwrap()).collect();
+
+    assert_eq!(held.len(), 33);
+    assert_eq!(monitor.stats().used, 990);
+    assert!(monitor.stats().peak <= 1000);
+    drop(held);
+    assert_eq!(monitor.stats().used, 0);
+}
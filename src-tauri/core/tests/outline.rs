@@ -0,0 +1,154 @@
+use std::path::Path;
+use tauriprompt_lib::outline::outline;
+
+fn outline_of(file: &str, source: &str) -> String {
+    outline(Path::new(file), source).unwrap()
+}
+
+#[test]
+fn rust_keeps_signatures_and_types() {
+    let source = r#"use std::fmt;
+
+pub struct Point {
+    pub x: i32,
+}
+
+impl Point {
+    pub fn new(x: i32) -> Self {
+        let doubled = x * 2;
+        Point { x: doubled }
+    }
+}
+
+fn main() {
+    println!("{}", Point::new(1).x);
+}
+"#;
+    assert_eq!(outline_of("main.rs", source), "\
+use std::fmt;
+pub struct Point {
+    pub x: i32,
+}
+impl Point {
+    pub fn new(x: i32) -> Self { ... }
+}
+fn main() { ... }");
+}
+
+#[test]
+fn typescript_elides_functions_methods_and_arrow_functions() {
+    let source = r#"import { a } from "./a";
+
+export interface Options {
+    depth: number;
+}
+
+export class Walker {
+    private seen = 0;
+
+    walk(options: Options): number {
+        this.seen += options.depth;
+        return this.seen;
+    }
+}
+
+export const run = (n: number) => {
+    return n + 1;
+};
+"#;
+    assert_eq!(outline_of("walker.ts", source), "\
+import { a } from \"./a\";
+export interface Options {
+    depth: number;
+}
+export class Walker {
+    private seen = 0
+    walk(options: Options): number { ... }
+}
+export const run = (n: number) => { ... }");
+}
+
+#[test]
+fn python_keeps_docstrings_and_decorators() {
+    let source = r#"import os
+
+class Store:
+    @property
+    def path(self):
+        """Where the store lives."""
+        return os.getcwd()
+
+def main():
+    print(Store().path)
+"#;
+    assert_eq!(outline_of("store.py", source), "\
+import os
+class Store:
+    @property
+    def path(self):
+        \"\"\"Where the store lives.\"\"\"
+        ...
+def main():
+    ...");
+}
+
+#[test]
+fn go_elides_functions_and_methods() {
+    let source = r#"package main
+
+import "fmt"
+
+type Point struct {
+	X int
+}
+
+func (p Point) String() string {
+	return fmt.Sprint(p.X)
+}
+
+func main() {
+	fmt.Println(Point{1})
+}
+"#;
+    assert_eq!(outline_of("main.go", source), "\
+package main
+import \"fmt\"
+type Point struct {
+	X int
+}
+func (p Point) String() string { ... }
+func main() { ... }");
+}
+
+#[test]
+fn java_outlines_class_members() {
+    let source = r#"package app;
+
+import java.util.List;
+
+public class Greeter {
+    private final String name;
+
+    public Greeter(String name) {
+        this.name = name;
+    }
+
+    public String greet(List<String> others) {
+        return "Hello " + name + String.join(", ", others);
+    }
+}
+"#;
+    assert_eq!(outline_of("Greeter.java", source), "\
+package app;
+import java.util.List;
+public class Greeter {
+    private final String name;
+    public Greeter(String name) { ... }
+    public String greet(List<String> others) { ... }
+}");
+}
+
+#[test]
+fn unsupported_files_are_refused() {
+    assert_eq!(outline(Path::new("notes.txt"), "text").unwrap_err().to_string(), "No outline support for .txt files");
+}
@@ -0,0 +1,39 @@
+use std::fs;
+use tauriprompt_lib::memory::MemoryMonitor;
+use tauriprompt_lib::paging::{PageStore, Target};
+
+fn numbered(lines: usize) -> String {
+    (1..=lines).map(|n| format!("line {}", n)).collect::<Vec<_>>().join("\n")
+}
+
+#[test]
+fn pages_are_read_from_any_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("a.txt");
+    fs::write(&path, numbered(3000) + "\n").unwrap();
+    let (store, memory) = (PageStore::default(), MemoryMonitor::new(1 << 20));
+
+    let page = store.read(&Target::File(path), 2047, 3, &memory).unwrap();
+    assert_eq!(page.start, 2047);
+    assert_eq!(page.lines, ["line 2048", "line 2049", "line 2050"]);
+    assert_eq!(page.total_lines, 3000);
+}
+
+#[test]
+fn reading_past_an_unterminated_last_line_gives_an_empty_page() {
+    // 1024 lines without a final newline: the last line completes a checkpoint
+    // interval but no checkpoint follows it
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("a.txt");
+    fs::write(&path, numbered(1024)).unwrap();
+    let (store, memory) = (PageStore::default(), MemoryMonitor::new(1 << 20));
+    let target = Target::File(path);
+
+    let last = store.read(&target, 1023, 10, &memory).unwrap();
+    assert_eq!(last.lines, ["line 1024"]);
+
+    let past = store.read(&target, 1024, 10, &memory).unwrap();
+    assert!(past.lines.is_empty());
+    assert_eq!((past.start, past.total_lines), (1024, 1024));
+    assert!(store.read(&target, 5000, 10, &memory).unwrap().lines.is_empty());
+}
@@ -0,0 +1,142 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use serde_json::json;
+use tauriprompt_lib::files::MAX_FILE_SIZE;
+use tauriprompt_lib::prompt::{self, PromptRequest};
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+fn request(root: &Path, files: &[&str], blame: bool) -> PromptRequest {
+    let files: Vec<String> = files.iter().map(|f| root.join(f).to_string_lossy().into_owned()).collect();
+    serde_json::from_value(json!({
+        "root": root.to_string_lossy(),
+        "files": files,
+        "gitHistory": { "commitLimit": 5, "blame": blame },
+    }))
+    .unwrap()
+}
+
+#[test]
+fn git_history_is_left_out_outside_a_repository() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+
+    let built = prompt::build(&request(dir.path(), &["a.txt"], true), MAX_FILE_SIZE).unwrap();
+    assert!(built.text.contains("one"));
+    assert!(!built.text.contains("Recent commits"));
+    assert!(!built.text.contains("Blame for"));
+}
+
+#[test]
+fn commits_are_not_listed_for_the_whole_repository_when_no_file_was_read() {
+    let dir = tempfile::tempdir().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "Add a"]);
+
+    let built = prompt::build(&request(dir.path(), &["missing.txt"], false), MAX_FILE_SIZE).unwrap();
+    assert_eq!(built.unreadable_files.len(), 1);
+    assert!(!built.text.contains("Recent commits"));
+}
+
+#[test]
+fn blame_reads_sha256_repositories() {
+    let dir = tempfile::tempdir().unwrap();
+    git(dir.path(), &["init", "-q", "--object-format=sha256"]);
+    fs::write(dir.path().join("a.txt"), "one\ntwo\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "Add a"]);
+
+    let built = prompt::build(&request(dir.path(), &["a.txt"], true), MAX_FILE_SIZE).unwrap();
+    assert!(built.text.contains("Test: Add a"), "{}", built.text);
+    let blame = built.text.split("Blame for \\a.txt:").nth(1).expect(&built.text);
+    assert!(blame.contains("1-2"), "{}", built.text);
+}
+
+#[test]
+fn files_are_laid_out_in_the_requested_format() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("a.rs"), "fn a() {}\n// ```").unwrap();
+    let build = |format: &str| {
+        let mut request = request(dir.path(), &["a.rs", "missing.rs"], false);
+        request.git_history = None;
+        request.format = serde_json::from_value(json!(format)).unwrap();
+        prompt::build(&request, MAX_FILE_SIZE).unwrap().text
+    };
+    let missing = dir.path().join("missing.rs").to_string_lossy().into_owned();
+
+    assert_eq!(build("plain"), format!(
+        "Filename: \\a.rs\nContents:\nfn a() {{}}\n// ```\n\nFilename: {0}\nError: {0} does not exist", missing
+    ));
+    assert_eq!(build("markdown"), format!(
+        "### \\a.rs\n````rs\nfn a() {{}}\n// ```\n````\n\n### {0}\n> Error: {0} does not exist", missing
+    ));
+    assert_eq!(build("xml"), format!(
+        "<file path=\"\\a.rs\">\nfn a() {{}}\n// ```\n</file>\n\n<file path=\"{0}\" error=\"{0} does not exist\"/>", missing
+    ));
+}
+
+#[test]
+fn ignore_rules_apply_to_the_project_tree() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("dist")).unwrap();
+    fs::write(dir.path().join("dist/out.js"), "").unwrap();
+    fs::write(dir.path().join("a.txt"), "").unwrap();
+
+    let mut request: PromptRequest = serde_json::from_value(json!({
+        "root": dir.path().to_string_lossy(),
+        "prompt": "{{tree}}",
+    }))
+    .unwrap();
+    assert!(prompt::build(&request, MAX_FILE_SIZE).unwrap().text.contains("dist/"));
+    request.ignore_rules = vec!["dist".to_string()];
+    let built = prompt::build(&request, MAX_FILE_SIZE).unwrap();
+    assert!(!built.text.contains("dist/"));
+    assert!(built.text.contains("a.txt"));
+    assert_eq!(built.estimated_tokens, prompt::estimate_tokens(&built.text));
+}
+
+#[test]
+fn guarded_files_in_the_git_diff_need_confirming() {
+    let dir = tempfile::tempdir().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    fs::write(dir.path().join(".ENV"), "KEY=old\n").unwrap();
+    fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "first"]);
+    fs::write(dir.path().join(".ENV"), "KEY=hunter2\n").unwrap();
+    fs::write(dir.path().join("a.txt"), "two\n").unwrap();
+
+    let env = dir.path().join(".ENV").to_string_lossy().into_owned();
+    let build = |confirmed: &[&str]| {
+        let request: PromptRequest = serde_json::from_value(json!({
+            "root": dir.path().to_string_lossy(),
+            "files": [],
+            "gitDiff": { "kind": "unstaged" },
+            "guard": { "confirmed": confirmed },
+        }))
+        .unwrap();
+        prompt::build(&request, MAX_FILE_SIZE).unwrap()
+    };
+
+    let refused = build(&[]);
+    assert!(refused.needs_confirmation);
+    assert!(!refused.text.contains("hunter2"));
+    assert_eq!(refused.guard.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(), [env.as_str()]);
+
+    let confirmed = build(&[&env]);
+    assert!(!confirmed.needs_confirmation);
+    assert!(confirmed.text.contains("+two"));
+    assert!(confirmed.text.contains("+++ b/.ENV"));
+}
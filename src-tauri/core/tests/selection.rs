@@ -0,0 +1,78 @@
+use tauriprompt_lib::selection::{self, Selection, SelectionRange};
+
+fn lines(start: usize, end: usize) -> SelectionRange {
+    SelectionRange::Lines { start, end }
+}
+
+const SOURCE: &str = "use std::fmt;
+
+struct Point {
+    x: i32,
+}
+
+impl Point {
+    fn new() -> Self {
+        Point { x: 0 }
+    }
+}
+
+fn main() {
+    let p = Point::new();
+}
+";
+
+#[test]
+fn line_ranges_and_symbols_are_parsed_from_the_spec() {
+    assert_eq!(Selection::parse("src/main.rs:120-200"), Selection { path: "src/main.rs".into(), range: lines(120, 200) });
+    assert_eq!(Selection::parse("src/main.rs:42").range, lines(42, 42));
+    assert_eq!(
+        Selection::parse("src/app.py#App.run"),
+        Selection { path: "src/app.py".into(), range: SelectionRange::Symbol("App.run".into()) }
+    );
+    // Not a range: reversed, zero-based or not a number
+    assert_eq!(Selection::parse("a.rs:20-10").range, SelectionRange::Whole);
+    assert_eq!(Selection::parse("a.rs:0").range, SelectionRange::Whole);
+    assert_eq!(Selection::parse("a.rs:main").path, "a.rs:main");
+    assert_eq!(Selection::parse("a.rs#not a symbol").range, SelectionRange::Whole);
+}
+
+#[test]
+fn windows_drive_letters_are_part_of_the_path() {
+    assert_eq!(Selection::parse(r"C:\x.rs:10-20"), Selection { path: r"C:\x.rs".into(), range: lines(10, 20) });
+    assert_eq!(Selection::parse(r"C:\x.rs"), Selection { path: r"C:\x.rs".into(), range: SelectionRange::Whole });
+    assert_eq!(Selection::parse(r"C:\x.rs#main").path, r"C:\x.rs");
+}
+
+#[test]
+fn existing_files_win_over_range_syntax() {
+    let dir = tempfile::tempdir().unwrap();
+    let odd = dir.path().join("notes:1");
+    std::fs::write(&odd, "x").unwrap();
+    let spec = odd.to_string_lossy().into_owned();
+    assert_eq!(Selection::parse(&spec), Selection { path: spec.clone(), range: SelectionRange::Whole });
+}
+
+#[test]
+fn ranges_are_widened_merged_and_clamped() {
+    let (resolved, unresolved) = selection::resolve_ranges(SOURCE, &[lines(4, 4), lines(6, 7), lines(14, 99)], 1);
+    assert_eq!(resolved, [(3, 8), (13, 15)]);
+    assert!(unresolved.is_empty());
+    assert_eq!(selection::extract(SOURCE, &[(1, 1), (13, 13)]), "use std::fmt;\n...\nfn main() {");
+}
+
+#[test]
+fn symbols_resolve_to_their_whole_block() {
+    let (resolved, _) = selection::resolve_ranges(SOURCE, &[SelectionRange::Symbol("Point".into())], 0);
+    assert_eq!(resolved, [(3, 5)]);
+    let (resolved, _) = selection::resolve_ranges(SOURCE, &[SelectionRange::Symbol("Point::new".into())], 0);
+    assert_eq!(resolved, [(8, 10)]);
+    assert_eq!(selection::find_symbol("class App:\n    def run(self):\n        pass\n\nx = 1\n", "App.run"), Some((2, 3)));
+}
+
+#[test]
+fn out_of_range_lines_and_unknown_symbols_are_reported_without_dropping_the_rest() {
+    let ranges = [lines(40, 50), SelectionRange::Symbol("missing".into()), lines(1, 1)];
+    let (resolved, unresolved) = selection::resolve_ranges(SOURCE, &ranges, 0);
+    assert_eq!(resolved, [(1, 1)]);
+    assert_eq!(unresolved, ["Line 40 is past the end of the file (15 lines)", "Symbol missing not found"]);
+}
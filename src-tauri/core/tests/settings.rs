@@ -0,0 +1,89 @@
+use std::fs;
+use serde_json::json;
+use tauriprompt_lib::guard::GuardAction;
+use tauriprompt_lib::layout::{WindowLayout, FALLBACK_MONITOR};
+use tauriprompt_lib::settings::{self, SettingsStore, CURRENT_VERSION};
+
+#[test]
+fn corrupt_or_mistyped_settings_are_quarantined_and_replaced_with_defaults() {
+    for stored in ["{\"version\": 2, \"theme\": ", "{\"version\": 2, \"richCopy\": \"yes\"}"] {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("settings.json"), stored).unwrap();
+
+        let store = SettingsStore::default();
+        let loaded = store.load(dir.path()).unwrap();
+        assert_eq!(loaded.version, CURRENT_VERSION);
+        assert!(!loaded.rich_copy);
+
+        let quarantined: Vec<_> = fs::read_dir(dir.path().join(settings::QUARANTINE_DIR)).unwrap().collect();
+        assert_eq!(quarantined.len(), 1);
+        let kept = fs::read_to_string(quarantined[0].as_ref().unwrap().path()).unwrap();
+        assert_eq!(kept, stored);
+
+        // The replacement is a valid file that updates apply to
+        let updated = store.update(dir.path(), serde_json::json!({ "richCopy": true })).unwrap();
+        assert!(updated.rich_copy);
+        assert!(store.load(dir.path()).unwrap().rich_copy);
+    }
+}
+
+#[test]
+fn settings_from_a_newer_version_are_left_alone() {
+    let dir = tempfile::tempdir().unwrap();
+    let stored = format!("{{\"version\": {}, \"theme\": \"future\"}}", CURRENT_VERSION + 1);
+    fs::write(dir.path().join("settings.json"), &stored).unwrap();
+
+    // The app keeps working on defaults
+    let store = SettingsStore::default();
+    let loaded = store.load(dir.path()).unwrap();
+    assert_eq!(loaded.theme, "solarized");
+    assert_eq!(loaded.version, CURRENT_VERSION);
+
+    // Without losing what the newer app wrote
+    assert!(store.update(dir.path(), json!({ "theme": "dark" })).is_err());
+    assert_eq!(fs::read_to_string(dir.path().join("settings.json")).unwrap(), stored);
+    assert!(!dir.path().join(settings::QUARANTINE_DIR).exists());
+}
+
+#[test]
+fn loose_version_0_files_are_migrated() {
+    let dir = tempfile::tempdir().unwrap();
+    let write = |file: &str, value: serde_json::Value| fs::write(dir.path().join(file), value.to_string()).unwrap();
+    write("theme.json", json!({ "theme": "dark" }));
+    write("redaction.json", json!({ "patterns": [{ "name": "ticket", "pattern": "TICKET-\\d+" }] }));
+    write("guard.json", json!({ "rules": [{ "pattern": "*.key", "action": "deny" }] }));
+
+    let loaded = SettingsStore::default().load(dir.path()).unwrap();
+    assert_eq!(loaded.version, CURRENT_VERSION);
+    assert_eq!(loaded.theme, "dark");
+    assert_eq!(loaded.redaction_patterns.len(), 1);
+    assert_eq!(loaded.redaction_patterns[0].pattern, "TICKET-\\d+");
+    assert_eq!(loaded.guard_rules.len(), 1);
+    assert_eq!(loaded.guard_rules[0].action, GuardAction::Deny);
+
+    // Written back at the current version
+    let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.path().join("settings.json")).unwrap()).unwrap();
+    assert_eq!(saved["version"], CURRENT_VERSION);
+    assert_eq!(saved["theme"], "dark");
+}
+
+#[test]
+fn a_version_1_window_layout_becomes_the_fallback_monitor_layout() {
+    let dir = tempfile::tempdir().unwrap();
+    let stored = json!({
+        "version": 1,
+        "theme": "light",
+        "windowLayout": { "x": 10.0, "y": 20.0, "width": 800.0, "height": 600.0 },
+    });
+    fs::write(dir.path().join("settings.json"), stored.to_string()).unwrap();
+
+    let loaded = SettingsStore::default().load(dir.path()).unwrap();
+    assert_eq!(loaded.version, 2);
+    assert_eq!(loaded.theme, "light");
+    let fallback = &loaded.window_layouts[FALLBACK_MONITOR];
+    assert_eq!(fallback.main, Some(WindowLayout { x: 10.0, y: 20.0, width: 800.0, height: 600.0 }));
+
+    let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.path().join("settings.json")).unwrap()).unwrap();
+    assert!(saved.get("windowLayout").is_none());
+    assert_eq!(saved["windowLayouts"][FALLBACK_MONITOR]["main"]["width"], 800.0);
+}
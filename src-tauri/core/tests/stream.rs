@@ -0,0 +1,65 @@
+use tauriprompt_lib::memory::{MemoryMonitor, Subsystem};
+use tauriprompt_lib::stream::{split_chunks, TransferStore};
+
+// One-, two-, three- and four-byte characters, so every chunk size lands
+// inside some sequence
+const TEXT: &str = "aé€😀b😀€éa\n日本語のテキスト🎉🎉 done";
+
+#[test]
+fn chunks_never_split_a_character_and_join_back_to_the_original() {
+    for max_bytes in 4..=TEXT.len() + 1 {
+        let chunks = split_chunks(TEXT, max_bytes);
+        assert_eq!(chunks.concat(), TEXT, "max_bytes {}", max_bytes);
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert!(!chunk.is_empty());
+            assert!(chunk.len() <= max_bytes, "chunk {} is {} bytes, max {}", i, chunk.len(), max_bytes);
+            // Only the last chunk, or one stopped short by a character, is under the limit
+            if i + 1 < chunks.len() {
+                let next = chunks[i + 1].chars().next().unwrap();
+                assert!(chunk.len() + next.len_utf8() > max_bytes);
+            }
+        }
+    }
+}
+
+#[test]
+fn limits_below_one_character_still_make_progress() {
+    let chunks = split_chunks("😀😀", 1);
+    assert_eq!(chunks, ["😀", "😀"]);
+    assert!(split_chunks("", 8).is_empty());
+}
+
+fn start(store: &TransferStore, monitor: &MemoryMonitor, viewer: &str, content: &str) -> String {
+    let allocation = monitor.reserve(Subsystem::Transfers, content.len() as u64).unwrap();
+    store.start(viewer, content, 4, "a.txt", None, allocation).transfer_id
+}
+
+#[test]
+fn transfers_hold_their_memory_until_finished() {
+    let (store, monitor) = (TransferStore::default(), MemoryMonitor::new(1000));
+    let id = start(&store, &monitor, "file-viewer", "0123456789");
+    assert_eq!(monitor.stats().used, 10);
+    assert_eq!(store.chunk(&id, 2).unwrap().content, "89");
+    assert_eq!(store.ack(&id, 0).unwrap(), [1, 2]);
+
+    store.finish(&id);
+    assert_eq!(monitor.stats().used, 0);
+    assert!(store.chunk(&id, 0).is_err());
+}
+
+#[test]
+fn closing_or_refilling_a_viewer_frees_its_transfers() {
+    let (store, monitor) = (TransferStore::default(), MemoryMonitor::new(1000));
+    let first = start(&store, &monitor, "file-viewer", "0123456789");
+    let other = start(&store, &monitor, "file-viewer-1", "abcd");
+
+    // New content for the same viewer replaces what it was being sent
+    let second = start(&store, &monitor, "file-viewer", "xyz");
+    assert!(store.chunk(&first, 0).is_err());
+    assert_eq!(monitor.stats().used, 7);
+
+    store.finish_viewer("file-viewer");
+    assert!(store.chunk(&second, 0).is_err());
+    assert_eq!(store.chunk(&other, 0).unwrap().content, "abcd");
+    assert_eq!(monitor.stats().used, 4);
+}
@@ -0,0 +1,28 @@
+use std::collections::HashMap;
+use tauriprompt_lib::template;
+
+fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+}
+
+#[test]
+fn placeholders_are_filled_and_built_ins_left_alone() {
+    let rendered = template::render(
+        "Review {{file}} for {{team}}.\n{{tree}}\nThanks, {{team}}",
+        &vars(&[("file", "main.rs"), ("team", "core")]),
+    ).unwrap();
+    assert_eq!(rendered, "Review main.rs for core.\n{{tree}}\nThanks, core");
+}
+
+#[test]
+fn values_are_not_expanded_again() {
+    let rendered = template::render("{{a}}", &vars(&[("a", "{{b}}"), ("b", "no")])).unwrap();
+    assert_eq!(rendered, "{{b}}");
+}
+
+#[test]
+fn missing_values_are_reported_together() {
+    let error = template::render("{{a}} {{b}} {{a}} {{tree}}", &HashMap::new()).unwrap_err();
+    assert_eq!(error.to_string(), "template: No value for a, b");
+    assert_eq!(template::placeholders("{{a}} {{ b }} {{tree}}"), ["a", "tree"]);
+}
@@ -0,0 +1,74 @@
+use tauriprompt_lib::viewers::{self, ViewerMode, ViewerRegistry, PRIMARY_LABEL};
+
+fn modes(registry: &ViewerRegistry) -> Vec<(String, ViewerMode)> {
+    registry.list().into_iter().map(|v| (v.label, v.mode)).collect()
+}
+
+#[test]
+fn viewers_are_listed_in_the_order_they_opened() {
+    let registry = ViewerRegistry::default();
+    let extra = registry.new_label();
+    registry.register(PRIMARY_LABEL, "a.rs", ViewerMode::Docked);
+    registry.register(&extra, "b.rs", ViewerMode::Tiled);
+
+    // Registering again updates in place
+    registry.register(PRIMARY_LABEL, "c.rs", ViewerMode::Docked);
+    assert_eq!(modes(&registry), [(PRIMARY_LABEL.to_string(), ViewerMode::Docked), (extra.clone(), ViewerMode::Tiled)]);
+    assert_eq!(registry.get(PRIMARY_LABEL).unwrap().title, "c.rs");
+
+    registry.remove(PRIMARY_LABEL);
+    assert!(registry.get(PRIMARY_LABEL).is_none());
+    assert_eq!(registry.list().len(), 1);
+    // Removing an unknown viewer is harmless
+    registry.remove("file-viewer-99");
+    assert_eq!(registry.list().len(), 1);
+}
+
+#[test]
+fn new_labels_are_never_reused() {
+    let registry = ViewerRegistry::default();
+    let first = registry.new_label();
+    registry.register(&first, "a", ViewerMode::Tiled);
+    registry.remove(&first);
+    let second = registry.new_label();
+
+    assert_ne!(first, second);
+    assert!(viewers::is_viewer(&first) && viewers::is_viewer(&second) && viewers::is_viewer(PRIMARY_LABEL));
+    assert!(!viewers::is_viewer("main") && !viewers::is_viewer("calendar"));
+}
+
+#[test]
+fn only_one_viewer_stays_docked() {
+    let registry = ViewerRegistry::default();
+    let (a, b) = (registry.new_label(), registry.new_label());
+    registry.register(PRIMARY_LABEL, "p", ViewerMode::Docked);
+    registry.register(&a, "a", ViewerMode::Floating);
+    registry.register(&b, "b", ViewerMode::Tiled);
+
+    // Docking another moves the docked one into the tiles and leaves floating ones alone
+    registry.set_mode(&b, ViewerMode::Docked).unwrap();
+    assert_eq!(modes(&registry), [
+        (PRIMARY_LABEL.to_string(), ViewerMode::Tiled),
+        (a.clone(), ViewerMode::Floating),
+        (b.clone(), ViewerMode::Docked),
+    ]);
+
+    // A new docked viewer does the same
+    registry.register(PRIMARY_LABEL, "p", ViewerMode::Docked);
+    assert_eq!(registry.get(&b).unwrap().mode, ViewerMode::Tiled);
+
+    registry.set_mode(PRIMARY_LABEL, ViewerMode::Floating).unwrap();
+    registry.set_mode(&a, ViewerMode::Tiled).unwrap();
+    assert!(registry.list().iter().all(|v| v.mode != ViewerMode::Docked));
+}
+
+#[test]
+fn unknown_viewers_cannot_change_mode() {
+    let registry = ViewerRegistry::default();
+    registry.register(PRIMARY_LABEL, "p", ViewerMode::Docked);
+
+    let error = registry.set_mode("file-viewer-7", ViewerMode::Docked).unwrap_err();
+    assert_eq!(error.to_string(), "No viewer named file-viewer-7");
+    // Nothing was undocked on the way
+    assert_eq!(registry.get(PRIMARY_LABEL).unwrap().mode, ViewerMode::Docked);
+}
@@ -0,0 +1,81 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+use notify::event::{CreateKind, DataChange, EventKind, MetadataKind, ModifyKind, RemoveKind};
+use tauriprompt_lib::watcher::{self, Debounce, EventFilter, WatchEvent};
+
+fn event(kind: EventKind, path: &str) -> notify::Event {
+    notify::Event::new(kind).add_path(PathBuf::from(path))
+}
+
+#[test]
+fn debounce_lets_one_event_through_per_interval() {
+    let debounce = Debounce::new(500);
+    assert!(debounce.ready(0));
+    assert!(!debounce.ready(1));
+    assert!(!debounce.ready(499));
+    assert!(debounce.ready(500));
+    assert!(!debounce.ready(900));
+    assert!(debounce.ready(1000));
+}
+
+#[test]
+fn a_burst_of_file_events_is_coalesced() {
+    let filter = EventFilter::new(None, 500);
+    let burst = [
+        event(EventKind::Create(CreateKind::File), "/p/a.rs"),
+        event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), "/p/a.rs"),
+        event(EventKind::Remove(RemoveKind::File), "/p/b.rs"),
+    ];
+    let accepted: Vec<_> = burst.iter()
+        .enumerate()
+        .filter_map(|(i, e)| filter.accept(e, 1000 + i as u64 * 10))
+        .collect();
+    assert_eq!(accepted, [WatchEvent::Created]);
+
+    let later = event(EventKind::Remove(RemoveKind::File), "/p/b.rs");
+    assert_eq!(filter.accept(&later, 1600), Some(WatchEvent::Deleted));
+}
+
+#[test]
+fn uninteresting_events_are_dropped_without_using_up_the_interval() {
+    let filter = EventFilter::new(None, 500);
+    let metadata = event(EventKind::Modify(ModifyKind::Metadata(MetadataKind::Permissions)), "/p/a.rs");
+    assert_eq!(filter.accept(&metadata, 0), None);
+    let git_churn = event(EventKind::Create(CreateKind::File), "/p/.git/objects/ab/cdef");
+    assert_eq!(filter.accept(&git_churn, 10), None);
+
+    let modified = event(EventKind::Modify(ModifyKind::Data(DataChange::Any)), "/p/a.rs");
+    assert_eq!(filter.accept(&modified, 20), Some(WatchEvent::Modified));
+}
+
+#[test]
+fn git_index_changes_are_debounced_separately() {
+    let filter = EventFilter::new(Some(PathBuf::from("/p/.git/index")), 500);
+    let index = event(EventKind::Modify(ModifyKind::Data(DataChange::Any)), "/p/.git/index");
+    let file = event(EventKind::Create(CreateKind::File), "/p/a.rs");
+
+    assert_eq!(filter.accept(&index, 0), Some(WatchEvent::GitStatus));
+    assert_eq!(filter.accept(&file, 10), Some(WatchEvent::Created));
+    assert_eq!(filter.accept(&index, 20), None);
+    assert_eq!(filter.accept(&index, 600), Some(WatchEvent::GitStatus));
+}
+
+#[test]
+fn watching_a_folder_reports_coalesced_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let (sender, receiver) = mpsc::channel();
+    let _watcher = watcher::watch(dir.path(), 2000, move |event| {
+        let _ = sender.send(event);
+    }).unwrap();
+
+    for i in 0..20 {
+        fs::write(dir.path().join(format!("{}.txt", i)), "x").unwrap();
+    }
+
+    let first = receiver.recv_timeout(Duration::from_secs(5)).expect("no event for the new files");
+    assert!(matches!(first, WatchEvent::Created | WatchEvent::Modified));
+    // Everything else happened well within the debounce interval
+    assert!(receiver.recv_timeout(Duration::from_millis(500)).is_err());
+}
@@ -0,0 +1,119 @@
+use std::path::Path;
+use tauriprompt_lib::error::AppError;
+use tauriprompt_lib::workspace::{self, Session, Workspace};
+
+fn workspace(name: &str, root: &str) -> Workspace {
+    Workspace {
+        id: String::new(),
+        name: name.to_string(),
+        root: root.to_string(),
+        checked_files: vec![format!("{}/src/main.rs", root)],
+        expanded_folders: vec![format!("{}/src", root)],
+        outline_files: Vec::new(),
+        search_query: "main".to_string(),
+        default_template: "Explain {{tree}}".to_string(),
+        updated_at: 0,
+    }
+}
+
+#[test]
+fn saved_workspaces_are_loaded_and_listed_by_name() {
+    let dir = tempfile::tempdir().unwrap();
+    let beta = workspace::save(dir.path(), workspace("beta", "/code/beta")).unwrap();
+    let alpha = workspace::save(dir.path(), workspace("", "/code/Alpha")).unwrap();
+
+    assert!(!beta.id.is_empty() && beta.id != alpha.id);
+    assert!(beta.updated_at > 0);
+    // Unnamed workspaces are named after their folder
+    assert_eq!(alpha.name, "Alpha");
+
+    let loaded = workspace::load(dir.path(), &beta.id).unwrap();
+    assert_eq!(loaded.checked_files, ["/code/beta/src/main.rs"]);
+    assert_eq!((loaded.search_query.as_str(), loaded.default_template.as_str()), ("main", "Explain {{tree}}"));
+
+    std::fs::write(dir.path().join("broken.json"), "{").unwrap();
+    let names: Vec<String> = workspace::list(dir.path()).unwrap().into_iter().map(|w| w.name).collect();
+    assert_eq!(names, ["Alpha", "beta"]);
+}
+
+#[test]
+fn saving_again_renames_in_place() {
+    let dir = tempfile::tempdir().unwrap();
+    let saved = workspace::save(dir.path(), workspace("old", "/code/app")).unwrap();
+
+    let renamed = workspace::save(dir.path(), Workspace { name: "new".to_string(), ..saved.clone() }).unwrap();
+    assert_eq!(renamed.id, saved.id);
+    let all = workspace::list(dir.path()).unwrap();
+    assert_eq!(all.len(), 1);
+    assert_eq!(all[0].name, "new");
+}
+
+#[test]
+fn deleted_workspaces_are_gone() {
+    let dir = tempfile::tempdir().unwrap();
+    let saved = workspace::save(dir.path(), workspace("app", "/code/app")).unwrap();
+
+    workspace::delete(dir.path(), &saved.id).unwrap();
+    assert!(workspace::list(dir.path()).unwrap().is_empty());
+    assert!(matches!(workspace::load(dir.path(), &saved.id), Err(AppError::NotFound { .. })));
+    assert!(matches!(workspace::delete(dir.path(), &saved.id), Err(AppError::NotFound { .. })));
+}
+
+#[test]
+fn ids_cannot_name_other_files() {
+    let dir = tempfile::tempdir().unwrap();
+    for id in ["../settings", "a/b", ""] {
+        assert!(matches!(workspace::load(dir.path(), id), Err(AppError::InvalidPath { .. })), "{}", id);
+    }
+    let escaping = Workspace { id: "../../x".to_string(), ..workspace("x", "/code/x") };
+    assert!(matches!(workspace::save(dir.path(), escaping), Err(AppError::InvalidPath { .. })));
+    // Listing a folder that was never created is not an error
+    assert!(workspace::list(&dir.path().join("none")).unwrap().is_empty());
+}
+
+#[test]
+fn sessions_reopen_workspaces_in_tab_order() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("workspaces");
+    let session_file = temp.path().join("session.json");
+    let a = workspace::save(&dir, workspace("a", "/code/a")).unwrap();
+    let b = workspace::save(&dir, workspace("b", "/code/b")).unwrap();
+
+    // Nothing saved yet
+    let empty = workspace::load_session(&session_file);
+    assert!(empty.open.is_empty() && empty.active.is_none());
+
+    let session = Session { open: vec![b.id.clone(), a.id.clone()], active: Some(a.id.clone()) };
+    workspace::save_session(&session_file, &session).unwrap();
+    let restored = workspace::restore_session(&dir, workspace::load_session(&session_file), |_| true);
+    let ids: Vec<&str> = restored.workspaces.iter().map(|w| w.id.as_str()).collect();
+    assert_eq!(ids, [b.id.as_str(), a.id.as_str()]);
+    assert_eq!(restored.active.as_deref(), Some(a.id.as_str()));
+
+    // Switching tabs only changes the active id
+    let switched = Session { active: Some(b.id.clone()), ..workspace::load_session(&session_file) };
+    workspace::save_session(&session_file, &switched).unwrap();
+    let restored = workspace::restore_session(&dir, workspace::load_session(&session_file), |_| true);
+    assert_eq!(restored.workspaces.len(), 2);
+    assert_eq!(restored.active.as_deref(), Some(b.id.as_str()));
+}
+
+#[test]
+fn sessions_skip_deleted_workspaces_and_unopenable_roots() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("workspaces");
+    let a = workspace::save(&dir, workspace("a", "/code/a")).unwrap();
+    let b = workspace::save(&dir, workspace("b", "/code/b")).unwrap();
+    let c = workspace::save(&dir, workspace("c", "/code/c")).unwrap();
+    workspace::delete(&dir, &c.id).unwrap();
+
+    let session = Session { open: vec![a.id.clone(), b.id.clone(), c.id.clone()], active: Some(b.id.clone()) };
+    let restored = workspace::restore_session(&dir, session, |root| root != Path::new("/code/b"));
+    let ids: Vec<&str> = restored.workspaces.iter().map(|w| w.id.as_str()).collect();
+    assert_eq!(ids, [a.id.as_str()]);
+    // The active workspace did not come back
+    assert_eq!(restored.active, None);
+
+    std::fs::write(temp.path().join("session.json"), "not json").unwrap();
+    assert!(workspace::load_session(&temp.path().join("session.json")).open.is_empty());
+}
@@ -4,42 +4,86 @@
 )]
 
 use std::{fs, path::{Path, PathBuf}};
-use std::sync::Mutex;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 use std::collections::HashSet;
 use tauri::{command, api::dialog, Manager, WindowEvent};
-use notify::{Watcher, RecursiveMode, EventKind};
-use std::sync::Arc;
+use tauriprompt_lib::{
+    api,
+    diff,
+    files,
+    git,
+    highlight,
+    history,
+    layout,
+    memory,
+    outline,
+    paging,
+    prompt,
+    scope,
+    selection,
+    settings,
+    stream,
+    tree,
+    viewers,
+    watcher,
+    workspace,
+};
+use tauriprompt_lib::error::AppError;
+use tauriprompt_lib::files::MAX_FILE_SIZE;
 
 // Constants
-const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024; // 10MB limit
 const DEBOUNCE_TIME: u64 = 500; // 500ms debounce time
-const MAX_DEPTH: u32 = 6; // Reduced max depth
-const BATCH_SIZE: usize = 50; // Batch size for processing
 const CHUNK_SIZE: usize = 500 * 1024; // 500KB chunks for streaming
-const CLEANUP_INTERVAL: u64 = 300; // 5 minutes
 const MEMORY_LIMIT: u64 = 512 * 1024 * 1024; // 512MB limit
 
 // File watcher state
 struct FileWatchState {
-    last_event: AtomicU64,
     watched_paths: Mutex<HashSet<String>>,
+    // Dropping the watcher stops it, so it lives here until stop_watching
+    watcher: Mutex<Option<notify::RecommendedWatcher>>,
 }
 
 impl Default for FileWatchState {
     fn default() -> Self {
         Self {
-            last_event: AtomicU64::new(0),
             watched_paths: Mutex::new(HashSet::new()),
+            watcher: Mutex::new(None),
         }
     }
 }
 
+// The rich clipboard is owned by this process on X11 and Wayland, so it has to
+// outlive the copy for other apps to paste from it
+#[derive(Default)]
+struct ClipboardState(Mutex<Option<arboard::Clipboard>>);
+
+// The local API server while it is enabled in the settings
+#[derive(Default)]
+struct ApiState {
+    server: Mutex<Option<api::Server>>,
+    // Why the server last failed to start, e.g. the port being taken
+    error: Mutex<Option<String>>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiStatus {
+    running: bool,
+    port: Option<u16>,
+    token_file: String,
+    error: Option<String>,
+}
+
 // State management
+// Layout of the main window's monitor, saved once windows stop moving
 struct ResizeState {
     last_resize: Instant,
+    // Set while a save is waiting for the windows to settle
     is_resizing: AtomicBool,
+    // Monitor key and its layout, as last loaded or changed
+    layout: Option<(String, layout::MonitorLayout)>,
 }
 
 impl Default for ResizeState {
@@ -47,238 +91,226 @@ impl Default for ResizeState {
         Self {
             last_resize: Instant::now(),
             is_resizing: AtomicBool::new(false),
+            layout: None,
         }
     }
 }
 
-// Memory monitoring
-struct MemoryMonitor {
-    last_cleanup: AtomicU64,
-    total_allocated: AtomicU64,
+// Failures from the window system, e.g. a viewer that could not be created or shown
+fn window_error(e: tauri::Error) -> AppError {
+    AppError::from(e.to_string()).context("window")
 }
 
-impl Default for MemoryMonitor {
-    fn default() -> Self {
-        Self {
-            last_cleanup: AtomicU64::new(0),
-            total_allocated: AtomicU64::new(0),
-        }
-    }
+// With the default config this is the platform data directory itself, which
+// other applications share; scope::APP_DIRS limits what is read from it
+fn app_dir_or_io_error() -> Result<PathBuf, AppError> {
+    tauri::api::path::app_data_dir(&tauri::Config::default())
+        .ok_or_else(|| AppError::from("Could not get app directory"))
 }
 
-impl MemoryMonitor {
-    fn check_and_cleanup(&self) -> Result<(), String> {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_err(|e| e.to_string())?
-            .as_secs();
-        
-        let last = self.last_cleanup.load(Ordering::Relaxed);
-        if now - last > CLEANUP_INTERVAL {
-            self.total_allocated.store(0, Ordering::Relaxed);
-            self.last_cleanup.store(now, Ordering::Relaxed);
-        }
-        Ok(())
-    }
+// Caller-supplied path below the app data directory, see scope::join_relative
+fn app_data_path(relative: &str) -> Result<PathBuf, AppError> {
+    scope::app_data_path(&app_dir_or_io_error()?, relative)
+}
 
-    fn allocate(&self, size: u64) -> Result<(), String> {
-        let current = self.total_allocated.load(Ordering::Relaxed);
-        if current + size > MEMORY_LIMIT {
-            return Err("Memory limit exceeded".to_string());
-        }
-        self.total_allocated.fetch_add(size, Ordering::Relaxed);
-        Ok(())
-    }
+// Scope checks for commands that read files from opened folders
+fn check_read(window: &tauri::Window, path: &str) -> Result<PathBuf, AppError> {
+    window.state::<scope::Scope>().check_read(Path::new(path), &app_dir_or_io_error()?)
 }
 
-#[derive(serde::Serialize)]
-struct FileInfo {
-    name: String,
-    path: String,
-    is_directory: bool,
-    children: Option<Vec<FileInfo>>
+// `file` as given to git: relative to the repository at `root`, or absolute
+fn check_read_in(window: &tauri::Window, root: &str, file: &str) -> Result<PathBuf, AppError> {
+    check_read(window, &Path::new(root).join(file).to_string_lossy())
 }
 
-// File functions
-#[command]
-async fn read_file(path: String) -> Result<String, String> {
-    let metadata = fs::metadata(&path).map_err(|e| e.to_string())?;
+// A text file of at most MAX_FILE_SIZE bytes
+fn read_text(path: &Path) -> Result<String, AppError> {
+    let metadata = fs::metadata(path).map_err(|e| AppError::io(path, e))?;
     if metadata.len() > MAX_FILE_SIZE {
-        return Err("File too large to read".to_string());
+        return Err(AppError::TooLarge {
+            path: Some(path.to_string_lossy().into_owned()),
+            size: metadata.len(),
+            limit: MAX_FILE_SIZE,
+        });
     }
-    fs::read_to_string(path).map_err(|e| e.to_string())
+    fs::read_to_string(path).map_err(|e| AppError::io(path, e))
+}
+
+// File functions
+#[command]
+async fn read_file(path: String, window: tauri::Window) -> Result<String, AppError> {
+    read_text(&check_read(&window, &path)?)
 }
 
 #[command]
-async fn select_folder() -> Result<String, String> {
+async fn select_folder(window: tauri::Window) -> Result<String, AppError> {
     let folder = dialog::blocking::FileDialogBuilder::new()
         .pick_folder()
         .ok_or("No folder selected")?;
+    // Picking a folder is what grants read access to it
+    window.state::<scope::Scope>().allow_root(&folder)?;
     Ok(folder.to_string_lossy().into_owned())
 }
 
 #[command]
-fn list_files(path: String, depth: Option<u32>) -> Result<Vec<FileInfo>, String> {
-    read_dir_recursive(Path::new(&path), depth.unwrap_or(0), 0)
+fn list_files(path: String, depth: Option<u32>, window: tauri::Window) -> Result<Vec<files::FileInfo>, AppError> {
+    check_read(&window, &path)?;
+    let settings = load_settings(&window.app_handle())?;
+    let statuses = git::StatusMap::load(Path::new(&path));
+    let walk = files::Walk { exclude: &settings.ignore_rules, ..Default::default() };
+    files::read_dir_filtered(Path::new(&path), depth.unwrap_or(0), 0, &statuses, &walk)
 }
 
-fn read_dir_recursive(path: &Path, max_depth: u32, current_depth: u32) -> Result<Vec<FileInfo>, String> {
-    if current_depth > max_depth || current_depth > MAX_DEPTH {
-        return Ok(Vec::new());
-    }
+// Prompt assembly
+#[command]
+async fn build_prompt(request: prompt::PromptRequest, window: tauri::Window) -> Result<prompt::BuiltPrompt, AppError> {
+    build_in_scope(&window.app_handle(), request)
+}
 
-    let mut result = Vec::new();
-    let entries: Vec<_> = fs::read_dir(path)
-        .map_err(|e| e.to_string())?
-        .filter_map(Result::ok)
-        .collect();
+// Builds with the settings' format and ignore rules, refusing files outside the opened folders
+fn build_in_scope(app_handle: &tauri::AppHandle, mut request: prompt::PromptRequest) -> Result<prompt::BuiltPrompt, AppError> {
+    let settings = load_settings(app_handle)?;
+    request.format.get_or_insert(settings.default_format);
+    request.ignore_rules.extend(settings.ignore_rules);
 
-    for chunk in entries.chunks(BATCH_SIZE) {
-        let chunk_len = chunk.len();
-        for entry in chunk.iter() {
-            let file_type = entry.file_type().map_err(|e| e.to_string())?;
-            let path_buf = entry.path();
-            let name = path_buf.file_name()
-                .ok_or("Invalid filename")?
-                .to_string_lossy()
-                .into_owned();
-
-            if file_type.is_dir() {
-                let children = if current_depth < max_depth {
-                    Some(read_dir_recursive(&path_buf, max_depth, current_depth + 1)?)
-                } else {
-                    None
-                };
-
-                result.push(FileInfo {
-                    name,
-                    path: path_buf.to_string_lossy().into_owned(),
-                    is_directory: true,
-                    children
-                });
-            } else {
-                result.push(FileInfo {
-                    name,
-                    path: path_buf.to_string_lossy().into_owned(),
-                    is_directory: false,
-                    children: None
-                });
-            }
-        }
-        
-        if chunk_len == BATCH_SIZE {
-            std::thread::sleep(Duration::from_millis(1));
+    let scope = app_handle.state::<scope::Scope>();
+    let app_dir = app_dir_or_io_error()?;
+    let paths = request.files.iter().map(|f| selection::Selection::parse(f).path);
+    for path in request.root.iter().cloned().chain(paths) {
+        // Missing files are reported in the prompt itself; only refuse out-of-scope ones
+        if let Err(e @ AppError::OutsideScope { .. }) = scope.check_read(Path::new(&path), &app_dir) {
+            return Err(e);
         }
     }
+    prompt::build(&request, MAX_FILE_SIZE)
+}
 
-    result.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-    Ok(result)
+#[command]
+async fn file_outline(path: String, window: tauri::Window) -> Result<String, AppError> {
+    let content = read_text(&check_read(&window, &path)?)?;
+    outline::outline(Path::new(&path), &content)
+}
+
+#[command]
+async fn render_tree(
+    path: String,
+    options: Option<tree::TreeOptions>,
+    included: Option<Vec<String>>,
+    window: tauri::Window
+) -> Result<String, AppError> {
+    check_read(&window, &path)?;
+    let mut options = options.unwrap_or_default();
+    options.exclude.extend(load_settings(&window.app_handle())?.ignore_rules);
+    let included = included.unwrap_or_default().into_iter().collect();
+    tree::render_dir(Path::new(&path), &options, &included)
+}
+
+// Git integration
+#[command]
+async fn git_modified_files(path: String, window: tauri::Window) -> Result<Vec<String>, AppError> {
+    check_read(&window, &path)?;
+    git::modified_files(Path::new(&path))
+}
+
+#[command]
+async fn git_recent_commits(
+    path: String,
+    files: Vec<String>,
+    limit: Option<usize>,
+    window: tauri::Window
+) -> Result<Vec<git::CommitInfo>, AppError> {
+    check_read(&window, &path)?;
+    for file in &files {
+        check_read_in(&window, &path, file)?;
+    }
+    git::recent_commits(Path::new(&path), &files, limit.unwrap_or(git::DEFAULT_COMMIT_LIMIT))
+}
+
+#[command]
+async fn git_blame(
+    path: String,
+    file: String,
+    start_line: Option<usize>,
+    end_line: Option<usize>,
+    window: tauri::Window
+) -> Result<Vec<git::BlameRange>, AppError> {
+    check_read(&window, &path)?;
+    check_read_in(&window, &path, &file)?;
+    let lines = start_line.zip(end_line);
+    git::blame(Path::new(&path), &file, lines)
 }
 
 // File watcher
 #[command]
-async fn watch_directory(path: String, window: tauri::Window) -> Result<(), String> {
+async fn watch_directory(path: String, window: tauri::Window) -> Result<(), AppError> {
+    check_read(&window, &path)?;
     let watch_state = window.state::<FileWatchState>();
     
     // Clean up previous watches
     {
-        let mut paths = watch_state.watched_paths.lock().map_err(|e| e.to_string())?;
+        let mut paths = watch_state.watched_paths.lock().unwrap_or_else(|e| e.into_inner());
         paths.clear();
         paths.insert(path.clone());
     }
 
-    let window = window.clone();
-    
-    struct EventHandlerImpl {
-        window: tauri::Window,
-        last_event: Arc<AtomicU64>,
-    }
-
-    let last_event = Arc::new(AtomicU64::new(0));
-    let handler = EventHandlerImpl {
-        window,
-        last_event: last_event.clone(),
-    };
-
-    let mut watcher = notify::recommended_watcher(move |res: Result<notify::Event, notify::Error>| {
-        if let Ok(event) = res {
-            let event_type = match event.kind {
-                notify::EventKind::Create(_) => Some("fs-created"),
-                notify::EventKind::Remove(_) => Some("fs-deleted"),
-                notify::EventKind::Modify(k) => match k {
-                    notify::event::ModifyKind::Data(_) => Some("fs-modified"),
-                    _ => None,
-                },
-                _ => None,
-            };
-
-            if let Some(event_type) = event_type {
-                let now = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_millis() as u64;
-                
-                let last = handler.last_event.load(Ordering::Relaxed);
-                if now - last < DEBOUNCE_TIME {
-                    return;
-                }
-                
-                handler.last_event.store(now, Ordering::Relaxed);
-                let _ = handler.window.emit(event_type, ());
-            }
-        }
-    }).map_err(|e| e.to_string())?;
-
-    watcher.watch(path.as_ref(), RecursiveMode::Recursive)
-        .map_err(|e| e.to_string())?;
-
+    let emitter = window.clone();
+    let watcher = watcher::watch(Path::new(&path), DEBOUNCE_TIME, move |event| {
+        let _ = emitter.emit(event.name(), ());
+    })?;
+    *watch_state.watcher.lock().unwrap_or_else(|e| e.into_inner()) = Some(watcher);
     Ok(())
 }
 #[command]
-async fn stop_watching(window: tauri::Window) -> Result<(), String> {
+async fn stop_watching(window: tauri::Window) -> Result<(), AppError> {
     let watch_state = window.state::<FileWatchState>();
-    let mut paths = watch_state.watched_paths.lock().map_err(|e| e.to_string())?;
+    let mut paths = watch_state.watched_paths.lock().unwrap_or_else(|e| e.into_inner());
     paths.clear();
+    watch_state.watcher.lock().unwrap_or_else(|e| e.into_inner()).take();
     Ok(())
 }
 
 // Explorer Integration
+fn explorer_error(e: std::io::Error) -> AppError {
+    AppError::from(e.to_string()).context("Could not open the file manager")
+}
+
 #[command]
-async fn open_in_explorer(path: String) -> Result<(), String> {
+async fn open_in_explorer(path: String, window: tauri::Window) -> Result<(), AppError> {
+    check_read(&window, &path)?;
     #[cfg(target_os = "windows")]
     {
         use std::process::Command;
         let path_str = path.as_str();
-        let metadata = std::fs::metadata(path_str).map_err(|e| e.to_string())?;
+        let metadata = std::fs::metadata(path_str).map_err(|e| AppError::io(Path::new(path_str), e))?;
         
         if metadata.is_dir() {
             Command::new("explorer.exe")
                 .arg(path_str)
                 .spawn()
-                .map_err(|e| e.to_string())?;
+                .map_err(explorer_error)?;
         } else {
             Command::new("explorer.exe")
                 .args(["/select,", path_str])
                 .spawn()
-                .map_err(|e| e.to_string())?;
+                .map_err(explorer_error)?;
         }
     }
 
     #[cfg(target_os = "macos")]
     {
         use std::process::Command;
-        let metadata = std::fs::metadata(&path).map_err(|e| e.to_string())?;
+        let metadata = std::fs::metadata(&path).map_err(|e| AppError::io(Path::new(&path), e))?;
         
         if metadata.is_dir() {
             Command::new("open")
                 .arg(&path)
                 .spawn()
-                .map_err(|e| e.to_string())?;
+                .map_err(explorer_error)?;
         } else {
             Command::new("open")
                 .args(["-R", &path])
                 .spawn()
-                .map_err(|e| e.to_string())?;
+                .map_err(explorer_error)?;
         }
     }
 
@@ -292,13 +324,13 @@ async fn open_in_explorer(path: String) -> Result<(), String> {
         } else if Command::new("dolphin").output().is_ok() {
             "dolphin"
         } else {
-            return Err("No supported file manager found".to_string());
+            return Err("No supported file manager found".into());
         };
 
         Command::new(file_manager)
             .arg(&path)
             .spawn()
-            .map_err(|e| e.to_string())?;
+            .map_err(explorer_error)?;
     }
 
     Ok(())
@@ -306,47 +338,193 @@ async fn open_in_explorer(path: String) -> Result<(), String> {
 
 // History management
 #[command]
-async fn write_history(path: String, content: String, window: tauri::Window) -> Result<(), String> {
-    let memory_monitor = window.state::<MemoryMonitor>();
-    memory_monitor.check_and_cleanup()?;
-    memory_monitor.allocate(content.len() as u64)?;
+async fn write_history(path: String, content: String, window: tauri::Window) -> Result<(), AppError> {
+    let _allocation = window.state::<memory::MemoryMonitor>()
+        .reserve(memory::Subsystem::History, content.len() as u64)?;
 
-    let app_dir = tauri::api::path::app_data_dir(&tauri::Config::default())
-        .ok_or("Could not get app directory")?;
-    let full_path = app_dir.join(path);
-    
-    if let Some(parent) = full_path.parent() {
-        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-    }
-    
-    fs::write(full_path, content).map_err(|e| e.to_string())
+    history::write(&app_data_path(&path)?, &app_data_path(history::HISTORY_DIR)?, &content)
 }
 
 #[command]
-async fn delete_history_file(path: String) -> Result<(), String> {
-    fs::remove_file(&path).map_err(|e| e.to_string())?;
-    
-    if let Some(parent) = Path::new(&path).parent() {
-        if parent.exists() {
-            let is_empty = fs::read_dir(parent)
-                .map_err(|e| e.to_string())?
-                .next()
-                .is_none();
-            
-            if is_empty {
-                fs::remove_dir_all(parent).map_err(|e| e.to_string())?;
+async fn delete_history_file(path: String) -> Result<(), AppError> {
+    history::delete(Path::new(&path), &app_dir_or_io_error()?.join(history::HISTORY_DIR))
+}
+
+// Settings
+#[command]
+async fn get_settings(window: tauri::Window) -> Result<settings::Settings, AppError> {
+    window.state::<settings::SettingsStore>().load(&app_data_path(settings::SETTINGS_DIR)?)
+}
+
+// Merges `patch` into the stored settings and tells every window about the result
+#[command]
+async fn update_settings(patch: serde_json::Value, window: tauri::Window) -> Result<settings::Settings, AppError> {
+    let updated = window.state::<settings::SettingsStore>()
+        .update(&app_data_path(settings::SETTINGS_DIR)?, patch)?;
+    // The patch may have changed the window layouts
+    window.state::<Mutex<ResizeState>>().lock().unwrap_or_else(|e| e.into_inner()).layout = None;
+    refresh_viewers(&window.app_handle()).map_err(window_error)?;
+    window.app_handle().emit_all("settings-changed", &updated).map_err(window_error)?;
+    sync_api(&window.app_handle(), &updated);
+    Ok(updated)
+}
+
+// Local API
+// Starts, stops or moves the server to match the settings
+fn sync_api(app_handle: &tauri::AppHandle, settings: &settings::Settings) {
+    let state = app_handle.state::<ApiState>();
+    let mut server = state.server.lock().unwrap_or_else(|e| e.into_inner());
+    let wanted = settings.api_enabled.then_some(settings.api_port);
+    if server.as_ref().map(api::Server::port) == wanted {
+        return;
+    }
+
+    // The old server has to let go of its port before a new one can take it
+    *server = None;
+    let started = wanted.map(|port| start_api(app_handle, port)).transpose();
+    let mut error = state.error.lock().unwrap_or_else(|e| e.into_inner());
+    match started {
+        Ok(started) => {
+            *server = started;
+            *error = None;
+        }
+        Err(e) => {
+            eprintln!("Error starting the local API: {}", e);
+            *error = Some(e.to_string());
+        }
+    }
+}
+
+fn start_api(app_handle: &tauri::AppHandle, port: u16) -> Result<api::Server, AppError> {
+    let token = api::load_or_create_token(&app_dir_or_io_error()?.join(api::TOKEN_FILE))?;
+    let app_handle = app_handle.clone();
+    api::Server::start(port, token, Arc::new(move |method: &str, params: serde_json::Value| {
+        api_call(&app_handle, method, params)
+    }))
+}
+
+// Opens a folder in a new tab
+#[derive(serde::Deserialize)]
+struct OpenFolder {
+    root: Option<String>,
+}
+
+// Workspaces and prompt building are answered here; anything about tabs is
+// forwarded to the main window, which owns them
+fn api_call(app_handle: &tauri::AppHandle, method: &str, params: serde_json::Value) -> Result<serde_json::Value, api::RpcError> {
+    match method {
+        "app.ping" => Ok(serde_json::json!({ "version": app_handle.package_info().version.to_string() })),
+        "workspace.list" => {
+            api::to_result(workspace::list(&app_data_path(workspace::WORKSPACES_DIR)?)?)
+        }
+        "prompt.build" => {
+            let request: prompt::PromptRequest = api::params(params)?;
+            api::to_result(build_in_scope(app_handle, request)?)
+        }
+        "tabs.list" | "tabs.open" | "selection.get" | "selection.add" | "selection.remove" | "prompt.set" | "prompt.copy" => {
+            if method == "tabs.open" {
+                if let Some(root) = api::params::<OpenFolder>(params.clone())?.root {
+                    allow_api_root(app_handle, &root)?;
+                }
             }
+            let window = app_handle.get_window("main").ok_or_else(|| AppError::from("Main window not found"))?;
+            app_handle.state::<api::UiBridge>().call(method, params, |request| {
+                window.emit("api-request", request).map_err(|e| AppError::from(e.to_string()))
+            })
         }
+        _ => Err(api::RpcError::method_not_found(method)),
     }
-    
-    Ok(())
 }
 
+// A folder an API client wants opened. Folders the user already opened are
+// fine; any other needs the user's go-ahead before it becomes readable.
+fn allow_api_root(app_handle: &tauri::AppHandle, root: &str) -> Result<(), AppError> {
+    let scope = app_handle.state::<scope::Scope>();
+    match scope.check_read(Path::new(root), &app_dir_or_io_error()?) {
+        Ok(_) => return Ok(()),
+        Err(AppError::OutsideScope { .. }) => {}
+        Err(e) => return Err(e),
+    }
+    let main_window = app_handle.get_window("main");
+    let message = format!("A program connected to the API wants to open {}.\n\nAllow it to read this folder?", root);
+    if !dialog::blocking::ask(main_window.as_ref(), "Open folder", message) {
+        return Err(AppError::OutsideScope { path: root.to_string() });
+    }
+    scope.allow_root(Path::new(root))
+}
+
+// The main window's answer to an `api-request` event
 #[command]
-fn get_app_data_dir() -> Result<String, String> {
-    tauri::api::path::app_data_dir(&tauri::Config::default())
-        .ok_or("Could not get app directory".to_string())
-        .map(|path| path.to_string_lossy().into_owned())
+fn api_respond(id: u64, result: Option<serde_json::Value>, error: Option<String>, window: tauri::Window) {
+    let result = match error {
+        Some(error) => Err(error),
+        None => Ok(result.unwrap_or_default()),
+    };
+    window.state::<api::UiBridge>().resolve(id, result);
+}
+
+#[command]
+fn api_status(window: tauri::Window) -> Result<ApiStatus, AppError> {
+    let state = window.state::<ApiState>();
+    let port = state.server.lock().unwrap_or_else(|e| e.into_inner()).as_ref().map(api::Server::port);
+    let error = state.error.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    Ok(ApiStatus {
+        running: port.is_some(),
+        port,
+        token_file: app_dir_or_io_error()?.join(api::TOKEN_FILE).to_string_lossy().into_owned(),
+        error,
+    })
+}
+
+// Workspaces
+#[command]
+async fn save_workspace(workspace: workspace::Workspace, window: tauri::Window) -> Result<workspace::Workspace, AppError> {
+    // Only folders opened in this session can be saved, since opening one later grants access to it
+    check_read(&window, &workspace.root)?;
+    workspace::save(&app_data_path(workspace::WORKSPACES_DIR)?, workspace)
+}
+
+#[command]
+async fn list_workspaces() -> Result<Vec<workspace::Workspace>, AppError> {
+    workspace::list(&app_data_path(workspace::WORKSPACES_DIR)?)
+}
+
+#[command]
+async fn open_workspace(id: String, window: tauri::Window) -> Result<workspace::Workspace, AppError> {
+    let workspace = workspace::load(&app_data_path(workspace::WORKSPACES_DIR)?, &id)?;
+    window.state::<scope::Scope>().allow_root(Path::new(&workspace.root))?;
+    Ok(workspace)
+}
+
+#[command]
+async fn delete_workspace(id: String) -> Result<(), AppError> {
+    workspace::delete(&app_data_path(workspace::WORKSPACES_DIR)?, &id)
+}
+
+#[command]
+async fn save_session(session: workspace::Session) -> Result<(), AppError> {
+    workspace::save_session(&app_data_path(workspace::SESSION_FILE)?, &session)
+}
+
+// Reopens the workspaces that were open at last exit, skipping any whose
+// file or root has since disappeared
+#[command]
+async fn restore_session(window: tauri::Window) -> Result<workspace::RestoredSession, AppError> {
+    let dir = app_data_path(workspace::WORKSPACES_DIR)?;
+    let session = workspace::load_session(&app_data_path(workspace::SESSION_FILE)?);
+    let scope = window.state::<scope::Scope>();
+    Ok(workspace::restore_session(&dir, session, |root| scope.allow_root(root).is_ok()))
+}
+
+// Bytes currently reserved by large buffers, per subsystem
+#[command]
+fn memory_stats(window: tauri::Window) -> memory::MemoryStats {
+    window.state::<memory::MemoryMonitor>().stats()
+}
+
+#[command]
+fn get_app_data_dir() -> Result<String, AppError> {
+    app_dir_or_io_error().map(|path| path.to_string_lossy().into_owned())
 }
 
 // File viewer window management
@@ -355,26 +533,183 @@ async fn create_file_window(
     window: tauri::Window, 
     title: String, 
     content: String, 
-    theme: Option<String>
-) -> Result<(), String> {
-    let memory_monitor = window.state::<MemoryMonitor>();
-    memory_monitor.check_and_cleanup()?;
-    
+    theme: Option<String>,
+    new_window: Option<bool>,
+    mode: Option<viewers::ViewerMode>
+) -> Result<String, AppError> {
     if content.len() as u64 > MAX_FILE_SIZE {
-        return Err("File too large to display".to_string());
+        return Err(AppError::TooLarge { path: None, size: content.len() as u64, limit: MAX_FILE_SIZE });
+    }
+
+    // Streamed content is held until the viewer has it all; see finish_transfer
+    let streamed = content.len() > CHUNK_SIZE;
+    let subsystem = if streamed { memory::Subsystem::Transfers } else { memory::Subsystem::Viewer };
+    let allocation = window.state::<memory::MemoryMonitor>().reserve(subsystem, content.len() as u64)?;
+    let label = viewer_label(&window, new_window);
+    let file_viewer = prepare_viewer(&window, &label, &title, mode, &theme).await?;
+
+    // Then set the content, streaming it in numbered chunks when large
+    if streamed {
+        let transfers = window.state::<stream::TransferStore>();
+        let start = transfers.start(&label, &content, CHUNK_SIZE, &title, theme.clone(), allocation);
+        file_viewer.emit("stream-start", &start).map_err(window_error)?;
+        for index in 0..start.total {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let chunk = transfers.chunk(&start.transfer_id, index)?;
+            file_viewer.emit("stream-chunk", chunk).map_err(window_error)?;
+        }
+    } else {
+        file_viewer.emit("set-content", serde_json::json!({
+            "content": content,
+            "filePath": title,
+            "theme": theme
+        })).map_err(window_error)?;
+    }
+
+    file_viewer.show().map_err(window_error)?;
+    refresh_viewers(&window.app_handle()).map_err(window_error)?;
+    Ok(label)
+}
+
+// Opens the viewer on a file or history snapshot without sending its content;
+// the viewer pages through it with read_page
+#[command]
+async fn open_viewer(
+    window: tauri::Window,
+    title: String,
+    source: paging::PageSource,
+    theme: Option<String>,
+    new_window: Option<bool>,
+    mode: Option<viewers::ViewerMode>
+) -> Result<String, AppError> {
+    // Fail here rather than in an empty viewer when the source is out of scope,
+    // missing or binary; this also indexes it for the first page
+    let target = resolve_page_source(&window, &source)?;
+    let memory_monitor = window.state::<memory::MemoryMonitor>();
+    window.state::<paging::PageStore>().read(&target, 0, 0, &memory_monitor)?;
+    let theme = match theme {
+        Some(theme) => Some(theme),
+        None => window.state::<settings::SettingsStore>()
+            .load(&app_data_path(settings::SETTINGS_DIR)?)
+            .ok()
+            .map(|settings| settings.theme),
+    };
+
+    let label = viewer_label(&window, new_window);
+    let file_viewer = prepare_viewer(&window, &label, &title, mode, &theme).await?;
+    file_viewer.emit("set-source", serde_json::json!({
+        "source": source,
+        "title": title,
+        "theme": theme
+    })).map_err(window_error)?;
+    file_viewer.show().map_err(window_error)?;
+    refresh_viewers(&window.app_handle()).map_err(window_error)?;
+    Ok(label)
+}
+
+fn resolve_page_source(window: &tauri::Window, source: &paging::PageSource) -> Result<paging::Target, AppError> {
+    match source {
+        paging::PageSource::File { path } => Ok(paging::Target::File(check_read(window, path)?)),
+        // Like deletion, only files inside the history folder count as entries
+        paging::PageSource::History { entry, file } => Ok(paging::Target::History {
+            entry: history::entry_path(&app_dir_or_io_error()?.join(history::HISTORY_DIR), entry)?,
+            file: file.clone(),
+        }),
+    }
+}
+
+// Lines `start..start + count` (0-based) of a file or history snapshot,
+// highlighted in `theme` when one is given. Each page starts highlighting
+// afresh, so a construct spanning a page boundary may be colored as plain text.
+#[command]
+async fn read_page(
+    source: paging::PageSource,
+    start: usize,
+    count: usize,
+    theme: Option<String>,
+    window: tauri::Window
+) -> Result<paging::Page, AppError> {
+    let target = resolve_page_source(&window, &source)?;
+    let memory_monitor = window.state::<memory::MemoryMonitor>();
+    let mut page = window.state::<paging::PageStore>().read(&target, start, count, &memory_monitor)?;
+    if let Some(theme) = theme {
+        let path = match &source {
+            paging::PageSource::File { path } => path,
+            paging::PageSource::History { file, .. } => file,
+        };
+        page.spans = Some(highlight::lines(path, page.lines.iter().map(String::as_str), &theme));
     }
+    Ok(page)
+}
+
+// Highlighting for content the viewer already holds, one list of spans per line
+#[command]
+async fn highlight_text(path: String, text: String, theme: String) -> Vec<Vec<highlight::Span>> {
+    highlight::lines(&path, text.lines(), &theme)
+}
+
+// `text` as a self-contained highlighted HTML block
+#[command]
+async fn highlight_html(path: String, text: String, theme: String) -> String {
+    highlight::document(&highlight::html(&path, &text, &theme), &theme)
+}
 
-    memory_monitor.allocate(content.len() as u64)?;
+fn clipboard_error(e: arboard::Error) -> AppError {
+    AppError::from(e.to_string()).context("clipboard")
+}
+
+// Puts both a plain and an HTML flavor on the clipboard, so rich editors
+// paste the highlighted version and everything else the plain text
+#[command]
+fn write_rich_clipboard(text: String, html: String, window: tauri::Window) -> Result<(), AppError> {
+    let state = window.state::<ClipboardState>();
+    let mut clipboard = state.0.lock().unwrap_or_else(|e| e.into_inner());
+    let clipboard = match clipboard.as_mut() {
+        Some(clipboard) => clipboard,
+        None => clipboard.insert(arboard::Clipboard::new().map_err(clipboard_error)?),
+    };
+    clipboard.set_html(html, Some(text)).map_err(clipboard_error)
+}
+
+// The shared viewer, or a fresh label when a separate window is wanted
+fn viewer_label(window: &tauri::Window, new_window: Option<bool>) -> String {
+    if new_window.unwrap_or(false) {
+        window.state::<viewers::ViewerRegistry>().new_label()
+    } else {
+        viewers::PRIMARY_LABEL.to_string()
+    }
+}
+
+// Readies the viewer window `label` next to `window`, creating it on first use.
+// The viewer keeps its current mode unless `mode` is given; a new shared viewer
+// opens in the mode saved for the monitor and extra ones are tiled beside it.
+async fn prepare_viewer(
+    window: &tauri::Window,
+    label: &str,
+    title: &str,
+    mode: Option<viewers::ViewerMode>,
+    theme: &Option<String>
+) -> Result<tauri::Window, AppError> {
     let app_handle = window.app_handle();
-    
-    let file_viewer = if let Some(file_viewer) = app_handle.get_window("file-viewer") {
+    let (monitor, saved) = active_layout(&app_handle).ok_or("Could not find the main window's monitor")?;
+    let registry = window.state::<viewers::ViewerRegistry>();
+    let mode = mode
+        .or_else(|| registry.get(label).map(|viewer| viewer.mode))
+        .unwrap_or(match saved.viewer_mode {
+            viewers::ViewerMode::Floating => viewers::ViewerMode::Floating,
+            _ if label != viewers::PRIMARY_LABEL => viewers::ViewerMode::Tiled,
+            saved_mode => saved_mode,
+        });
+    registry.register(label, title, mode);
+
+    let file_viewer = if let Some(file_viewer) = app_handle.get_window(label) {
         // Clear existing content before setting new content
-        file_viewer.emit("clear-content", ()).map_err(|e| e.to_string())?;
+        file_viewer.emit("clear-content", ()).map_err(window_error)?;
         file_viewer
     } else {
         let file_viewer = tauri::WindowBuilder::new(
             &app_handle,
-            "file-viewer",
+            label,
             tauri::WindowUrl::App("file-viewer.html".into())
         )
         .title("File Viewer")
@@ -384,122 +719,395 @@ async fn create_file_window(
         .transparent(true)
         .skip_taskbar(true)
         .build()
-        .map_err(|e| e.to_string())?;
+        .map_err(window_error)?;
 
         // Give the window a moment to initialize
         tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Floating viewers start where the last one was left, or where a docked
+        // one would be; attached ones are placed by refresh_viewers once shown
+        let main = window_rect(window).map_err(window_error)?;
+        let rect = match saved.floating {
+            Some(floating) => monitor.fit(monitor.to_physical(&floating)),
+            None => layout::Rect {
+                x: main.x + main.width as i32,
+                y: main.y,
+                width: monitor.dock_size(&saved),
+                height: main.height,
+            },
+        };
+        set_window_rect(&file_viewer, rect).map_err(window_error)?;
         file_viewer
     };
 
-    let main_position = window.outer_position().map_err(|e| e.to_string())?;
-    let main_size = window.outer_size().map_err(|e| e.to_string())?;
-    
-    file_viewer.set_position(tauri::Position::Physical(tauri::PhysicalPosition {
-        x: main_position.x + main_size.width as i32,
-        y: main_position.y,
-    })).map_err(|e| e.to_string())?;
-    
-    file_viewer.set_size(tauri::Size::Physical(tauri::PhysicalSize {
-        width: 1600,
-        height: main_size.height,
-    })).map_err(|e| e.to_string())?;
-
     // Send the theme first
-    if let Some(theme) = &theme {
+    if let Some(theme) = theme {
         file_viewer.emit("theme-update", serde_json::json!({ "theme": theme }))
-            .map_err(|e| e.to_string())?;
+            .map_err(window_error)?;
         
         // Give the theme a moment to apply
         tokio::time::sleep(Duration::from_millis(50)).await;
     }
 
-    // Then set the content
-    if content.len() > CHUNK_SIZE {
-        for chunk in content.as_bytes().chunks(CHUNK_SIZE) {
-            if let Ok(chunk_str) = String::from_utf8(chunk.to_vec()) {
-                tokio::time::sleep(Duration::from_millis(10)).await;
-                file_viewer.emit("append-content", serde_json::json!({
-                    "content": chunk_str,
-                    "filePath": &title,
-                    "theme": &theme
-                })).map_err(|e| e.to_string())?;
-            }
+    Ok(file_viewer)
+}
+
+#[command]
+async fn list_viewers(window: tauri::Window) -> viewers::ViewerLayout {
+    viewer_layout(&window.app_handle())
+}
+
+// Docks, tiles or floats a viewer window; `side` moves the dock for every
+// attached viewer on this monitor
+#[command]
+async fn set_viewer_mode(
+    label: String,
+    mode: viewers::ViewerMode,
+    side: Option<layout::DockSide>,
+    window: tauri::Window
+) -> Result<(), AppError> {
+    let app_handle = window.app_handle();
+    app_handle.state::<viewers::ViewerRegistry>().set_mode(&label, mode)?;
+    remember_layout(&app_handle, |_, saved| {
+        if let Some(side) = side {
+            saved.dock_side = side;
+        }
+        if label == viewers::PRIMARY_LABEL {
+            saved.viewer_mode = mode;
+        }
+    });
+    refresh_viewers(&app_handle).map_err(window_error)
+}
+
+fn monitor_info(monitor: &tauri::Monitor) -> layout::MonitorInfo {
+    let position = monitor.position();
+    let size = monitor.size();
+    layout::MonitorInfo::new(
+        monitor.name().map(String::as_str),
+        layout::Rect { x: position.x, y: position.y, width: size.width, height: size.height },
+        monitor.scale_factor(),
+    )
+}
+
+fn window_rect(window: &tauri::Window) -> tauri::Result<layout::Rect> {
+    let position = window.outer_position()?;
+    let size = window.outer_size()?;
+    Ok(layout::Rect { x: position.x, y: position.y, width: size.width, height: size.height })
+}
+
+fn set_window_rect(window: &tauri::Window, rect: layout::Rect) -> tauri::Result<()> {
+    window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x: rect.x, y: rect.y }))?;
+    window.set_size(tauri::Size::Physical(tauri::PhysicalSize { width: rect.width, height: rect.height }))
+}
+
+fn load_settings(app_handle: &tauri::AppHandle) -> Result<settings::Settings, AppError> {
+    app_handle.state::<settings::SettingsStore>().load(&app_data_path(settings::SETTINGS_DIR)?)
+}
+
+// The main window's monitor and the layout remembered for it
+fn active_layout(app_handle: &tauri::AppHandle) -> Option<(layout::MonitorInfo, layout::MonitorLayout)> {
+    let main_window = app_handle.get_window("main")?;
+    let monitor = monitor_info(&main_window.current_monitor().ok().flatten()?);
+
+    let state = app_handle.state::<Mutex<ResizeState>>();
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((key, cached)) = &state.layout {
+        if *key == monitor.key {
+            return Some((monitor, cached.clone()));
         }
-    } else {
-        file_viewer.emit("set-content", serde_json::json!({
-            "content": content,
-            "filePath": title,
-            "theme": theme
-        })).map_err(|e| e.to_string())?;
     }
 
-    file_viewer.show().map_err(|e| e.to_string())?;
-    Ok(())
+    let saved = load_settings(app_handle)
+        .ok()
+        .and_then(|mut settings| {
+            settings.window_layouts.remove(&monitor.key)
+                .or_else(|| settings.window_layouts.remove(layout::FALLBACK_MONITOR))
+        })
+        .unwrap_or_default();
+    state.layout = Some((monitor.key.clone(), saved.clone()));
+    Some((monitor, saved))
 }
 
-#[command]
-async fn list_history_files(path: String) -> Result<Vec<String>, String> {
-    let app_dir = tauri::api::path::app_data_dir(&tauri::Config::default())
-        .ok_or("Could not get app directory")?;
-    let dir_path = app_dir.join(path);
-    
-    if !dir_path.exists() {
-        return Ok(vec![]);
-    }
-
-    let mut files = Vec::new();
-    list_json_files_recursive(&dir_path, &mut files)?;
-    Ok(files)
-}
-
-fn list_json_files_recursive(dir: &PathBuf, files: &mut Vec<String>) -> Result<(), String> {
-    if dir.is_dir() {
-        let entries: Vec<_> = fs::read_dir(dir)
-            .map_err(|e| e.to_string())?
-            .filter_map(Result::ok)
-            .collect();
-
-        for chunk in entries.chunks(BATCH_SIZE) {
-            let chunk_len = chunk.len();
-            for entry in chunk.iter() {
-                let path = entry.path();
-                
-                if path.is_dir() {
-                    list_json_files_recursive(&path, files)?;
-                } else if path.extension().and_then(|ext| ext.to_str()) == Some("json") {
-                    files.push(path.to_string_lossy().into_owned());
+// Applies `change` to the active monitor's layout right away, and writes it to
+// the settings once the windows have been still for DEBOUNCE_TIME
+fn remember_layout(app_handle: &tauri::AppHandle, change: impl FnOnce(&layout::MonitorInfo, &mut layout::MonitorLayout)) {
+    let Some((monitor, mut saved)) = active_layout(app_handle) else {
+        return;
+    };
+    change(&monitor, &mut saved);
+
+    {
+        let state = app_handle.state::<Mutex<ResizeState>>();
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        state.layout = Some((monitor.key, saved));
+        state.last_resize = Instant::now();
+        if state.is_resizing.swap(true, Ordering::SeqCst) {
+            return;
+        }
+    }
+
+    let app_handle = app_handle.clone();
+    let debounce = Duration::from_millis(DEBOUNCE_TIME);
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(debounce).await;
+            let pending = {
+                let state = app_handle.state::<Mutex<ResizeState>>();
+                let state = state.lock().unwrap_or_else(|e| e.into_inner());
+                if state.last_resize.elapsed() < debounce {
+                    continue;
+                }
+                state.is_resizing.store(false, Ordering::SeqCst);
+                state.layout.clone()
+            };
+            if let Some((key, saved)) = pending {
+                let patch = serde_json::json!({ "windowLayouts": { &key: saved }, "lastMonitor": key });
+                let result = app_data_path(settings::SETTINGS_DIR)
+                    .and_then(|dir| app_handle.state::<settings::SettingsStore>().update(&dir, patch));
+                if let Err(e) = result {
+                    eprintln!("Error saving window layout: {}", e);
                 }
             }
-            
-            if chunk_len == BATCH_SIZE {
-                std::thread::sleep(Duration::from_millis(1));
+            break;
+        }
+    });
+}
+
+// Puts the main window back where it was, on the monitor it was last used on
+fn restore_main_layout(app_handle: &tauri::AppHandle) -> Result<(), AppError> {
+    let main_window = app_handle.get_window("main").ok_or("Main window not found")?;
+    let settings = load_settings(app_handle)?;
+    let monitors: Vec<layout::MonitorInfo> = main_window.available_monitors()
+        .map_err(window_error)?
+        .iter()
+        .map(monitor_info)
+        .collect();
+    let current = main_window.current_monitor().ok().flatten().map(|m| monitor_info(&m));
+
+    let target = layout::restore_target(&monitors, current.as_ref(), &settings.window_layouts, settings.last_monitor.as_deref());
+    if let Some(rect) = target {
+        set_window_rect(&main_window, rect).map_err(window_error)?;
+    }
+    Ok(())
+}
+
+// Records where the main window is, unless it is minimized or maximized
+fn remember_main(app_handle: &tauri::AppHandle, main_window: &tauri::Window) -> tauri::Result<()> {
+    if main_window.is_minimized()? || main_window.is_maximized()? {
+        return Ok(());
+    }
+    let rect = window_rect(main_window)?;
+    remember_layout(app_handle, |monitor, saved| saved.main = Some(monitor.to_logical(rect)));
+    Ok(())
+}
+
+fn visible_viewers(app_handle: &tauri::AppHandle) -> Vec<viewers::ViewerInfo> {
+    app_handle.state::<viewers::ViewerRegistry>()
+        .list()
+        .into_iter()
+        .filter(|viewer| app_handle.get_window(&viewer.label).is_some_and(|w| w.is_visible().unwrap_or(false)))
+        .collect()
+}
+
+// Where the visible docked and tiled viewers belong right now
+fn arrangement(app_handle: &tauri::AppHandle) -> tauri::Result<Vec<(String, layout::Rect)>> {
+    let (Some(main_window), Some((monitor, saved))) = (app_handle.get_window("main"), active_layout(app_handle)) else {
+        return Ok(Vec::new());
+    };
+    let main = window_rect(&main_window)?;
+    Ok(layout::arrange(main, saved.dock_side, monitor.dock_size(&saved), &visible_viewers(app_handle)))
+}
+
+// Moves the visible docked and tiled viewers into place next to the main window
+fn layout_viewers(app_handle: &tauri::AppHandle) -> tauri::Result<()> {
+    for (label, rect) in arrangement(app_handle)? {
+        if let Some(viewer) = app_handle.get_window(&label) {
+            if window_rect(&viewer)? != rect {
+                set_window_rect(&viewer, rect)?;
             }
         }
     }
     Ok(())
 }
 
+fn viewer_layout(app_handle: &tauri::AppHandle) -> viewers::ViewerLayout {
+    viewers::ViewerLayout {
+        viewers: app_handle.state::<viewers::ViewerRegistry>().list(),
+        dock_side: active_layout(app_handle).map(|(_, saved)| saved.dock_side).unwrap_or_default(),
+    }
+}
+
+// Re-lays out the viewers and tells them about their current modes
+fn refresh_viewers(app_handle: &tauri::AppHandle) -> tauri::Result<()> {
+    layout_viewers(app_handle)?;
+    app_handle.emit_all("viewers-changed", viewer_layout(app_handle))
+}
+
+// A viewer moved or resized by the user: floating ones remember where they
+// were left, resizing an attached one changes the dock size, and dragging the
+// docked one brings the main window along
+fn viewer_changed(app_handle: &tauri::AppHandle, window: &tauri::Window, resized: bool) -> tauri::Result<()> {
+    let Some(viewer) = app_handle.state::<viewers::ViewerRegistry>().get(window.label()) else {
+        return Ok(());
+    };
+    let rect = window_rect(window)?;
+    if viewer.mode == viewers::ViewerMode::Floating {
+        remember_layout(app_handle, |monitor, saved| saved.floating = Some(monitor.to_logical(rect)));
+        return Ok(());
+    }
+
+    let arranged = arrangement(app_handle)?;
+    let Some((_, expected)) = arranged.iter().find(|(label, _)| label == window.label()) else {
+        return Ok(());
+    };
+    // Our own layout changes arrive here too and match the arrangement
+    if rect == *expected {
+        return Ok(());
+    }
+    let (Some(main_window), Some((monitor, saved))) = (app_handle.get_window("main"), active_layout(app_handle)) else {
+        return Ok(());
+    };
+
+    if resized {
+        let size = layout::dock_size_from(rect, saved.dock_side, arranged.len());
+        remember_layout(app_handle, |monitor, saved| saved.dock_size = size as f64 / monitor.scale);
+        layout_viewers(app_handle)?;
+    } else if viewer.mode == viewers::ViewerMode::Docked {
+        let main = window_rect(&main_window)?;
+        let (x, y) = layout::main_for_viewer(rect, main.width, main.height, saved.dock_side, monitor.dock_size(&saved));
+        if (x, y) != (main.x, main.y) {
+            main_window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }))?;
+        }
+    }
+    Ok(())
+}
+
+// Viewer side of the chunk stream: acknowledge, ask again, or release a transfer
+#[command]
+async fn ack_chunk(transfer_id: String, index: usize, window: tauri::Window) -> Result<Vec<usize>, AppError> {
+    window.state::<stream::TransferStore>().ack(&transfer_id, index)
+}
+
+#[command]
+async fn resend_chunks(transfer_id: String, indices: Vec<usize>, window: tauri::Window) -> Result<(), AppError> {
+    let transfers = window.state::<stream::TransferStore>();
+    for index in indices {
+        let chunk = transfers.chunk(&transfer_id, index)?;
+        window.emit("stream-chunk", chunk).map_err(window_error)?;
+    }
+    Ok(())
+}
+
+#[command]
+async fn finish_transfer(transfer_id: String, window: tauri::Window) -> Result<(), AppError> {
+    window.state::<stream::TransferStore>().finish(&transfer_id);
+    Ok(())
+}
+
+#[command]
+async fn list_history_files(path: String) -> Result<Vec<String>, AppError> {
+    history::list_files(&app_data_path(&path)?)
+}
+
+#[command]
+async fn list_history_day(path: String) -> Result<history::HistoryDay, AppError> {
+    history::load_day(
+        &app_data_path(&path)?,
+        &app_data_path(history::HISTORY_DIR)?,
+        &app_data_path(history::QUARANTINE_DIR)?,
+    )
+}
+
+#[command]
+async fn history_restore(
+    path: String,
+    root: Option<String>,
+    window: tauri::Window
+) -> Result<history::RestoredSelection, AppError> {
+    let entry = history::load_entry(&app_data_path(&path)?)?;
+    // Restoring compares files on disk with the entry, so it counts as reading the root.
+    // A root that no longer exists just restores with every file missing.
+    let restore_root = root.as_deref().map(PathBuf::from).or_else(|| history::entry_root(&entry));
+    if let Some(restore_root) = &restore_root {
+        if let Err(e @ AppError::OutsideScope { .. }) = check_read(&window, &restore_root.to_string_lossy()) {
+            return Err(e);
+        }
+    }
+    Ok(history::restore(&entry, root.as_deref().map(Path::new)))
+}
+
+// Compares a file stored in a history entry with the same file in another
+// entry, or with its current contents on disk when `against` is omitted
+#[command]
+async fn diff_history_file(
+    path: String,
+    file: String,
+    against: Option<String>,
+    word_level: Option<bool>,
+    context: Option<usize>,
+    window: tauri::Window
+) -> Result<diff::FileDiff, AppError> {
+    let entry = history::load_entry(&app_data_path(&path)?)?;
+    let old = history::snapshot(&entry, &file)?;
+
+    let (new_content, new_label) = match against {
+        Some(other_path) => {
+            let other = history::load_entry(&app_data_path(&other_path)?)?;
+            let snapshot = history::snapshot(&other, &file)?;
+            (snapshot.content.clone(), format!("{} ({})", file, other.timestamp))
+        }
+        None => {
+            let disk_path = check_read(&window, &selection::Selection::parse(&file).path)?;
+            (read_text(&disk_path)?, format!("{} (current)", file))
+        }
+    };
+
+    Ok(diff::diff_text(
+        &old.content,
+        &new_content,
+        &format!("{} ({})", file, entry.timestamp),
+        &new_label,
+        context.unwrap_or(diff::DEFAULT_CONTEXT),
+        word_level.unwrap_or(false),
+    ))
+}
+
+
 fn handle_window_event(event: &WindowEvent, window: &tauri::Window) -> Result<(), Box<dyn std::error::Error>> {
+    let app_handle = window.app_handle();
+    let registry = app_handle.state::<viewers::ViewerRegistry>();
     match event {
         WindowEvent::CloseRequested { api, .. } => {
             if window.label() == "main" {
-                // When main window is closed, close file viewer if it exists
-                let app_handle = window.app_handle();
-                if let Some(file_viewer) = app_handle.get_window("file-viewer") {
-                    file_viewer.close()?;
+                // When main window is closed, close every file viewer
+                for viewer in registry.list() {
+                    if let Some(file_viewer) = app_handle.get_window(&viewer.label) {
+                        file_viewer.close()?;
+                    }
                 }
-            } else if window.label() == "file-viewer" {
-                window.emit_all("file-viewer-closed", ())?;
+            } else if window.label() == viewers::PRIMARY_LABEL {
+                // The shared viewer is only hidden so it opens quickly next time
+                app_handle.state::<stream::TransferStore>().finish_viewer(window.label());
+                window.emit_all("file-viewer-closed", serde_json::json!({ "label": window.label() }))?;
                 window.hide()?;
                 api.prevent_close();
+                refresh_viewers(&app_handle)?;
+            } else if viewers::is_viewer(window.label()) {
+                registry.remove(window.label());
+                window.emit_all("file-viewer-closed", serde_json::json!({ "label": window.label() }))?;
+                refresh_viewers(&app_handle)?;
             }
         }
-        WindowEvent::Focused(focused) => {
-            if *focused {
-                let app_handle = window.app_handle();
-                
-                if window.label() == "main" {
-                    if let Some(file_viewer) = app_handle.get_window("file-viewer") {
+        WindowEvent::Destroyed if viewers::is_viewer(window.label()) => {
+            app_handle.state::<stream::TransferStore>().finish_viewer(window.label());
+        }
+        WindowEvent::Focused(true) => {
+            if window.label() == "main" {
+                // Bring attached viewers up with the main window
+                let attached = registry.list().into_iter()
+                    .filter(|viewer| viewer.mode != viewers::ViewerMode::Floating);
+                for viewer in attached {
+                    if let Some(file_viewer) = app_handle.get_window(&viewer.label) {
                         if file_viewer.is_visible()? {
                             window.set_always_on_top(true)?;
                             file_viewer.set_always_on_top(true)?;
@@ -508,45 +1116,29 @@ fn handle_window_event(event: &WindowEvent, window: &tauri::Window) -> Result<()
                             file_viewer.set_always_on_top(false)?;
                         }
                     }
-                } else if window.label() == "file-viewer" {
-                    if let Some(main_window) = app_handle.get_window("main") {
-                        window.set_always_on_top(true)?;
-                        main_window.set_always_on_top(true)?;
-                        main_window.unminimize()?;
-                        window.set_always_on_top(false)?;
-                        main_window.set_always_on_top(false)?;
-                    }
+                }
+            } else if registry.get(window.label()).is_some_and(|v| v.mode != viewers::ViewerMode::Floating) {
+                if let Some(main_window) = app_handle.get_window("main") {
+                    window.set_always_on_top(true)?;
+                    main_window.set_always_on_top(true)?;
+                    main_window.unminimize()?;
+                    window.set_always_on_top(false)?;
+                    main_window.set_always_on_top(false)?;
                 }
             }
         }
-        WindowEvent::Moved(position) => {
+        WindowEvent::Moved(_) | WindowEvent::Resized(_) => {
             if window.label() == "main" {
-                let app_handle = window.app_handle();
-                if let Some(file_viewer) = app_handle.get_window("file-viewer") {
-                    if file_viewer.is_visible()? {
-                        if let Ok(main_size) = window.outer_size() {
-                            file_viewer.set_position(tauri::Position::Physical(tauri::PhysicalPosition {
-                                x: position.x + main_size.width as i32,
-                                y: position.y,
-                            }))?;
-                        }
-                    }
-                }
-            } else if window.label() == "file-viewer" {
-                let app_handle = window.app_handle();
-                if let Some(main_window) = app_handle.get_window("main") {
-                    if let Ok(file_viewer_pos) = window.outer_position() {
-                        if let Ok(main_size) = main_window.outer_size() {
-                            let new_main_x = file_viewer_pos.x - main_size.width as i32;
-                            main_window.set_position(tauri::Position::Physical(tauri::PhysicalPosition {
-                                x: new_main_x,
-                                y: position.y,
-                            }))?;
-                        }
-                    }
-                }
+                remember_main(&app_handle, window)?;
+                layout_viewers(&app_handle)?;
+            } else if viewers::is_viewer(window.label()) {
+                viewer_changed(&app_handle, window, matches!(event, WindowEvent::Resized(_)))?;
             }
         }
+        WindowEvent::ScaleFactorChanged { .. } => {
+            // Dock sizes are kept in logical pixels, so they change with the scale
+            layout_viewers(&app_handle)?;
+        }
         _ => {}
     }
     Ok(())
@@ -556,13 +1148,29 @@ fn main() {
     tauri::Builder::default()
         .manage(FileWatchState::default())
         .manage(Mutex::new(ResizeState::default()))
-        .manage(MemoryMonitor::default())
+        .manage(memory::MemoryMonitor::new(MEMORY_LIMIT))
+        .manage(scope::Scope::default())
+        .manage(settings::SettingsStore::default())
+        .manage(stream::TransferStore::default())
+        .manage(paging::PageStore::default())
+        .manage(viewers::ViewerRegistry::default())
+        .manage(ClipboardState::default())
+        .manage(ApiState::default())
+        .manage(api::UiBridge::default())
         .on_window_event(|event| {
             if let Err(e) = handle_window_event(event.event(), event.window()) {
                 eprintln!("Error handling window event: {:?}", e);
             }
         })
         .setup(|app| {
+            if let Err(e) = restore_main_layout(&app.handle()) {
+                eprintln!("Error restoring window layout: {}", e);
+            }
+            match load_settings(&app.handle()) {
+                Ok(settings) => sync_api(&app.handle(), &settings),
+                Err(e) => eprintln!("Error loading settings: {}", e),
+            }
+
             // Add a handler for when the main window is destroyed
             let main_window = app.get_window("main").unwrap();
             main_window.on_window_event(move |event| {
@@ -582,9 +1190,39 @@ fn main() {
             stop_watching,
             write_history,
             list_history_files,
+            list_history_day,
             create_file_window,
+            open_viewer,
+            read_page,
+            highlight_text,
+            highlight_html,
+            write_rich_clipboard,
+            list_viewers,
+            set_viewer_mode,
+            ack_chunk,
+            resend_chunks,
+            finish_transfer,
             delete_history_file,
-            get_app_data_dir
+            get_app_data_dir,
+            memory_stats,
+            get_settings,
+            update_settings,
+            api_respond,
+            api_status,
+            save_workspace,
+            list_workspaces,
+            open_workspace,
+            delete_workspace,
+            save_session,
+            restore_session,
+            history_restore,
+            diff_history_file,
+            build_prompt,
+            file_outline,
+            render_tree,
+            git_modified_files,
+            git_recent_commits,
+            git_blame
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
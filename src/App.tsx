@@ -1,6 +1,7 @@
 import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api";
 import { writeText } from "@tauri-apps/api/clipboard";
+import { ask } from "@tauri-apps/api/dialog";
 import { Folder } from "lucide-react";
 import { Theme, ThemeContext } from "./ThemeContext";
 import themes from "./themes";
@@ -10,12 +11,38 @@ import CheckedFilesTab from "./components/CheckedFilesTab";
 import FileSystemWatcher from "./components/FileSystemWatcher";
 import FileTreeItem from "./components/FileTreeItem";
 import { saveToHistory } from "./components/FolderHistory";
-import { emit } from '@tauri-apps/api/event';
+import { emit, listen } from '@tauri-apps/api/event';
 import { getAll } from '@tauri-apps/api/window';
-import { markHistorySuccess } from './components/FolderHistory';
+import { markHistorySuccess, restoreHistoryEntry } from './components/FolderHistory';
 import { TabBar } from "./components/TabBar";
 import type { TabData } from "./components/TabBar";
-import { loadTheme, saveTheme } from "./utils/storage";
+import { loadSettings, saveTheme, updateSettings } from "./utils/storage";
+import type { RedactionPattern, GuardRule, Settings } from "./utils/storage";
+import { listWorkspaces, saveWorkspace, openWorkspace, deleteWorkspace, saveSession, restoreSession, treeDepthFor } from "./utils/workspaces";
+import type { Workspace } from "./utils/workspaces";
+import { apiStatus, respond, respondWithError, resolvePath } from "./utils/api";
+import type { ApiRequest, ApiStatus } from "./utils/api";
+import { errorMessage } from "./utils/errors";
+import { shortcutAction } from "./utils/shortcuts";
+
+const emptyTab = (id: string): TabData => ({
+    id,
+    promptInput: '',
+    addScriptFix: false,
+    checkedFiles: new Set(),
+    expandedFolders: new Set(),
+    files: [],
+    filteredFiles: [],
+    watchedPath: null,
+    searchQuery: '',
+    lastCopiedEntry: null,
+    gitDiff: null,
+    includeCommits: false,
+    includeBlame: false,
+    includeTree: false,
+    outlineFiles: new Set(),
+    workspaceId: null
+});
 
 const App = () => {
     const [activeView, setActiveView] = useState<'files' | 'checked'>('files');
@@ -24,6 +51,14 @@ const App = () => {
     const [showToast, setShowToast] = useState(false);
     const [isSelectingFolder, setIsSelectingFolder] = useState(false);
     const [showToastSuccess, setShowToastSuccess] = useState(false);
+    const [copyMessage, setCopyMessage] = useState("Copied to clipboard!");
+    const [redactionPatterns, setRedactionPatterns] = useState<RedactionPattern[]>([]);
+    const [guardRules, setGuardRules] = useState<GuardRule[]>([]);
+    const [richCopy, setRichCopy] = useState(false);
+    const [tokenBudget, setTokenBudget] = useState<number | null>(null);
+    const [shortcuts, setShortcuts] = useState<Record<string, string>>({});
+    const [apiEnabled, setApiEnabled] = useState(false);
+    const [apiState, setApiState] = useState<ApiStatus | null>(null);
     const promptTextareaRef = useRef<HTMLTextAreaElement>(null);
     const [activeContextMenu, setActiveContextMenu] = useState<string | null>(null);
 
@@ -32,22 +67,43 @@ const App = () => {
     path: string;
     is_directory: boolean;
     children?: FileInfo[];
+    git_status?: 'modified' | 'added' | 'untracked' | 'ignored' | 'conflicted' | null;
     displayPath?: string;
 }
+    // Keeps the default theme from overwriting the saved one before it loads
+    const settingsLoaded = useRef(false);
     useEffect(() => {
-        const initTheme = async () => {
-            const savedTheme = await loadTheme();
-            if (savedTheme) {
-                setTheme(savedTheme);
+        const applySettings = (settings: Settings) => {
+            setTheme(settings.theme);
+            setRedactionPatterns(settings.redactionPatterns);
+            setGuardRules(settings.guardRules);
+            setRichCopy(settings.richCopy);
+            setTokenBudget(settings.tokenBudget);
+            setShortcuts(settings.shortcuts);
+            setApiEnabled(settings.apiEnabled);
+            apiStatus().then(setApiState).catch(err => console.error('Failed to get API status:', err));
+        };
+        const initSettings = async () => {
+            const settings = await loadSettings();
+            if (settings) {
+                applySettings(settings);
             }
+            settingsLoaded.current = true;
+        };
+        initSettings();
+
+        const unlistenSettings = listen<Settings>('settings-changed', (event) => applySettings(event.payload));
+        return () => {
+            unlistenSettings.then(unlisten => unlisten());
         };
-        initTheme();
     }, []);
     useEffect(() => {
         const syncTheme = async () => {
             try {
                 await emit('theme-update', { theme });
-                await saveTheme(theme);
+                if (settingsLoaded.current) {
+                    await saveTheme(theme);
+                }
             } catch (error) {
                 console.error('Error syncing theme:', error);
             }
@@ -57,21 +113,130 @@ const App = () => {
     }, [theme]);
     
     // Tab Management
-    const [tabs, setTabs] = useState<TabData[]>([{
-        id: '1',
-        promptInput: '',
-        addScriptFix: false,
-        checkedFiles: new Set(),
-        expandedFolders: new Set(),
-        files: [],
-        filteredFiles: [],
-        watchedPath: null,
-        searchQuery: '',
-        lastCopiedEntry: null 
-    }]);
+    const [tabs, setTabs] = useState<TabData[]>([emptyTab('1')]);
     const [activeTabId, setActiveTabId] = useState('1');
     
     const activeTab = tabs.find(tab => tab.id === activeTabId)!;
+
+    // Workspaces
+    const [workspaces, setWorkspaces] = useState<Workspace[]>([]);
+    // The session is only saved once the previous one has been restored
+    const sessionRestored = useRef(false);
+
+    const tabFromWorkspace = async (id: string, workspace: Workspace): Promise<TabData> => {
+        const files = await invoke<FileInfo[]>('list_files', {
+            path: workspace.root,
+            depth: treeDepthFor(workspace.root, workspace.expandedFolders)
+        });
+        return {
+            ...emptyTab(id),
+            promptInput: workspace.defaultTemplate,
+            checkedFiles: new Set(workspace.checkedFiles),
+            expandedFolders: new Set(workspace.expandedFolders),
+            outlineFiles: new Set(workspace.outlineFiles),
+            files,
+            filteredFiles: files,
+            watchedPath: workspace.root,
+            searchQuery: workspace.searchQuery,
+            workspaceId: workspace.id
+        };
+    };
+
+    useEffect(() => {
+        const restoreWorkspaces = async () => {
+            try {
+                const { workspaces: open, active } = await restoreSession();
+                const restored = await Promise.all(
+                    open.map((workspace, index) => tabFromWorkspace(String(index + 1), workspace))
+                );
+                if (restored.length) {
+                    const activeRestored = restored.find(tab => tab.workspaceId === active) ?? restored[0];
+                    setTabs(restored);
+                    setActiveTabId(activeRestored.id);
+                    await invoke('watch_directory', { path: activeRestored.watchedPath });
+                }
+            } catch (err) {
+                console.error('Failed to restore workspaces:', err);
+            } finally {
+                sessionRestored.current = true;
+            }
+            listWorkspaces().then(setWorkspaces).catch(err => console.error('Failed to list workspaces:', err));
+        };
+        restoreWorkspaces();
+    }, []);
+
+    const openWorkspaceIds = tabs.map(tab => tab.workspaceId).filter((id): id is string => !!id);
+    useEffect(() => {
+        if (!sessionRestored.current) return;
+        saveSession(openWorkspaceIds, activeTab?.workspaceId ?? null)
+            .catch(err => console.error('Failed to save session:', err));
+    }, [openWorkspaceIds.join(','), activeTab?.workspaceId]);
+
+    const handleSaveWorkspace = async () => {
+        if (!activeTab.watchedPath) return;
+        try {
+            const existing = workspaces.find(w => w.id === activeTab.workspaceId);
+            const saved = await saveWorkspace({
+                id: activeTab.workspaceId ?? '',
+                name: existing?.name ?? '',
+                root: activeTab.watchedPath,
+                checkedFiles: Array.from(activeTab.checkedFiles),
+                expandedFolders: Array.from(activeTab.expandedFolders),
+                outlineFiles: Array.from(activeTab.outlineFiles),
+                searchQuery: activeTab.searchQuery,
+                defaultTemplate: activeTab.promptInput
+            });
+            updateActiveTab({ workspaceId: saved.id });
+            setWorkspaces(await listWorkspaces());
+        } catch (err) {
+            console.error('Failed to save workspace:', err);
+        }
+    };
+
+    // Shows the tab `makeTab` builds for `root` and returns its id. The current
+    // tab is reused when nothing is open in it.
+    const openInTab = async (root: string, makeTab: (tabId: string) => Promise<TabData>) => {
+        const tabId = activeTab.watchedPath
+            ? (Math.max(...tabs.map(t => parseInt(t.id))) + 1).toString()
+            : activeTabId;
+        const tab = await makeTab(tabId);
+
+        if (activeTab.watchedPath) {
+            await invoke('stop_watching');
+        }
+        await invoke('watch_directory', { path: root });
+        setTabs(prev => prev.some(t => t.id === tabId)
+            ? prev.map(t => t.id === tabId ? tab : t)
+            : [...prev, tab]);
+        setActiveTabId(tabId);
+        return tabId;
+    };
+
+    const handleOpenWorkspace = async (id: string) => {
+        const alreadyOpen = tabs.find(tab => tab.workspaceId === id);
+        if (alreadyOpen) {
+            setActiveTabId(alreadyOpen.id);
+            return;
+        }
+        try {
+            const workspace = await openWorkspace(id);
+            await openInTab(workspace.root, tabId => tabFromWorkspace(tabId, workspace));
+        } catch (err) {
+            console.error('Failed to open workspace:', err);
+        }
+    };
+
+    const handleDeleteWorkspace = async () => {
+        const id = activeTab.workspaceId;
+        if (!id) return;
+        try {
+            await deleteWorkspace(id);
+            setTabs(prev => prev.map(tab => tab.workspaceId === id ? { ...tab, workspaceId: null } : tab));
+            setWorkspaces(await listWorkspaces());
+        } catch (err) {
+            console.error('Failed to delete workspace:', err);
+        }
+    };
     
     const updateActiveTab = (updates: Partial<TabData>) => {
         setTabs(prevTabs => prevTabs.map(tab =>
@@ -151,7 +316,8 @@ const App = () => {
                     filteredFiles: fileList,
                     checkedFiles: new Set(),
                     expandedFolders: new Set(),
-                    searchQuery: ''
+                    searchQuery: '',
+                    workspaceId: null
                 });
             }
         } catch (err) {
@@ -163,18 +329,7 @@ const App = () => {
 
     const handleAddTab = () => {
         const newTabId = (Math.max(...tabs.map(t => parseInt(t.id))) + 1).toString();
-        setTabs(prev => [...prev, {
-            id: newTabId,
-            promptInput: '',
-            addScriptFix: false,
-            checkedFiles: new Set(),
-            expandedFolders: new Set(),
-            files: [],
-            filteredFiles: [],
-            watchedPath: null,
-            searchQuery: '',
-            lastCopiedEntry: null // Add this line
-        }]);
+        setTabs(prev => [...prev, emptyTab(newTabId)]);
         setActiveTabId(newTabId);
     };
 
@@ -190,60 +345,274 @@ const App = () => {
         }
     };
 
-    const copyToClipboard = async () => {
+    const checkModifiedFiles = async () => {
+        if (!activeTab.watchedPath) return;
         try {
-            const selectedFilesContent = await Promise.all(
-                Array.from(activeTab.checkedFiles).map(async (filePath) => {
-                    try {
-                        const content = await invoke<string>('read_file', { path: filePath });
-                        const relativePath = activeTab.watchedPath
-                            ? filePath.replace(activeTab.watchedPath, '').replace(/^[/\\]/, '\\')
-                            : filePath;
-                        return `Filename: ${relativePath}\nContents:\n${content}`;
-                    } catch (error) {
-                        console.error(`Error reading file ${filePath}:`, error);
-                        return `Filename: ${filePath}\nError: Could not read file`;
-                    }
-                })
-            );
+            const modified = await invoke<string[]>('git_modified_files', { path: activeTab.watchedPath });
+            updateActiveTab({ checkedFiles: new Set([...activeTab.checkedFiles, ...modified]) });
+        } catch (err) {
+            console.error('Failed to load modified files:', err);
+        }
+    };
 
-            let clipboardText = '';
-            if (activeTab.promptInput.trim()) {
-                clipboardText += `Prompt: ${activeTab.promptInput}\n\n`;
-            }
+    interface BuiltPrompt {
+        text: string;
+        redactions: { path: string; redactions: { kind: string; line: number }[] }[];
+        guard: { path: string; pattern: string; action: 'warn' | 'deny'; confirmed: boolean }[];
+        needsConfirmation: boolean;
+        html: string | null;
+        estimatedTokens: number;
+    }
 
-            clipboardText += selectedFilesContent.join('\n\n');
+    const buildPrompt = (tab: TabData, confirmed: string[]) =>
+        invoke<BuiltPrompt>('build_prompt', {
+            request: {
+                root: tab.watchedPath,
+                prompt: tab.promptInput,
+                files: Array.from(tab.checkedFiles),
+                modes: Object.fromEntries(Array.from(tab.outlineFiles).map(path => [path, 'outline'])),
+                addScriptFix: tab.addScriptFix,
+                gitDiff: tab.gitDiff ? { kind: tab.gitDiff } : null,
+                gitHistory: tab.includeCommits || tab.includeBlame
+                    ? { commitLimit: tab.includeCommits ? 10 : 0, blame: tab.includeBlame }
+                    : null,
+                tree: tab.includeTree ? { markIncluded: true } : null,
+                redaction: { patterns: redactionPatterns },
+                guard: { rules: guardRules, confirmed },
+                richTheme: richCopy ? theme : null
+            }
+        });
 
-            if (activeTab.addScriptFix) {
-                clipboardText += '\nsend full script with fix';
+    // Whether the prompt made it to the clipboard
+    const copyToClipboard = async (tab: TabData = activeTab) => {
+        try {
+            let built = await buildPrompt(tab, []);
+            if (built.needsConfirmation) {
+                const denied = built.guard.filter(f => f.action === 'deny').map(f => f.path);
+                const include = await ask(
+                    `These files may contain secrets:\n\n${denied.join('\n')}\n\nInclude them anyway?`,
+                    { title: 'Sensitive files', type: 'warning' }
+                );
+                if (!include) return false;
+                built = await buildPrompt(tab, denied);
             }
+            const { text: clipboardText, html, redactions } = built;
 
             // Save to history and get the result
             const historyResult = await saveToHistory(
-                activeTab.promptInput,
-                activeTab.checkedFiles,
-                activeTab.addScriptFix
+                tab.promptInput,
+                tab.checkedFiles,
+                tab.addScriptFix,
+                tab.watchedPath
             );
 
-            await writeText(clipboardText);
+            if (html) {
+                await invoke('write_rich_clipboard', { text: clipboardText, html });
+            } else {
+                await writeText(clipboardText);
+            }
+            const redactedCount = redactions.reduce((sum, file) => sum + file.redactions.length, 0);
+            if (redactedCount > 0) {
+                console.warn('Redacted before copying:', redactions);
+            }
+            const warned = built.guard.filter(f => f.action === 'warn');
+            if (warned.length > 0) {
+                console.warn('Sensitive files included:', warned);
+            }
+            const overBudget = tokenBudget !== null && built.estimatedTokens > tokenBudget;
+            const notes = [
+                redactedCount > 0 ? `${redactedCount} secret${redactedCount === 1 ? '' : 's'} redacted` : '',
+                warned.length > 0 ? `${warned.length} sensitive file${warned.length === 1 ? '' : 's'} included` : '',
+                overBudget ? `about ${built.estimatedTokens} tokens, over the budget of ${tokenBudget}` : ''
+            ].filter(Boolean);
+            setCopyMessage(notes.length > 0 ? `Copied to clipboard! ${notes.join(', ')}` : "Copied to clipboard!");
             setShowToast(true);
 
-            // Update the last copied entry for the active tab
-            setTabs(prevTabs => prevTabs.map(tab =>
-                tab.id === activeTabId
+            // Update the last copied entry for the copied tab
+            setTabs(prevTabs => prevTabs.map(t =>
+                t.id === tab.id
                     ? {
-                        ...tab, lastCopiedEntry: {
+                        ...t, lastCopiedEntry: {
                             path: historyResult.path,
                             timestamp: historyResult.timestamp
                         }
                     }
-                    : tab
+                    : t
             ));
+            return true;
         } catch (err) {
             console.error('Failed to copy to clipboard:', err);
+            return false;
+        }
+    };
+
+    // Local API: editors and scripts act on tabs through these, see api.rs
+    const describeTab = (tab: TabData, index: number) => ({
+        tab: index + 1,
+        active: tab.id === activeTabId,
+        root: tab.watchedPath,
+        workspaceId: tab.workspaceId,
+        prompt: tab.promptInput,
+        checkedFiles: Array.from(tab.checkedFiles),
+        outlineFiles: Array.from(tab.outlineFiles)
+    });
+
+    const notifyExternal = (message: string) => {
+        setShowToastSuccess(false);
+        setCopyMessage(message);
+        setShowToast(true);
+    };
+
+    // Reopens a history entry's folder and selection in a tab
+    const handleRestoreHistory = async (entry: string) => {
+        try {
+            const restored = await restoreHistoryEntry(entry);
+            const root = restored.root;
+            if (!root) {
+                notifyExternal('This history entry has no folder to restore');
+                return;
+            }
+            await openInTab(root, async tabId => {
+                const files = await invoke<FileInfo[]>('list_files', { path: root, depth: 1 });
+                return {
+                    ...emptyTab(tabId),
+                    promptInput: restored.prompt,
+                    addScriptFix: restored.addScriptFix,
+                    checkedFiles: new Set(restored.checkedFiles),
+                    files,
+                    filteredFiles: files,
+                    watchedPath: root
+                };
+            });
+            const notes = [
+                restored.missingCount > 0 ? `${restored.missingCount} missing` : '',
+                restored.changedCount > 0 ? `${restored.changedCount} changed since` : ''
+            ].filter(Boolean);
+            notifyExternal(notes.length > 0 ? `Selection restored, ${notes.join(', ')}` : 'Selection restored');
+        } catch (err) {
+            console.error('Failed to restore history entry:', err);
+            notifyExternal(`Could not restore the selection: ${errorMessage(err)}`);
         }
     };
 
+    const handleApiRequest = async ({ method, params }: ApiRequest): Promise<unknown> => {
+        const { tab: position, ...args } = params ?? {};
+        const index = position === undefined ? tabs.findIndex(t => t.id === activeTabId) : position - 1;
+        const tab = tabs[index];
+        const changeTab = (change: (tab: TabData) => Partial<TabData>) =>
+            setTabs(prev => prev.map(t => t.id === tab.id ? { ...t, ...change(t) } : t));
+
+        if (method === 'tabs.list') {
+            return tabs.map(describeTab);
+        }
+        if (method === 'tabs.open') {
+            let tabId: string;
+            if (args.workspace) {
+                const workspace = workspaces.find(w => w.id === args.workspace || w.name === args.workspace);
+                if (!workspace) throw new Error(`No saved workspace named ${args.workspace}`);
+                const alreadyOpen = tabs.find(t => t.workspaceId === workspace.id);
+                if (alreadyOpen) {
+                    setActiveTabId(alreadyOpen.id);
+                    tabId = alreadyOpen.id;
+                } else {
+                    const opened = await openWorkspace(workspace.id);
+                    tabId = await openInTab(opened.root, id => tabFromWorkspace(id, opened));
+                }
+            } else if (args.root) {
+                const root = args.root;
+                tabId = await openInTab(root, async id => {
+                    const files = await invoke<FileInfo[]>('list_files', { path: root, depth: 1 });
+                    return { ...emptyTab(id), watchedPath: root, files, filteredFiles: files };
+                });
+            } else {
+                throw new Error('Give a root folder or a workspace to open');
+            }
+            notifyExternal('Tab opened by an external client');
+            const opened = tabs.findIndex(t => t.id === tabId);
+            return { tab: opened >= 0 ? opened + 1 : tabs.length + 1 };
+        }
+
+        if (!tab) throw new Error(`There is no tab ${position}`);
+        switch (method) {
+            case 'selection.get':
+                return describeTab(tab, index);
+            case 'selection.add':
+            case 'selection.remove': {
+                if (!tab.watchedPath) throw new Error(`No folder is open in tab ${index + 1}`);
+                const files = (args.files ?? []).map(file => resolvePath(tab.watchedPath!, file));
+                const adding = method === 'selection.add';
+                const update = (current: Set<string>, include: boolean) => include
+                    ? new Set([...current, ...files])
+                    : new Set([...current].filter(f => !files.includes(f)));
+                changeTab(t => ({
+                    checkedFiles: update(t.checkedFiles, adding),
+                    outlineFiles: adding && !args.outline ? t.outlineFiles : update(t.outlineFiles, adding)
+                }));
+                notifyExternal(`${files.length} file${files.length === 1 ? '' : 's'} ${adding ? 'added to' : 'removed from'} tab ${index + 1}`);
+                return { tab: index + 1, files };
+            }
+            case 'prompt.set':
+                changeTab(() => ({ promptInput: args.prompt ?? '' }));
+                return { tab: index + 1 };
+            case 'prompt.copy':
+                return { tab: index + 1, copied: await copyToClipboard(tab) };
+        }
+    };
+
+    // Keyboard shortcuts from the settings
+    const shortcutHandler = useRef<(event: KeyboardEvent) => void>(() => {});
+    shortcutHandler.current = (event: KeyboardEvent) => {
+        const action = shortcutAction(shortcuts, event);
+        if (!action) return;
+        event.preventDefault();
+        switch (action) {
+            case 'copy':
+                copyToClipboard();
+                break;
+            case 'newTab':
+                handleAddTab();
+                break;
+            case 'closeTab':
+                if (tabs.length > 1) handleCloseTab(activeTabId);
+                break;
+            case 'openFolder':
+                selectFolder();
+                break;
+        }
+    };
+    useEffect(() => {
+        const onKeyDown = (event: KeyboardEvent) => shortcutHandler.current(event);
+        window.addEventListener('keydown', onKeyDown);
+        return () => window.removeEventListener('keydown', onKeyDown);
+    }, []);
+
+    // Sent by the history panel; also goes through a ref to see current tabs
+    const restoreHandler = useRef(handleRestoreHistory);
+    restoreHandler.current = handleRestoreHistory;
+    useEffect(() => {
+        const unlisten = listen<{ entry: string }>('restore-history', (event) => restoreHandler.current(event.payload.entry));
+        return () => {
+            unlisten.then(unlisten => unlisten());
+        };
+    }, []);
+
+    // The listener is registered once, so it goes through a ref to see current tabs
+    const apiHandler = useRef(handleApiRequest);
+    apiHandler.current = handleApiRequest;
+    useEffect(() => {
+        const unlisten = listen<ApiRequest>('api-request', async (event) => {
+            const { id } = event.payload;
+            try {
+                await respond(id, await apiHandler.current(event.payload));
+            } catch (err) {
+                await respondWithError(id, errorMessage(err instanceof Error ? err.message : err));
+            }
+        });
+        return () => {
+            unlisten.then(unlisten => unlisten());
+        };
+    }, []);
+
     
     return (
         <ThemeContext.Provider value={{ theme, setTheme }}>
@@ -280,6 +649,51 @@ const App = () => {
                     themeColors={themeColors}
                 />
 
+                <div className="flex-none flex items-center gap-2 px-1.5 pt-1.5">
+                    <select
+                        value=""
+                        onChange={(e) => e.target.value && handleOpenWorkspace(e.target.value)}
+                        style={{
+                            backgroundColor: themeColors.inputBg,
+                            borderColor: themeColors.border,
+                            color: themeColors.text
+                        }}
+                        className="flex-1 p-1 border rounded"
+                    >
+                        <option value="">
+                            {workspaces.find(w => w.id === activeTab.workspaceId)?.name ?? 'Open workspace...'}
+                        </option>
+                        {workspaces.map(workspace => (
+                            <option key={workspace.id} value={workspace.id}>{workspace.name}</option>
+                        ))}
+                    </select>
+                    <button
+                        onClick={handleSaveWorkspace}
+                        disabled={!activeTab.watchedPath}
+                        style={{
+                            backgroundColor: themeColors.button,
+                            color: themeColors.buttonText,
+                            opacity: activeTab.watchedPath ? 1 : 0.7
+                        }}
+                        className="px-3 py-1 rounded hover:opacity-90"
+                        title="Save the folder, checked files and prompt as a workspace"
+                    >
+                        Save workspace
+                    </button>
+                    {activeTab.workspaceId && (
+                        <button
+                            onClick={handleDeleteWorkspace}
+                            style={{
+                                backgroundColor: themeColors.button,
+                                color: themeColors.buttonText
+                            }}
+                            className="px-3 py-1 rounded hover:opacity-90"
+                        >
+                            Delete
+                        </button>
+                    )}
+                </div>
+
                 <div className="flex-1 overflow-hidden p-1.5 flex flex-col min-h-0">
                     <div className="space-y-1.5 flex flex-col h-full">
                         {activeTab.files.length > 0 && (
@@ -339,7 +753,7 @@ const App = () => {
                                     )}
                                 </div>
 
-                                <div className="flex-none">
+                                <div className="flex-none flex items-center gap-4">
                                     <label className="flex items-center">
                                         <input
                                             type="checkbox"
@@ -352,6 +766,113 @@ const App = () => {
                                         />
                                         <span>Add 'send full script with fix' to prompt</span>
                                     </label>
+                                    <label className="flex items-center" title="Paste highlighted code into editors and chat apps that accept rich text">
+                                        <input
+                                            type="checkbox"
+                                            checked={richCopy}
+                                            onChange={(e) => {
+                                                setRichCopy(e.target.checked);
+                                                updateSettings({ richCopy: e.target.checked });
+                                            }}
+                                            className="mr-2"
+                                            style={{
+                                                accentColor: themeColors.highlight
+                                            }}
+                                        />
+                                        <span>Copy as rich text</span>
+                                    </label>
+                                    <label
+                                        className="flex items-center"
+                                        title={apiState?.running
+                                            ? `Listening on 127.0.0.1:${apiState.port}; clients send the token from ${apiState.tokenFile}`
+                                            : apiState?.error ?? 'Let editors and scripts add files to tabs and copy prompts through a local server'}
+                                    >
+                                        <input
+                                            type="checkbox"
+                                            checked={apiEnabled}
+                                            onChange={async (e) => {
+                                                setApiEnabled(e.target.checked);
+                                                await updateSettings({ apiEnabled: e.target.checked });
+                                                const status = await apiStatus();
+                                                setApiState(status);
+                                                if (status.error) {
+                                                    notifyExternal(`Editor integrations unavailable: ${status.error}`);
+                                                }
+                                            }}
+                                            className="mr-2"
+                                            style={{
+                                                accentColor: themeColors.highlight
+                                            }}
+                                        />
+                                        <span>Editor integrations</span>
+                                    </label>
+                                </div>
+
+                                <div className="flex-none flex items-center gap-2">
+                                    <select
+                                        value={activeTab.gitDiff ?? ''}
+                                        onChange={(e) => updateActiveTab({ gitDiff: (e.target.value || null) as TabData['gitDiff'] })}
+                                        style={{
+                                            backgroundColor: themeColors.inputBg,
+                                            borderColor: themeColors.border,
+                                            color: themeColors.text
+                                        }}
+                                        className="flex-1 p-1 border rounded"
+                                    >
+                                        <option value="">No git changes</option>
+                                        <option value="unstaged">Include unstaged changes</option>
+                                        <option value="staged">Include staged changes</option>
+                                    </select>
+                                    <button
+                                        onClick={checkModifiedFiles}
+                                        style={{
+                                            backgroundColor: themeColors.button,
+                                            color: themeColors.buttonText
+                                        }}
+                                        className="px-3 py-1 rounded hover:opacity-90"
+                                        title="Check all files with uncommitted changes"
+                                    >
+                                        Check modified
+                                    </button>
+                                </div>
+
+                                <div className="flex-none flex items-center gap-4">
+                                    <label className="flex items-center">
+                                        <input
+                                            type="checkbox"
+                                            checked={activeTab.includeCommits}
+                                            onChange={(e) => updateActiveTab({ includeCommits: e.target.checked })}
+                                            className="mr-2"
+                                            style={{
+                                                accentColor: themeColors.highlight
+                                            }}
+                                        />
+                                        <span>Recent commits</span>
+                                    </label>
+                                    <label className="flex items-center">
+                                        <input
+                                            type="checkbox"
+                                            checked={activeTab.includeBlame}
+                                            onChange={(e) => updateActiveTab({ includeBlame: e.target.checked })}
+                                            className="mr-2"
+                                            style={{
+                                                accentColor: themeColors.highlight
+                                            }}
+                                        />
+                                        <span>Blame</span>
+                                    </label>
+                                    <label className="flex items-center">
+                                        <input
+                                            type="checkbox"
+                                            checked={activeTab.includeTree}
+                                            onChange={(e) => updateActiveTab({ includeTree: e.target.checked })}
+                                            className="mr-2"
+                                            style={{
+                                                accentColor: themeColors.highlight
+                                            }}
+                                        />
+                                        <span>Project tree</span>
+                                    </label>
                                 </div>
 
                                 <div className="flex-none">
@@ -433,11 +954,21 @@ const App = () => {
                                 {activeView === 'checked' ? (
                                     <CheckedFilesTab
                                         checkedFiles={activeTab.checkedFiles}
+                                        outlineFiles={activeTab.outlineFiles}
                                         onRemoveFile={(path) => {
                                             const newChecked = new Set(activeTab.checkedFiles);
                                             newChecked.delete(path);
                                             updateActiveTab({ checkedFiles: newChecked });
                                         }}
+                                        onToggleOutline={(path) => {
+                                            const newOutline = new Set(activeTab.outlineFiles);
+                                            if (newOutline.has(path)) {
+                                                newOutline.delete(path);
+                                            } else {
+                                                newOutline.add(path);
+                                            }
+                                            updateActiveTab({ outlineFiles: newOutline });
+                                        }}
                                         themeColors={themeColors}
                                     />
                                 ) : (
@@ -510,7 +1041,7 @@ const App = () => {
                                         {isSelectingFolder ? 'Selecting...' : 'Select Folder'}
                                     </button>
                                     <button
-                                        onClick={copyToClipboard}
+                                        onClick={() => copyToClipboard()}
                                         disabled={activeTab.checkedFiles.size === 0}
                                         style={{
                                             backgroundColor: themeColors.button,
@@ -530,7 +1061,7 @@ const App = () => {
 
                
             <Toast
-                message={showToastSuccess ? "Marked as success!" : "Copied to clipboard!"}
+                message={showToastSuccess ? "Marked as success!" : copyMessage}
                 type={showToastSuccess ? 'success' : 'copy'}
                 visible={showToast}
                 onHide={() => setShowToast(false)}
@@ -1,5 +1,5 @@
 import React from 'react';
-import { Trash2, CheckCircle, Check } from 'lucide-react';
+import { Trash2, CheckCircle, Check, Columns, RotateCcw } from 'lucide-react';
 import { getAll } from '@tauri-apps/api/window';
 
 interface CalendarListItemProps {
@@ -13,9 +13,10 @@ interface CalendarListItemProps {
     index: number;
     themeColors: any;
     activeEntryTimestamp: string | null;
-    onViewEntry: (entry: any) => Promise<void>;
+    onViewEntry: (entry: any, newWindow?: boolean) => Promise<void>;
     onDeleteEntry: (path: string) => Promise<void>;
     onMarkSuccess: (entry: any) => Promise<void>;
+    onRestoreEntry: (entry: any) => Promise<void>;
     onToggleShowFiles: (index: number) => void;
     isDeletingEntry: string | null;
     selectedDate: Date;
@@ -29,6 +30,7 @@ const CalendarListItem: React.FC<CalendarListItemProps> = ({
     onViewEntry,
     onDeleteEntry,
     onMarkSuccess,
+    onRestoreEntry,
     onToggleShowFiles,
     isDeletingEntry,
     selectedDate
@@ -97,6 +99,28 @@ const CalendarListItem: React.FC<CalendarListItemProps> = ({
                             <Check className="w-4 h-4" />
                         </button>
                     )}
+                    <button
+                        onClick={(e) => {
+                            e.stopPropagation();
+                            onViewEntry(entry, true);
+                        }}
+                        className="opacity-0 group-hover:opacity-100 transition-opacity p-1 hover:bg-opacity-10 hover:bg-white rounded"
+                        style={{ color: themeColors.text }}
+                        title="Open in a new viewer"
+                    >
+                        <Columns className="w-4 h-4" />
+                    </button>
+                    <button
+                        onClick={(e) => {
+                            e.stopPropagation();
+                            onRestoreEntry(entry);
+                        }}
+                        className="opacity-0 group-hover:opacity-100 transition-opacity p-1 hover:bg-opacity-10 hover:bg-white rounded"
+                        style={{ color: themeColors.text }}
+                        title="Restore this selection in a tab"
+                    >
+                        <RotateCcw className="w-4 h-4" />
+                    </button>
                     <button
                         onClick={(e) => {
                             e.stopPropagation();
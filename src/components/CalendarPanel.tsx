@@ -3,7 +3,7 @@ import { X, ChevronLeft, ChevronRight } from 'lucide-react';
 import { invoke } from "@tauri-apps/api/tauri";
 import { getAll } from '@tauri-apps/api/window';
 import { ThemeContext } from '../ThemeContext';
-import { listen } from '@tauri-apps/api/event';
+import { emit, listen } from '@tauri-apps/api/event';
 import CalendarListItem from './CalendarListItem';
 
 interface CalendarPanelProps {
@@ -20,6 +20,11 @@ interface HistoryEntry {
     showAllFiles?: boolean;
 }
 
+interface HistoryDay {
+    entries: HistoryEntry[];
+    quarantined: string[];
+}
+
 const CalendarPanel: React.FC<CalendarPanelProps> = ({ themeColors, isOpen, onClose }) => {
     const { theme } = useContext(ThemeContext);
     const [currentDate, setCurrentDate] = useState<Date>(new Date());
@@ -121,18 +126,17 @@ const CalendarPanel: React.FC<CalendarPanelProps> = ({ themeColors, isOpen, onCl
                 const day = String(selectedDate.getDate()).padStart(2, '0');
                 const path = `prompt-copy/history/${year}/${month}/${day}`;
 
-                const files = await invoke<string[]>('list_history_files', { path });
-                if (!files.length) {
+                // Corrupt entries are quarantined by the backend rather than failing the day
+                const { entries: dayEntries, quarantined } = await invoke<HistoryDay>('list_history_day', { path });
+                if (quarantined.length) {
+                    console.warn('Quarantined corrupt history entries:', quarantined);
+                }
+                if (!dayEntries.length) {
                     setHistoryEntries([]);
                     return;
                 }
 
-                const entries = await Promise.all(
-                    files.map(async (file: string) => {
-                        const content = await invoke<string>('read_file', { path: file });
-                        return { ...JSON.parse(content), showAllFiles: false };
-                    })
-                );
+                const entries = dayEntries.map(entry => ({ ...entry, showAllFiles: false }));
 
                 setHistoryEntries(
                     entries.sort((a, b) =>
@@ -169,8 +173,11 @@ const CalendarPanel: React.FC<CalendarPanelProps> = ({ themeColors, isOpen, onCl
     // Effect for file viewer event handling
     useEffect(() => {
         const setupListeners = async () => {
-            const unsubscribeClose = await listen('file-viewer-closed', () => {
-                setActiveEntryTimestamp(null);
+            // Only the shared viewer tracks the active entry
+            const unsubscribeClose = await listen<{ label?: string }>('file-viewer-closed', (event) => {
+                if (!event.payload?.label || event.payload.label === 'file-viewer') {
+                    setActiveEntryTimestamp(null);
+                }
             });
 
             // Monitor file viewer visibility
@@ -226,21 +233,15 @@ const CalendarPanel: React.FC<CalendarPanelProps> = ({ themeColors, isOpen, onCl
             const day = String(selectedDate.getDate()).padStart(2, '0');
             const path = `prompt-copy/history/${year}/${month}/${day}`;
 
-            const files = await invoke<string[]>('list_history_files', { path });
+            const { entries: dayEntries } = await invoke<HistoryDay>('list_history_day', { path });
 
-            if (!files.length) {
+            if (!dayEntries.length) {
                 setHistoryEntries([]);
                 const updatedDates = new Set(datesWithHistory);
                 updatedDates.delete(`${year}-${month}-${day}`);
                 setDatesWithHistory(updatedDates);
             } else {
-                const entries = await Promise.all(
-                    files.map(async (file: string) => {
-                        const content = await invoke<string>('read_file', { path: file });
-                        const parsed = JSON.parse(content);
-                        return { ...parsed, showAllFiles: false };
-                    })
-                );
+                const entries = dayEntries.map(entry => ({ ...entry, showAllFiles: false }));
 
                 setHistoryEntries(
                     entries.sort((a, b) =>
@@ -255,32 +256,61 @@ const CalendarPanel: React.FC<CalendarPanelProps> = ({ themeColors, isOpen, onCl
         }
     };
 
-    const handleViewEntry = async (entry: HistoryEntry) => {
+    // With `newWindow` the entry opens in its own viewer, tiled beside the others
+    const handleViewEntry = async (entry: HistoryEntry, newWindow = false) => {
         try {
             const windows = await getAll();
             const fileViewer = windows.find(w => w.label === 'file-viewer');
             const entryTimestamp = new Date(entry.timestamp).getTime().toString();
 
-            if (fileViewer && activeEntryTimestamp === entryTimestamp) {
+            if (!newWindow && fileViewer && activeEntryTimestamp === entryTimestamp) {
                 await fileViewer.hide();
                 setActiveEntryTimestamp(null);
                 return;
             }
 
-            const content = JSON.stringify(entry, null, 2);
+            // File contents stay on disk; the viewer pages them in from the entry
+            const year = selectedDate.getFullYear();
+            const month = String(selectedDate.getMonth() + 1).padStart(2, '0');
+            const day = String(selectedDate.getDate()).padStart(2, '0');
+            const entryPath = `${year}/${month}/${day}/${entryTimestamp}.json`;
+            const content = JSON.stringify({
+                ...entry,
+                files: entry.files.map(file => ({
+                    path: file.path,
+                    source: { kind: 'history', entry: entryPath, file: file.path }
+                }))
+            }, null, 2);
             await invoke('create_file_window', {
                 title: new Date(entry.timestamp).toLocaleString(),
                 content: content,
-                theme: theme
+                theme: theme,
+                newWindow
             });
 
-            setActiveEntryTimestamp(entryTimestamp);
+            if (!newWindow) {
+                setActiveEntryTimestamp(entryTimestamp);
+            }
         } catch (error) {
             console.error('Error viewing history entry:', error);
             setActiveEntryTimestamp(null);
         }
     };
 
+    // The main window reopens the folder and selects the files again
+    const handleRestoreEntry = async (entry: HistoryEntry) => {
+        try {
+            const year = selectedDate.getFullYear();
+            const month = String(selectedDate.getMonth() + 1).padStart(2, '0');
+            const day = String(selectedDate.getDate()).padStart(2, '0');
+            const entryTimestamp = new Date(entry.timestamp).getTime();
+            await emit('restore-history', { entry: `${year}/${month}/${day}/${entryTimestamp}.json` });
+            onClose();
+        } catch (error) {
+            console.error('Error restoring history entry:', error);
+        }
+    };
+
     const handleMarkSuccess = async (entry: HistoryEntry) => {
         try {
             const year = selectedDate.getFullYear();
@@ -456,6 +486,7 @@ const CalendarPanel: React.FC<CalendarPanelProps> = ({ themeColors, isOpen, onCl
                                             onViewEntry={handleViewEntry}
                                             onDeleteEntry={handleDeleteEntry}
                                             onMarkSuccess={handleMarkSuccess}
+                                            onRestoreEntry={handleRestoreEntry}
                                             onToggleShowFiles={handleToggleShowFiles}
                                             isDeletingEntry={isDeletingEntry}
                                             selectedDate={selectedDate}
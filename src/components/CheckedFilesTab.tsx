@@ -4,7 +4,9 @@ import { invoke } from "@tauri-apps/api";
 
 interface CheckedFilesTabProps {
     checkedFiles: Set<string>;
+    outlineFiles: Set<string>;
     onRemoveFile: (path: string) => void;
+    onToggleOutline: (path: string) => void;
     themeColors: any;
 }
 
@@ -59,7 +61,9 @@ const ContextMenu: React.FC<ContextMenuProps> = ({ x, y, file, onClose, themeCol
 
 const CheckedFilesTab: React.FC<CheckedFilesTabProps> = ({
     checkedFiles,
+    outlineFiles,
     onRemoveFile,
+    onToggleOutline,
     themeColors
 }) => {
     const [contextMenu, setContextMenu] = useState<{ x: number; y: number; file: { path: string; name: string } } | null>(null);
@@ -107,6 +111,17 @@ const CheckedFilesTab: React.FC<CheckedFilesTabProps> = ({
                             <File className="w-4 h-4 flex-shrink-0" />
                             <span className="truncate">{fileName}</span>
                         </div>
+                        <button
+                            onClick={() => onToggleOutline(filePath)}
+                            className="hover:opacity-80 transition-opacity ml-auto flex-shrink-0 text-xs px-1 rounded"
+                            style={{
+                                backgroundColor: outlineFiles.has(filePath) ? themeColors.highlight : 'transparent',
+                                color: outlineFiles.has(filePath) ? themeColors.buttonText : themeColors.text
+                            }}
+                            title="Send only declarations and signatures"
+                        >
+                            Outline
+                        </button>
                         <button
                             onClick={() => onRemoveFile(filePath)}
                             className="hover:opacity-80 transition-opacity ml-2 flex-shrink-0"
@@ -7,6 +7,7 @@ interface FileInfo {
     path: string;
     is_directory: boolean;
     children?: FileInfo[];
+    git_status?: 'modified' | 'added' | 'untracked' | 'ignored' | 'conflicted' | null;
 }
 
 interface FileSystemWatcherProps {
@@ -33,12 +34,14 @@ const FileSystemWatcher = ({ rootPath, onFileChange }: FileSystemWatcherProps) =
         let unlistenCreated: (() => void) | undefined;
         let unlistenDeleted: (() => void) | undefined;
         let unlistenModified: (() => void) | undefined;
+        let unlistenGitStatus: (() => void) | undefined;
 
         const setupListeners = async () => {
             try {
                 unlistenCreated = await listen('fs-created', () => refreshFiles());
                 unlistenDeleted = await listen('fs-deleted', () => refreshFiles());
                 unlistenModified = await listen('fs-modified', () => refreshFiles());
+                unlistenGitStatus = await listen('git-status-changed', () => refreshFiles());
             } catch (error) {
                 console.error('Failed to setup file system listeners:', error);
             }
@@ -50,6 +53,7 @@ const FileSystemWatcher = ({ rootPath, onFileChange }: FileSystemWatcherProps) =
             unlistenCreated?.();
             unlistenDeleted?.();
             unlistenModified?.();
+            unlistenGitStatus?.();
         };
     }, [rootPath, onFileChange]);
 
@@ -1,15 +1,26 @@
 ﻿import React, { useState, useEffect } from 'react';
-import { Folder, File, ChevronRight, ChevronDown } from "lucide-react";
+import { Folder, File, ChevronRight, ChevronDown, Eye } from "lucide-react";
 import { invoke } from "@tauri-apps/api";
+import { ask, message } from "@tauri-apps/api/dialog";
+import { isAppError, needsAccess } from "../utils/errors";
 
 interface FileInfo {
     name: string;
     path: string;
     is_directory: boolean;
     children?: FileInfo[];
+    git_status?: 'modified' | 'added' | 'untracked' | 'ignored' | 'conflicted' | null;
     displayPath?: string;
 }
 
+const GIT_STATUS_BADGES: Record<string, { label: string; color: string }> = {
+    modified: { label: 'M', color: '#e2c08d' },
+    added: { label: 'A', color: '#81b88b' },
+    untracked: { label: 'U', color: '#73c991' },
+    ignored: { label: 'I', color: '#8c8c8c' },
+    conflicted: { label: '!', color: '#e06c75' }
+};
+
 interface FileTreeItemProps {
     file: FileInfo;
     checkedFiles: Set<string>;
@@ -43,6 +54,27 @@ const ContextMenu: React.FC<ContextMenuProps> = ({ x, y, file, onClose, themeCol
         onClose();
     };
 
+    // Pages the file into the viewer, so even very large files open quickly
+    const handleView = async () => {
+        onClose();
+        const open = () => invoke('open_viewer', { title: file.name, source: { kind: 'file', path: file.path } });
+        try {
+            await open();
+        } catch (error) {
+            if (isAppError(error) && error.code === 'binary') {
+                await message(`${file.name} is not a text file and can't be shown in the viewer.`, { title: 'Binary file' });
+            } else if (needsAccess(error)) {
+                // Picking the folder again grants access to it
+                const reopen = await ask(`${file.name} can't be read. Open its folder again to grant access?`, { title: 'Access denied', type: 'warning' });
+                if (reopen && await invoke('select_folder').catch(() => null)) {
+                    await open().catch(e => console.error('Failed to open file viewer:', e));
+                }
+            } else {
+                console.error('Failed to open file viewer:', error);
+            }
+        }
+    };
+
     useEffect(() => {
         const handleClickOutside = () => onClose();
         window.addEventListener('click', handleClickOutside);
@@ -69,6 +101,16 @@ const ContextMenu: React.FC<ContextMenuProps> = ({ x, y, file, onClose, themeCol
                     <Folder className="w-4 h-4" />
                     Show in Explorer
                 </button>
+                {!file.is_directory && (
+                    <button
+                        className="w-full px-4 py-2 text-left hover:opacity-80 flex items-center gap-2"
+                        onClick={handleView}
+                        style={{ color: themeColors.text }}
+                    >
+                        <Eye className="w-4 h-4" />
+                        View File
+                    </button>
+                )}
             </div>
         </div>
     );
@@ -192,6 +234,15 @@ const FileTreeItem: React.FC<FileTreeItemProps> = ({
                             </span>
                         )}
                     </div>
+                    {file.git_status && (
+                        <span
+                            style={{ color: GIT_STATUS_BADGES[file.git_status].color, fontSize: '10px', fontWeight: 600 }}
+                            className="ml-auto pl-2"
+                            title={`Git: ${file.git_status}`}
+                        >
+                            {GIT_STATUS_BADGES[file.git_status].label}
+                        </span>
+                    )}
                 </div>
                 {!searchQuery && file.is_directory && expandedFolders.has(file.path) && file.children && (
                     <div className="pl-4">
@@ -1,39 +1,100 @@
-﻿import React, { useState, useEffect } from 'react';
+﻿import React, { useState, useEffect, useRef } from 'react';
 import { listen } from '@tauri-apps/api/event';
-import { ChevronDown, FileX, Copy, CheckCircle, GitCompare } from 'lucide-react';
+import { ChevronDown, FileX, Copy, CheckCircle, GitCompare, PanelLeft, PanelRight, PanelBottom, Columns, AppWindow, ClipboardCopy } from 'lucide-react';
 import { appWindow, getAll } from '@tauri-apps/api/window';
 import { emit } from '@tauri-apps/api/event';
 import { writeText } from '@tauri-apps/api/clipboard';
 import themes from '../themes';
 import { Theme } from '../ThemeContext';
-import { readTextFile } from '@tauri-apps/api/fs';
+import { invoke } from '@tauri-apps/api/tauri';
+import PagedContent, { PageSource, Span, HighlightedLine, readAll } from './PagedContent';
+import HistoryDiff from './HistoryDiff';
 
 interface FileContent {
     prompt?: string;
-    files?: Array<{
-        path: string;
-        content: string;
-    }>;
+    files?: ViewerFile[];
     addScriptFix?: boolean;
 }
 
+// Files with a `source` are paged in from the backend instead of sent whole
+interface ViewerFile {
+    path: string;
+    content?: string;
+    source?: PageSource;
+}
+
+interface StreamStart {
+    transferId: string;
+    total: number;
+    totalBytes: number;
+    filePath: string;
+    theme: Theme | null;
+}
+
+interface StreamChunk {
+    transferId: string;
+    index: number;
+    total: number;
+    content: string;
+}
+
+interface StreamTransfer {
+    id: string;
+    filePath: string;
+    theme: Theme | null;
+    chunks: (string | undefined)[];
+    received: number;
+    retries: number;
+    timer?: ReturnType<typeof setTimeout>;
+}
+
+// Mirrors viewers::ViewerMode and layout::DockSide
+type ViewerMode = 'docked' | 'tiled' | 'floating';
+type DockSide = 'left' | 'right' | 'bottom';
+
+interface ViewerInfo {
+    label: string;
+    title: string;
+    mode: ViewerMode;
+}
+
+interface ViewerLayout {
+    viewers: ViewerInfo[];
+    dockSide: DockSide;
+}
+
+// Docking on a side also moves the tiled viewers there
+const VIEWER_MODES: { mode: ViewerMode; side?: DockSide; label: string; Icon: typeof PanelRight }[] = [
+    { mode: 'docked', side: 'left', label: 'Dock left of the main window', Icon: PanelLeft },
+    { mode: 'docked', side: 'right', label: 'Dock right of the main window', Icon: PanelRight },
+    { mode: 'docked', side: 'bottom', label: 'Dock below the main window', Icon: PanelBottom },
+    { mode: 'tiled', label: 'Tile with the other viewers', Icon: Columns },
+    { mode: 'floating', label: 'Float freely', Icon: AppWindow }
+];
+
+// Quiet period before missing chunks are requested again, and how often
+const STREAM_RESEND_DELAY = 1000;
+const MAX_STREAM_RETRIES = 3;
+
 const FileViewer = () => {
     const [content, setContent] = useState<FileContent | string>('');
     const [title, setTitle] = useState('');
     const [currentTheme, setCurrentTheme] = useState<Theme>('solarized');
-    const [selectedFile, setSelectedFile] = useState<{ path: string; content: string } | null>(null);
+    const [selectedFile, setSelectedFile] = useState<ViewerFile | null>(null);
     const [copySuccess, setCopySuccess] = useState(false);
+    const [richCopySuccess, setRichCopySuccess] = useState(false);
+    const [highlighted, setHighlighted] = useState<Span[][] | null>(null);
     const [isComparing, setIsComparing] = useState(false);
-    const [previousContent, setPreviousContent] = useState<string>('');
     const themeColors = themes[currentTheme].colors;
-    const [currentFileContent, setCurrentFileContent] = useState<string>('');
+    const [mode, setMode] = useState<ViewerMode>('docked');
+    const [dockSide, setDockSide] = useState<DockSide>('right');
    
     const handleClose = async () => {
         try {
             const windows = await getAll();
             const calendar = windows.find(w => w.label === 'calendar');
             if (calendar) {
-                await emit('file-viewer-closed', {});
+                await emit('file-viewer-closed', { label: appWindow.label });
             }
             await appWindow.close();
         } catch (error) {
@@ -58,60 +119,127 @@ const FileViewer = () => {
         const unlisten = listen('tauri://close-requested', handleMainClose);
         return () => { unlisten.then(u => u()); };
     }, []);
+    // Follows mode changes, including this viewer being undocked by another.
+    // Placement itself is handled by the backend as windows move.
     useEffect(() => {
-        const checkMainWindowPosition = async () => {
-            try {
-                const windows = await getAll();
-                const mainWindow = windows.find(w => w.label === 'main');
-
-                if (mainWindow) {
-                    const mainPos = await mainWindow.outerPosition();
-                    const mainSize = await mainWindow.outerSize();
-                    const fileViewerSize = await appWindow.outerSize();
-
-                    await appWindow.setPosition({
-                        type: 'Physical',
-                        x: mainPos.x + mainSize.width,
-                        y: mainPos.y
-                    });
-
-                    if (fileViewerSize.height !== mainSize.height) {
-                        await appWindow.setSize({
-                            type: 'Physical',
-                            width: fileViewerSize.width,
-                            height: mainSize.height
-                        });
-                    }
-                }
-            } catch (error) {
-                console.error('Error syncing window position:', error);
+        const applyLayout = (layout: ViewerLayout) => {
+            const own = layout.viewers.find(v => v.label === appWindow.label);
+            if (own) {
+                setMode(own.mode);
             }
+            setDockSide(layout.dockSide);
         };
-
-        const interval = setInterval(checkMainWindowPosition, 100);
-        return () => clearInterval(interval);
+        invoke<ViewerLayout>('list_viewers').then(applyLayout).catch(console.error);
+        const unlisten = listen<ViewerLayout>('viewers-changed', (event) => applyLayout(event.payload));
+        return () => { unlisten.then(u => u()); };
     }, []);
 
+    const handleSetMode = async (next: ViewerMode, side?: DockSide) => {
+        try {
+            await invoke('set_viewer_mode', { label: appWindow.label, mode: next, side });
+        } catch (error) {
+            console.error('Error changing viewer mode:', error);
+        }
+    };
+
+    const applyContent = (content: string, filePath: string, theme?: Theme | null) => {
+        try {
+            const parsedContent = JSON.parse(content);
+            setContent(parsedContent);
+            // Replaces whatever file, possibly paged, was shown before
+            setSelectedFile(parsedContent.files?.[0] ?? null);
+        } catch (e) {
+            setContent(content);
+            setSelectedFile(null);
+        }
+        setTitle(filePath);
+        if (theme) {
+            setCurrentTheme(theme);
+        }
+    };
+
+    // Large content arrives as numbered chunks; see stream.rs
+    const transferRef = useRef<StreamTransfer | null>(null);
+
+    const completeTransfer = async (transfer: StreamTransfer) => {
+        clearTimeout(transfer.timer);
+        transferRef.current = null;
+        applyContent(transfer.chunks.join(''), transfer.filePath, transfer.theme);
+        await invoke('finish_transfer', { transferId: transfer.id });
+    };
+
+    // Asks again for whatever has not arrived once chunks stop coming in
+    const scheduleResend = (transfer: StreamTransfer) => {
+        clearTimeout(transfer.timer);
+        transfer.timer = setTimeout(async () => {
+            if (transferRef.current !== transfer || transfer.retries >= MAX_STREAM_RETRIES) return;
+            transfer.retries += 1;
+            const missing = transfer.chunks
+                .map((chunk, index) => chunk === undefined ? index : -1)
+                .filter(index => index >= 0);
+            try {
+                await invoke('resend_chunks', { transferId: transfer.id, indices: missing });
+            } catch (error) {
+                console.error('Error requesting missing chunks:', error);
+            }
+            scheduleResend(transfer);
+        }, STREAM_RESEND_DELAY);
+    };
+
     useEffect(() => {
         const setupListeners = async () => {
             try {
                 const unlistenContent = await listen('set-content', (event: any) => {
                     const { content, filePath, theme } = event.payload;
-                    try {
-                        const parsedContent = JSON.parse(content);
-                        setContent(parsedContent);
-                        if (parsedContent.files?.length > 0) {
-                            setSelectedFile(parsedContent.files[0]);
-                        }
-                    } catch (e) {
-                        setContent(content);
-                    }
-                    setTitle(filePath);
+                    applyContent(content, filePath, theme);
+                });
+
+                const unlistenSource = await listen<{ source: PageSource; title: string; theme: Theme | null }>('set-source', (event) => {
+                    const { source, title, theme } = event.payload;
+                    setContent('');
+                    setSelectedFile({ path: source.kind === 'file' ? source.path : source.file, source });
+                    setTitle(title);
                     if (theme) {
                         setCurrentTheme(theme);
                     }
                 });
 
+                const unlistenStreamStart = await listen<StreamStart>('stream-start', (event) => {
+                    const { transferId, total, filePath, theme } = event.payload;
+                    if (transferRef.current) {
+                        clearTimeout(transferRef.current.timer);
+                    }
+                    const transfer: StreamTransfer = {
+                        id: transferId,
+                        filePath,
+                        theme,
+                        chunks: new Array(total).fill(undefined),
+                        received: 0,
+                        retries: 0
+                    };
+                    transferRef.current = transfer;
+                    scheduleResend(transfer);
+                });
+
+                const unlistenStreamChunk = await listen<StreamChunk>('stream-chunk', async (event) => {
+                    const { transferId, index, content } = event.payload;
+                    const transfer = transferRef.current;
+                    if (!transfer || transfer.id !== transferId || transfer.chunks[index] !== undefined) return;
+
+                    transfer.chunks[index] = content;
+                    transfer.received += 1;
+                    try {
+                        await invoke('ack_chunk', { transferId, index });
+                    } catch (error) {
+                        console.error('Error acknowledging chunk:', error);
+                    }
+                    if (transfer.received === transfer.chunks.length) {
+                        await completeTransfer(transfer);
+                    } else {
+                        scheduleResend(transfer);
+                    }
+                });
+
                 const unlistenTheme = await listen('theme-update', (event: any) => {
                     const { theme } = event.payload;
                     if (theme) {
@@ -121,6 +249,9 @@ const FileViewer = () => {
 
                 return () => {
                     unlistenContent();
+                    unlistenSource();
+                    unlistenStreamStart();
+                    unlistenStreamChunk();
                     unlistenTheme();
                 };
             } catch (error) {
@@ -131,18 +262,31 @@ const FileViewer = () => {
         setupListeners();
     }, []);
 
+    // Content held by the viewer is highlighted once per file and theme
+    useEffect(() => {
+        setHighlighted(null);
+        if (!selectedFile || selectedFile.source || !selectedFile.content) return;
+        let current = true;
+        invoke<Span[][]>('highlight_text', { path: selectedFile.path, text: selectedFile.content, theme: currentTheme })
+            .then(spans => { if (current) setHighlighted(spans); })
+            .catch(console.error);
+        return () => { current = false; };
+    }, [selectedFile, currentTheme]);
+
+    const contentToCopy = async () => {
+        if (selectedFile?.source) {
+            return readAll(selectedFile.source);
+        } else if (selectedFile) {
+            return selectedFile.content ?? '';
+        } else if (typeof content === 'object') {
+            return JSON.stringify(content, null, 2);
+        }
+        return content as string;
+    };
+
     const handleCopyContent = async () => {
         try {
-            let contentToCopy = '';
-            if (selectedFile) {
-                contentToCopy = selectedFile.content;
-            } else if (typeof content === 'object') {
-                contentToCopy = JSON.stringify(content, null, 2);
-            } else {
-                contentToCopy = content as string;
-            }
-
-            await writeText(contentToCopy);
+            await writeText(await contentToCopy());
             setCopySuccess(true);
             setTimeout(() => setCopySuccess(false), 2000);
         } catch (error) {
@@ -150,67 +294,30 @@ const FileViewer = () => {
         }
     };
 
-    const toggleCompare = async () => {
-        if (!isComparing && selectedFile) {
-            setPreviousContent(selectedFile.content);
-            try {
-                const currentContent = await readTextFile(selectedFile.path);
-                setCurrentFileContent(currentContent);
-            } catch (error) {
-                console.error('Error reading current file:', error);
-            }
+    // Plain text plus a highlighted HTML flavor for apps that accept rich text
+    const handleCopyRich = async () => {
+        if (!selectedFile) return;
+        try {
+            const text = await contentToCopy();
+            const html = await invoke<string>('highlight_html', { path: selectedFile.path, text, theme: currentTheme });
+            await invoke('write_rich_clipboard', { text, html });
+            setRichCopySuccess(true);
+            setTimeout(() => setRichCopySuccess(false), 2000);
+        } catch (error) {
+            console.error('Error copying rich text to clipboard:', error);
         }
-        setIsComparing(!isComparing);
     };
 
-    const computeDiff = (oldStr: string, newStr: string) => {
-        const oldLines = oldStr.split('\n');
-        const newLines = newStr.split('\n');
-        const result: { text: string; type: 'add' | 'remove' | 'same' }[] = [];
-
-        let i = 0, j = 0;
-        while (i < oldLines.length || j < newLines.length) {
-            if (i >= oldLines.length) {
-                result.push({ text: newLines[j], type: 'add' });
-                j++;
-            } else if (j >= newLines.length) {
-                result.push({ text: oldLines[i], type: 'remove' });
-                i++;
-            } else if (oldLines[i] === newLines[j]) {
-                result.push({ text: oldLines[i], type: 'same' });
-                i++;
-                j++;
-            } else {
-                result.push({ text: oldLines[i], type: 'remove' });
-                result.push({ text: newLines[j], type: 'add' });
-                i++;
-                j++;
-            }
-        }
+    // Files from a history entry can be compared with what is on disk now
+    const historySource = selectedFile?.source?.kind === 'history' ? selectedFile.source : null;
 
-        return result;
-    };
+    useEffect(() => {
+        setIsComparing(false);
+    }, [selectedFile]);
 
-    const renderContent = (content: string, diffView = false) => {
+    const renderContent = (content: string) => {
         if (!content) return renderEmptyState();
 
-        if (!diffView) {
-            return (
-                <pre
-                    className="p-4 rounded font-mono text-sm overflow-x-auto whitespace-pre"
-                    style={{
-                        backgroundColor: themeColors.background,
-                        maxWidth: '100%',
-                        tabSize: 4
-                    }}
-                >
-                    {content}
-                </pre>
-            );
-        }
-
-        const diff = computeDiff(previousContent, content);
-
         return (
             <pre
                 className="p-4 rounded font-mono text-sm overflow-x-auto whitespace-pre"
@@ -220,24 +327,9 @@ const FileViewer = () => {
                     tabSize: 4
                 }}
             >
-                {diff.map((line, idx) => (
-                    <div
-                        key={idx}
-                        style={{
-                            backgroundColor: line.type === 'add'
-                                ? 'rgba(40, 167, 69, 0.2)'
-                                : line.type === 'remove'
-                                    ? 'rgba(220, 53, 69, 0.2)'
-                                    : 'transparent',
-                            color: themeColors.text,
-                        }}
-                    >
-                        {line.type === 'add' && '+ '}
-                        {line.type === 'remove' && '- '}
-                        {line.type === 'same' && '  '}
-                        {line.text}
-                    </div>
-                ))}
+                {highlighted
+                    ? highlighted.map((spans, idx) => <div key={idx}>{spans.length > 0 ? <HighlightedLine spans={spans} /> : '\n'}</div>)
+                    : content}
             </pre>
         );
     };
@@ -271,14 +363,32 @@ const FileViewer = () => {
                 className="h-8 shrink-0"
             >
                 <div className="flex items-center justify-between h-full pointer-events-none">
-                    <div className="w-24 flex items-center pl-2" />
+                    <div className="w-32 flex items-center gap-1 pl-2 pointer-events-auto">
+                        {VIEWER_MODES.map(({ mode: option, side, label, Icon }) => {
+                            const active = mode === option && (!side || side === dockSide);
+                            return (
+                                <button
+                                    key={side ?? option}
+                                    onClick={() => handleSetMode(option, side)}
+                                    className="p-0.5 rounded hover:opacity-80 transition-colors"
+                                    style={{
+                                        backgroundColor: active ? themeColors.highlight : 'transparent',
+                                        color: active ? themeColors.buttonText : themeColors.text
+                                    }}
+                                    title={label}
+                                >
+                                    <Icon className="w-3.5 h-3.5" />
+                                </button>
+                            );
+                        })}
+                    </div>
                     <div
                         style={{ color: themeColors.text }}
                         className="flex-1 text-center text-sm font-medium truncate mx-2"
                     >
                         {title}
                     </div>
-                    <div className="w-24 flex items-center justify-end gap-2 pr-2 pointer-events-auto">
+                    <div className="w-32 flex items-center justify-end gap-2 pr-2 pointer-events-auto">
                         <button
                             onClick={handleClose}
                             className="w-3 h-3 rounded-full bg-red-500 hover:bg-red-600 transition-colors focus:outline-none"
@@ -376,35 +486,42 @@ const FileViewer = () => {
                                     )}
                                 </button>
                                 <button
-                                    onClick={toggleCompare}
-                                    className="p-1 rounded hover:opacity-80 transition-colors hidden"
+                                    onClick={handleCopyRich}
+                                    className="p-1 rounded hover:opacity-80 transition-colors"
                                     style={{
-                                        backgroundColor: isComparing ? themeColors.highlight : 'transparent',
-                                        color: isComparing ? themeColors.buttonText : themeColors.text
+                                        backgroundColor: richCopySuccess ? themeColors.highlight : 'transparent',
+                                        color: richCopySuccess ? themeColors.buttonText : themeColors.text
                                     }}
-                                    title="Compare versions"
+                                    title="Copy as rich text"
                                 >
-                                    <GitCompare className="w-4 h-4" />
+                                    {richCopySuccess ? (
+                                        <CheckCircle className="w-4 h-4" />
+                                    ) : (
+                                        <ClipboardCopy className="w-4 h-4" />
+                                    )}
                                 </button>
+                                {historySource && (
+                                    <button
+                                        onClick={() => setIsComparing(!isComparing)}
+                                        className="p-1 rounded hover:opacity-80 transition-colors"
+                                        style={{
+                                            backgroundColor: isComparing ? themeColors.highlight : 'transparent',
+                                            color: isComparing ? themeColors.buttonText : themeColors.text
+                                        }}
+                                        title="Compare with the current file"
+                                    >
+                                        <GitCompare className="w-4 h-4" />
+                                    </button>
+                                )}
                             </div>
 
-                            {isComparing && (
-                                <div className="flex justify-between text-sm font-medium mb-2 border-b" style={{ borderColor: themeColors.border }}>
-                                    <div>Previous Version</div>
-                                    <div>Current Version</div>
-                                </div>
+                            {historySource && isComparing ? (
+                                <HistoryDiff entry={historySource.entry} file={historySource.file} themeColors={themeColors} />
+                            ) : selectedFile.source ? (
+                                <PagedContent source={selectedFile.source} theme={currentTheme} themeColors={themeColors} />
+                            ) : (
+                                renderContent(selectedFile.content ?? '')
                             )}
-                            <div className={`flex transition-all duration-300 ${isComparing ? 'space-x-2' : ''}`}>
-                                <div className={`transition-all duration-300 ${isComparing ? 'w-[49.5%]' : 'w-full'}`}>
-                                    {isComparing ? renderContent(selectedFile.content, true) : renderContent(selectedFile.content, false)}
-                                </div>
-
-                                {isComparing && (
-                                    <div className="w-[49.5%]">
-                                        {renderContent(currentFileContent, true)}
-                                    </div>
-                                )}
-                            </div>
                         </div>
                     ) : typeof content === 'object' && content.files ? (
                         renderEmptyState()
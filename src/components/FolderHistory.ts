@@ -1,17 +1,29 @@
 import { invoke } from "@tauri-apps/api";
+import { isAppError } from "../utils/errors";
 
-export async function saveToHistory(prompt: string, files: Set<string>, addScriptFix: boolean) {
+// `src/main.rs:120-200` or `src/main.rs#name` -> `src/main.rs`
+const stripSelection = (spec: string) => spec.replace(/(:\d+(-\d+)?|#[\w$.:]+)$/, '');
+
+export async function saveToHistory(prompt: string, files: Set<string>, addScriptFix: boolean, root: string | null) {
     try {
         // First gather all file contents
         const filesWithContent = await Promise.all(
             Array.from(files).map(async (filePath) => {
                 try {
-                    const content = await invoke('read_file', { path: filePath });
+                    // Line-range and symbol selections are stored with the whole file
+                    const content = await invoke('read_file', { path: stripSelection(filePath) });
                     return {
                         path: filePath,
                         content
                     };
                 } catch (error) {
+                    // Binary and oversized files are expected in a selection and simply left out
+                    if (isAppError(error) && (error.code === 'binary' || error.code === 'tooLarge')) {
+                        return {
+                            path: filePath,
+                            content: `Skipped: ${error.message}`
+                        };
+                    }
                     console.error(`Error reading file ${filePath}:`, error);
                     return {
                         path: filePath,
@@ -23,6 +35,7 @@ export async function saveToHistory(prompt: string, files: Set<string>, addScrip
 
         const historyData = {
             timestamp: new Date().toISOString(),
+            root,
             prompt,
             files: filesWithContent,
             addScriptFix,
@@ -73,4 +86,22 @@ export async function markHistorySuccess(historyPath: string) {
         console.error('Failed to mark history as success:', error);
         throw error;
     }
-}
\ No newline at end of file
+}
+// Mirrors history::RestoredSelection
+export type RestoreStatus = 'unchanged' | 'changed' | 'missing' | 'unrecorded';
+
+export interface RestoredSelection {
+    root: string | null;
+    prompt: string;
+    addScriptFix: boolean;
+    checkedFiles: string[];
+    files: Array<{ recordedPath: string; path: string; status: RestoreStatus }>;
+    missingCount: number;
+    changedCount: number;
+}
+
+// `entryPath` is relative to the history folder, e.g. `2024/01/02/1704164645000.json`.
+// With `root` the selection is moved onto another checkout of the same project.
+export function restoreHistoryEntry(entryPath: string, root: string | null = null) {
+    return invoke<RestoredSelection>('history_restore', { path: `prompt-copy/history/${entryPath}`, root });
+}
@@ -0,0 +1,115 @@
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/tauri';
+import { errorMessage } from '../utils/errors';
+
+// Mirrors diff::FileDiff and its parts
+type LineKind = 'context' | 'added' | 'removed';
+
+interface DiffSpan {
+    text: string;
+    changed: boolean;
+}
+
+interface DiffLine {
+    kind: LineKind;
+    oldLine: number | null;
+    newLine: number | null;
+    content: string;
+    spans: DiffSpan[] | null;
+}
+
+interface DiffHunk {
+    header: string;
+    lines: DiffLine[];
+}
+
+interface FileDiff {
+    oldLabel: string;
+    newLabel: string;
+    identical: boolean;
+    additions: number;
+    deletions: number;
+    hunks: DiffHunk[];
+}
+
+interface HistoryDiffProps {
+    // Entry path relative to the history folder, as in a history PageSource
+    entry: string;
+    file: string;
+    themeColors: any;
+}
+
+const LINE_BACKGROUND: Record<LineKind, string> = {
+    context: 'transparent',
+    added: 'rgba(40, 167, 69, 0.2)',
+    removed: 'rgba(220, 53, 69, 0.2)'
+};
+
+const SPAN_BACKGROUND: Record<LineKind, string> = {
+    context: 'transparent',
+    added: 'rgba(40, 167, 69, 0.45)',
+    removed: 'rgba(220, 53, 69, 0.45)'
+};
+
+const MARKER: Record<LineKind, string> = { context: ' ', added: '+', removed: '-' };
+
+// A file as stored in a history entry against its current contents on disk
+const HistoryDiff: React.FC<HistoryDiffProps> = ({ entry, file, themeColors }) => {
+    const [diff, setDiff] = useState<FileDiff | null>(null);
+    const [error, setError] = useState<string | null>(null);
+
+    useEffect(() => {
+        let current = true;
+        setDiff(null);
+        setError(null);
+        invoke<FileDiff>('diff_history_file', { path: `prompt-copy/history/${entry}`, file, wordLevel: true })
+            .then(result => { if (current) setDiff(result); })
+            .catch(e => { if (current) setError(errorMessage(e)); });
+        return () => { current = false; };
+    }, [entry, file]);
+
+    if (error) {
+        return <div className="p-4 text-sm opacity-70">{error}</div>;
+    }
+    if (!diff) {
+        return <div className="p-4 text-sm opacity-70">Comparing...</div>;
+    }
+
+    return (
+        <div>
+            <div className="flex justify-between text-sm font-medium mb-2 border-b" style={{ borderColor: themeColors.border }}>
+                <div className="truncate">{diff.oldLabel}</div>
+                <div className="truncate">
+                    {diff.identical ? 'No changes' : `+${diff.additions} -${diff.deletions}`}
+                </div>
+            </div>
+            <pre
+                className="p-4 rounded font-mono text-sm overflow-x-auto whitespace-pre"
+                style={{ backgroundColor: themeColors.background, maxWidth: '100%', tabSize: 4 }}
+            >
+                {diff.hunks.map((hunk, hunkIndex) => (
+                    <div key={hunkIndex}>
+                        <div className="opacity-60">{hunk.header}</div>
+                        {hunk.lines.map((line, lineIndex) => (
+                            <div key={lineIndex} style={{ backgroundColor: LINE_BACKGROUND[line.kind], color: themeColors.text }}>
+                                {MARKER[line.kind]}{' '}
+                                {line.spans
+                                    ? line.spans.map((span, spanIndex) => (
+                                        <span
+                                            key={spanIndex}
+                                            style={{ backgroundColor: span.changed ? SPAN_BACKGROUND[line.kind] : 'transparent' }}
+                                        >
+                                            {span.text}
+                                        </span>
+                                    ))
+                                    : line.content}
+                            </div>
+                        ))}
+                    </div>
+                ))}
+            </pre>
+        </div>
+    );
+};
+
+export default HistoryDiff;
@@ -0,0 +1,211 @@
+import React, { useState, useEffect, useRef } from 'react';
+import { invoke } from '@tauri-apps/api/tauri';
+import { Theme } from '../ThemeContext';
+import { errorMessage } from '../utils/errors';
+
+// Mirrors paging::PageSource
+export type PageSource =
+    | { kind: 'file'; path: string }
+    | { kind: 'history'; entry: string; file: string };
+
+// Mirrors highlight::Span
+export interface Span {
+    text: string;
+    color: string;
+    bold: boolean;
+    italic: boolean;
+}
+
+interface Page {
+    start: number;
+    lines: string[];
+    totalLines: number;
+    totalBytes: number;
+    truncated: number[];
+    // Only present when a theme was passed
+    spans?: Span[][];
+}
+
+const PAGE_LINES = 500;
+const LINE_HEIGHT = 20;
+// Pages kept in memory; the ones furthest from view are dropped first
+const MAX_PAGES = 20;
+// Browsers stop growing elements past roughly this height, so longer
+// content is scrolled proportionally instead
+const MAX_SCROLL_HEIGHT = 10_000_000;
+
+export const readPage = (source: PageSource, start: number, count: number, theme?: Theme) =>
+    invoke<Page>('read_page', { source, start, count, theme: theme ?? null });
+
+export const HighlightedLine: React.FC<{ spans: Span[] }> = ({ spans }) => (
+    <>
+        {spans.map((span, i) => (
+            <span
+                key={i}
+                style={{
+                    color: span.color,
+                    fontWeight: span.bold ? 'bold' : undefined,
+                    fontStyle: span.italic ? 'italic' : undefined
+                }}
+            >
+                {span.text}
+            </span>
+        ))}
+    </>
+);
+
+// The whole content, fetched page by page; only used for copying
+export const readAll = async (source: PageSource) => {
+    const lines: string[] = [];
+    let total = Infinity;
+    while (lines.length < total) {
+        const page = await readPage(source, lines.length, 5000);
+        total = page.totalLines;
+        if (page.lines.length === 0) break;
+        lines.push(...page.lines);
+    }
+    return lines.join('\n');
+};
+
+interface PagedContentProps {
+    source: PageSource;
+    theme: Theme;
+    themeColors: any;
+}
+
+// Renders only the visible lines of a file, loading pages from the backend as they scroll into view
+const PagedContent: React.FC<PagedContentProps> = ({ source, theme, themeColors }) => {
+    const containerRef = useRef<HTMLDivElement>(null);
+    const [view, setView] = useState({ top: 0, height: 0 });
+    const [totalLines, setTotalLines] = useState<number | null>(null);
+    const [pages, setPages] = useState<Map<number, Page>>(new Map());
+    const [error, setError] = useState<string | null>(null);
+    const loading = useRef<Set<number>>(new Set());
+    const sourceKey = JSON.stringify(source);
+    // Pages are highlighted for one theme, so switching themes reloads them
+    const pagesKey = `${sourceKey}:${theme}`;
+    const sourceKeyRef = useRef(pagesKey);
+
+    const total = totalLines ?? 0;
+    const fullHeight = total * LINE_HEIGHT;
+    const scrollHeight = Math.min(fullHeight, MAX_SCROLL_HEIGHT);
+    const scale = scrollHeight > 0 ? fullHeight / scrollHeight : 1;
+    const exactLine = view.top * scale / LINE_HEIGHT;
+    const firstLine = Math.min(Math.floor(exactLine), Math.max(total - 1, 0));
+    const visibleCount = Math.ceil(view.height / LINE_HEIGHT) + 1;
+    const centerPage = Math.floor((firstLine + visibleCount / 2) / PAGE_LINES);
+
+    const loadPage = async (index: number) => {
+        if (loading.current.has(index)) return;
+        loading.current.add(index);
+        const key = sourceKeyRef.current;
+        try {
+            const page = await readPage(source, index * PAGE_LINES, PAGE_LINES, theme);
+            if (sourceKeyRef.current !== key) return;
+            setTotalLines(page.totalLines);
+            setPages(prev => {
+                const next = new Map(prev);
+                next.set(index, page);
+                const byDistance = [...next.keys()].sort((a, b) =>
+                    Math.abs(b - centerPage) - Math.abs(a - centerPage));
+                byDistance.slice(0, Math.max(next.size - MAX_PAGES, 0)).forEach(k => next.delete(k));
+                return next;
+            });
+        } catch (e) {
+            if (sourceKeyRef.current === key) setError(errorMessage(e));
+        } finally {
+            if (sourceKeyRef.current === key) loading.current.delete(index);
+        }
+    };
+
+    useEffect(() => {
+        sourceKeyRef.current = pagesKey;
+        loading.current = new Set();
+        setPages(new Map());
+        setError(null);
+    }, [pagesKey]);
+
+    useEffect(() => {
+        setTotalLines(null);
+        containerRef.current?.scrollTo(0, 0);
+        setView(v => ({ ...v, top: 0 }));
+        loadPage(0);
+    }, [sourceKey]);
+
+    useEffect(() => {
+        const container = containerRef.current;
+        if (!container) return;
+        const observer = new ResizeObserver(() =>
+            setView(v => ({ ...v, height: container.clientHeight })));
+        observer.observe(container);
+        return () => observer.disconnect();
+    }, []);
+
+    // Fetch the pages around the visible lines, half a page either side
+    useEffect(() => {
+        if (totalLines === null) return;
+        const from = Math.max(firstLine - PAGE_LINES / 2, 0);
+        const to = Math.min(firstLine + visibleCount + PAGE_LINES / 2, totalLines);
+        for (let index = Math.floor(from / PAGE_LINES); index * PAGE_LINES < to; index++) {
+            if (!pages.has(index)) loadPage(index);
+        }
+    }, [firstLine, visibleCount, totalLines, pages]);
+
+    const handleScroll = (e: React.UIEvent<HTMLDivElement>) => {
+        const { scrollTop, clientHeight } = e.currentTarget;
+        setView({ top: scrollTop, height: clientHeight });
+    };
+
+    if (error) {
+        return <div className="p-4 text-sm" style={{ color: themeColors.text }}>{error}</div>;
+    }
+
+    const lines = [];
+    for (let i = firstLine; i < Math.min(firstLine + visibleCount, total); i++) {
+        const page = pages.get(Math.floor(i / PAGE_LINES));
+        const offset = i % PAGE_LINES;
+        lines.push(
+            <div key={i} className="flex" style={{ height: LINE_HEIGHT, lineHeight: `${LINE_HEIGHT}px` }}>
+                <span className="select-none text-right pr-4 opacity-50 shrink-0" style={{ width: '5rem' }}>
+                    {i + 1}
+                </span>
+                <span className={page ? '' : 'opacity-30'}>
+                    {page?.spans ? <HighlightedLine spans={page.spans[offset]} /> : page ? page.lines[offset] : '…'}
+                    {page?.truncated.includes(offset) && (
+                        <span className="opacity-50" title="Line shortened for display"> …</span>
+                    )}
+                </span>
+            </div>
+        );
+    }
+
+    return (
+        <div
+            ref={containerRef}
+            onScroll={handleScroll}
+            className="rounded font-mono text-sm overflow-auto"
+            style={{
+                backgroundColor: themeColors.background,
+                color: themeColors.text,
+                height: 'calc(100vh - 12rem)',
+                tabSize: 4
+            }}
+        >
+            <div style={{ height: scrollHeight, position: 'relative', minWidth: 'max-content' }}>
+                <div
+                    className="whitespace-pre"
+                    style={{
+                        position: 'absolute',
+                        top: view.top - (exactLine - firstLine) * LINE_HEIGHT,
+                        left: 0,
+                        right: 0
+                    }}
+                >
+                    {lines}
+                </div>
+            </div>
+        </div>
+    );
+};
+
+export default PagedContent;
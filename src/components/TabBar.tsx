@@ -9,6 +9,7 @@ interface FileInfo {
     path: string;
     is_directory: boolean;
     children?: FileInfo[];
+    git_status?: 'modified' | 'added' | 'untracked' | 'ignored' | 'conflicted' | null;
     displayPath?: string;
 }
 
@@ -26,6 +27,13 @@ interface TabData {
     watchedPath: string | null;
     searchQuery: string;
     lastCopiedEntry: { path: string; timestamp: string } | null;
+    gitDiff: 'staged' | 'unstaged' | null;
+    includeCommits: boolean;
+    includeBlame: boolean;
+    includeTree: boolean;
+    outlineFiles: Set<string>;
+    // Saved workspace this tab was opened from or saved as
+    workspaceId: string | null;
 }
 
 /**
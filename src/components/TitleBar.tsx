@@ -106,8 +106,8 @@ const TitleBar: React.FC<TitleBarProps> = ({ title = "Prompt Copy" }) => {
     const handleClose = async () => {
         try {
             const windows = await getAll();
-            const fileViewer = windows.find(w => w.label === 'file-viewer');
-            if (fileViewer) {
+            const fileViewers = windows.filter(w => w.label.startsWith('file-viewer'));
+            for (const fileViewer of fileViewers) {
                 await fileViewer.close();
             }
         } catch (error) {
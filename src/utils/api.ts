@@ -0,0 +1,41 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+// A call from an editor or script that only the UI can answer, see api.rs
+export interface ApiRequest {
+    id: number;
+    method: 'tabs.list' | 'tabs.open' | 'selection.get' | 'selection.add' | 'selection.remove'
+        | 'prompt.set' | 'prompt.copy';
+    params: ApiParams | null;
+}
+
+export interface ApiParams {
+    // 1-based position in the tab bar; the active tab when left out
+    tab?: number;
+    root?: string;
+    // Workspace id or name
+    workspace?: string;
+    // Absolute, or relative to the tab's folder; `path:10-20` and `path#symbol` work too
+    files?: string[];
+    outline?: boolean;
+    prompt?: string;
+}
+
+export interface ApiStatus {
+    running: boolean;
+    port: number | null;
+    tokenFile: string;
+    error: string | null;
+}
+
+export const apiStatus = () => invoke<ApiStatus>('api_status');
+
+export const respond = (id: number, result: unknown) =>
+    invoke('api_respond', { id, result: result ?? null });
+
+export const respondWithError = (id: number, error: string) =>
+    invoke('api_respond', { id, error });
+
+const isAbsolute = (path: string) => path.startsWith('/') || /^[A-Za-z]:[\\/]/.test(path);
+
+export const resolvePath = (root: string, file: string) =>
+    isAbsolute(file) ? file : `${root.replace(/[\\/]+$/, '')}/${file.replace(/^\.[\\/]/, '')}`;
@@ -0,0 +1,17 @@
+// Mirrors error::AppError, which every command fails with
+export interface AppError {
+    code: 'notFound' | 'permissionDenied' | 'outsideScope' | 'outsideHistory' | 'invalidPath'
+        | 'tooLarge' | 'binary' | 'memoryLimit' | 'io' | 'other';
+    message: string;
+    path: string | null;
+    context: string | null;
+}
+
+export const isAppError = (error: unknown): error is AppError =>
+    typeof error === 'object' && error !== null && 'code' in error && 'message' in error;
+
+export const errorMessage = (error: unknown) => isAppError(error) ? error.message : String(error);
+
+// Failures that opening the containing folder again can fix
+export const needsAccess = (error: unknown) =>
+    isAppError(error) && (error.code === 'outsideScope' || error.code === 'permissionDenied');
@@ -0,0 +1,51 @@
+// Actions that settings.shortcuts can bind, e.g. { copy: "CmdOrCtrl+Enter" }
+export type ShortcutAction = 'copy' | 'newTab' | 'closeTab' | 'openFolder';
+
+const isMac = navigator.platform.toUpperCase().includes('MAC');
+
+// Whether a keydown matches an accelerator in Tauri's notation, such as
+// "CmdOrCtrl+Shift+C"; modifiers that aren't named must not be held
+export const matchesShortcut = (accelerator: string, event: KeyboardEvent) => {
+    const parts = accelerator.split('+').map(part => part.trim().toLowerCase());
+    const key = parts.pop();
+    if (!key) return false;
+
+    let ctrl = false, meta = false, shift = false, alt = false;
+    for (const modifier of parts) {
+        switch (modifier) {
+            case 'cmdorctrl':
+            case 'commandorcontrol':
+                if (isMac) meta = true; else ctrl = true;
+                break;
+            case 'ctrl':
+            case 'control':
+                ctrl = true;
+                break;
+            case 'cmd':
+            case 'command':
+            case 'meta':
+            case 'super':
+                meta = true;
+                break;
+            case 'shift':
+                shift = true;
+                break;
+            case 'alt':
+            case 'option':
+                alt = true;
+                break;
+            default:
+                return false;
+        }
+    }
+
+    const pressed = event.key === ' ' ? 'space' : event.key.toLowerCase();
+    return pressed === key
+        && event.ctrlKey === ctrl
+        && event.metaKey === meta
+        && event.shiftKey === shift
+        && event.altKey === alt;
+};
+
+export const shortcutAction = (shortcuts: Record<string, string>, event: KeyboardEvent) =>
+    Object.entries(shortcuts).find(([, accelerator]) => matchesShortcut(accelerator, event))?.[0] as ShortcutAction | undefined;
@@ -1,27 +1,77 @@
 import { invoke } from "@tauri-apps/api/tauri";
 import { Theme } from "../ThemeContext";
 
-export const saveTheme = async (theme: Theme) => {
+export interface RedactionPattern {
+    name: string;
+    pattern: string;
+}
+
+export interface GuardRule {
+    pattern: string;
+    action: 'allow' | 'warn' | 'deny';
+}
+
+// Logical pixels relative to the monitor's top-left corner
+export interface WindowLayout {
+    x: number;
+    y: number;
+    width: number;
+    height: number;
+}
+
+// Mirrors layout::MonitorLayout
+export interface MonitorLayout {
+    main: WindowLayout | null;
+    dockSide: 'left' | 'right' | 'bottom';
+    dockSize: number;
+    floating: WindowLayout | null;
+    viewerMode: 'docked' | 'tiled' | 'floating';
+}
+
+// Mirrors settings::Settings; the backend migrates older files on load
+export interface Settings {
+    version: number;
+    theme: Theme;
+    defaultFormat: 'plain' | 'markdown' | 'xml';
+    ignoreRules: string[];
+    tokenBudget: number | null;
+    // Keyed by monitor name
+    windowLayouts: Record<string, MonitorLayout>;
+    lastMonitor: string | null;
+    // ShortcutAction to accelerator, see utils/shortcuts.ts
+    shortcuts: Record<string, string>;
+    redactionPatterns: RedactionPattern[];
+    guardRules: GuardRule[];
+    richCopy: boolean;
+    // Local API for editors and scripts
+    apiEnabled: boolean;
+    apiPort: number;
+}
+
+export const loadSettings = async (): Promise<Settings | null> => {
     try {
-        await invoke('write_history', {
-            path: 'settings/theme.json',
-            content: JSON.stringify({ theme })
-        });
+        return await invoke<Settings>('get_settings');
     } catch (error) {
-        console.error('Error saving theme:', error);
+        console.error('Error loading settings:', error);
+        return null;
     }
 };
 
-export const loadTheme = async (): Promise<Theme | null> => {
+// Every window is notified through the `settings-changed` event
+export const updateSettings = async (patch: Partial<Settings>): Promise<Settings | null> => {
     try {
-        const appDataDir = await invoke<string>('get_app_data_dir');
-        const content = await invoke<string>('read_file', {
-            path: `${appDataDir}/settings/theme.json`
-        });
-        const data = JSON.parse(content);
-        return data.theme;
+        return await invoke<Settings>('update_settings', { patch });
     } catch (error) {
-        // Return null instead of logging error for first-time users
+        console.error('Error saving settings:', error);
         return null;
     }
-};
\ No newline at end of file
+};
+
+export const saveTheme = async (theme: Theme) => {
+    await updateSettings({ theme });
+};
+
+export const loadTheme = async (): Promise<Theme | null> => {
+    const settings = await loadSettings();
+    return settings ? settings.theme : null;
+};
@@ -0,0 +1,44 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+// Mirrors workspace::Workspace
+export interface Workspace {
+    id: string;
+    name: string;
+    root: string;
+    checkedFiles: string[];
+    expandedFolders: string[];
+    outlineFiles: string[];
+    searchQuery: string;
+    defaultTemplate: string;
+    updatedAt: number;
+}
+
+export interface RestoredSession {
+    workspaces: Workspace[];
+    active: string | null;
+}
+
+export const listWorkspaces = () => invoke<Workspace[]>('list_workspaces');
+
+// Leave `id` empty to create a new workspace
+export const saveWorkspace = (workspace: Omit<Workspace, 'updatedAt'>) =>
+    invoke<Workspace>('save_workspace', { workspace });
+
+// Also grants read access to the workspace root for this session
+export const openWorkspace = (id: string) => invoke<Workspace>('open_workspace', { id });
+
+export const deleteWorkspace = (id: string) => invoke('delete_workspace', { id });
+
+export const saveSession = (open: string[], active: string | null) =>
+    invoke('save_session', { session: { open, active } });
+
+export const restoreSession = () => invoke<RestoredSession>('restore_session');
+
+// Levels of the file tree to load so every expanded folder has its children
+export const treeDepthFor = (root: string, expandedFolders: string[]) => {
+    const rootDepth = root.split(/[/\\]/).filter(Boolean).length;
+    return expandedFolders.reduce((depth, folder) => {
+        const folderDepth = folder.split(/[/\\]/).filter(Boolean).length - rootDepth;
+        return Math.max(depth, folderDepth + 1);
+    }, 1);
+};